@@ -56,6 +56,8 @@ messages = Messages
 media = Media
 media-controls = Media Controls
 notifications = Notifications
+reply = Reply
+reply-placeholder = Write a reply...
 device-must-be-connected = Device must be connected to use actions
 send-to = Send to { $device }
 send-to-title = Send to { $device }
@@ -83,7 +85,7 @@ settings-battery-desc = Display battery level next to connected devices
 settings-offline = Show offline devices
 settings-offline-desc = Display paired devices that are not currently connected
 settings-notifications = Show notifications
-settings-notifications-desc = Display notifications from connected devices
+settings-notifications-desc = List phone notifications and show them on the desktop
 
 # SMS Notifications settings
 settings-sms-notifications = SMS notifications
@@ -105,6 +107,7 @@ settings-call-show-name-desc = Display contact name if available
 sms-notification-title = New SMS
 sms-notification-title-from = New SMS from { $sender }
 sms-notification-body-hidden = Message received
+open-conversation = Open conversation
 
 # Call Notification text
 incoming-call = Incoming Call
@@ -116,6 +119,48 @@ missed-call-from = Missed call from { $name }
 settings-file-notifications = File notifications
 settings-file-notifications-desc = Show notification when files are received
 
+# Media player bridge settings
+settings-media-bridge = Desktop media controls
+settings-media-bridge-desc = Control music playing on the phone from the desktop's media controls, media keys and playerctl
+mpris-identity = { $device } (phone)
+
+# Notification rules settings
+settings-notification-rules = Notification rules
+settings-notification-rules-desc = Hide phone notifications by app, title or whether they were silent. The first matching rule decides.
+notification-rule-device = Device
+notification-rule-app = App name
+notification-rule-title = Title pattern (regular expression)
+notification-rule-silent = Only silent notifications
+notification-rule-allow = Show instead of hiding
+notification-rule-add = Add rule
+notification-rule-hide = Hide
+notification-rule-show = Show
+notification-rule-any-app = any app
+notification-rule-title-matches = title matches { $pattern }
+notification-rule-silent-only = silent only
+notification-rule-empty = Give the rule an app name, a title pattern or the silent condition
+notification-rule-no-device = Pick a device for the rule
+mute-app = Hide notifications from this app
+app-muted = Notifications from { $app } are hidden
+
+# Notification history
+notification-history = Notification history
+search-notifications = Search notifications
+clear-history = Clear history
+history-cleared = Notification history cleared
+history-clear-failed = Failed to clear notification history
+no-notification-history = No notifications in the last { $days ->
+    [one] day
+   *[other] { $days } days
+}
+no-matching-notifications = No matching notifications
+settings-history-retention = Notification history
+settings-history-retention-desc = How long phone notifications are kept on this computer
+history-retention-days = { $days ->
+    [one] 1 day
+   *[other] { $days } days
+}
+
 # File Notification text
 file-received = File Received
 file-received-from = File received from { $device }
@@ -136,11 +181,38 @@ load-more-conversations = Load more conversations
 syncing = Syncing with phone...
 syncing-conversations = Syncing conversations...
 hold-to-copy = Hold to copy
+search-messages = Search messages
+no-search-results = No matching messages
+
+# SMS export
+export-all = Export all conversations as
+export-conversation = Export conversation as
+export-format-json = JSON
+export-format-csv = CSV
+export-format-text = Text
+export-me = Me
+sms-exporting = Exporting messages...
+sms-export-done = Exported { $count } messages to { $path }
+sms-export-partial = Exported { $count } messages to { $path }, some may be missing
+sms-export-unreachable = the phone is not reachable
+sms-export-failed = Export failed
+
+# MMS attachments
+attachment-downloading = Downloading attachment...
+attachment-failed = Could not open attachment
+attach-file = Attach files
+paste-image = Paste image
+attachment-filter = Pictures, video, audio and contacts
+attachment-unsupported = { $name } can't be sent by MMS
+attachment-too-large = { $name } would make the message larger than { $limit }
+no-image-in-clipboard = The clipboard holds no image
 
 # New message
 new-message = New Message
 to = To:
 recipient-placeholder = Type a name to search contacts, or enter a phone number
+sim = SIM:
+sim-label = SIM { $number }
 type-message = Type a message...
 send = Send
 sending = Sending...
@@ -154,6 +226,11 @@ enable-mpris = Make sure the MPRIS plugin is enabled
 player = Player:
 volume = Volume
 no-track-playing = No track playing
+media-shuffle-on = Shuffle on
+media-shuffle-off = Shuffle off
+media-loop-none = Repeat off
+media-loop-playlist = Repeat playlist
+media-loop-track = Repeat track
 
 # Share dialog
 share-text-placeholder = Enter text or URL to share...
@@ -172,6 +249,28 @@ clipboard-sent = Clipboard sent!
 clipboard-failed = Failed to send clipboard
 sms-sent = Message sent!
 sms-failed = Failed to send message
+message-pending = Sending
+message-failed = Not sent
+message-draft = Draft
+dismiss-failed = Failed to dismiss notification
+dismiss-on-phone = Dismiss on phone
+reply-sent = Reply sent!
+reply-failed = Failed to send reply
+pairing-failed = Pairing failed
+media-action-failed = Media control failed
+error-daemon-not-running = KDE Connect is not running. Start it and try again.
+error-device-not-found = KDE Connect no longer knows this device. Refresh the device list.
+error-plugin-not-available = The { $plugin } plugin is not enabled. Enable it in KDE Connect on both devices.
+error-device-not-reachable = The device is not reachable. Make sure it is on the same network and KDE Connect is open on it.
+error-device-not-paired = The device is not paired. Pair it and try again.
+error-dbus = Could not talk to KDE Connect: { $details }
+daemon-not-installed = KDE Connect is not installed
+daemon-not-installed-hint = Install the kdeconnect package from your distribution to use this applet.
+daemon-not-running = KDE Connect is not running
+daemon-crashed = KDE Connect stopped unexpectedly
+daemon-start = Start KDE Connect
+daemon-starting = Starting KDE Connect...
+daemon-start-failed = Could not start KDE Connect: { $details }
 group-sms-not-supported = Group messaging not supported
 pairing-accepted = Pairing accepted
 pairing-rejected = Pairing rejected
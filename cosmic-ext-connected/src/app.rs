@@ -2,32 +2,55 @@
 
 use crate::config::Config;
 use crate::constants::{
-    dbus::SIGNAL_REFRESH_DEBOUNCE_SECS, notifications::FILE_TIMEOUT_MS, refresh,
+    dbus::SIGNAL_REFRESH_DEBOUNCE_SECS,
+    notifications::{FILE_TIMEOUT_MS, HISTORY_RETENTION_DAYS},
+    refresh,
+    sms::SEARCH_DEBOUNCE_MS,
 };
+use crate::daemon::{check_daemon_async, daemon_stopped_async, start_daemon_async};
+use crate::device::filter::{NotificationFilters, NotificationRule, RuleAction};
+use crate::device::history::{
+    clear_history_async, load_history_async, record_notification_async, HistoryEntry,
+};
+use crate::device::mirror::{
+    close_desktop_notification_async, show_phone_notification_async, MirroredNotifications,
+    ACTION_DISMISS, ACTION_REPLY,
+};
+use crate::device::state::{apply_event, FollowUp};
 use crate::device::{
-    accept_pairing_async, dismiss_notification_async, fetch_devices_async, find_my_phone_async,
-    reject_pairing_async, request_pair_async, send_clipboard_async, send_ping_async,
+    accept_pairing_async, dismiss_notification_async, fetch_device_async, fetch_devices_async,
+    fetch_notification_async, find_my_phone_async, reject_pairing_async,
+    reply_to_notification_async, request_pair_async, send_clipboard_async, send_ping_async,
     share_file_async, share_text_async, unpair_async,
 };
 use crate::fl;
+use crate::media::album_art::cache_album_art_async;
+use crate::media::mpris::mpris_bridge_subscription;
 use crate::media::{
-    fetch_media_info_async, media_action_async, view_media_controls, MediaAction,
-    MediaControlsParams,
+    apply_media_changes, fetch_media_info_async, media_action_async, view_media_controls,
+    LoopStatus, MediaAction, MediaControlsParams,
 };
+use crate::notifications::should_show_phone_notification;
 use crate::sms::{
-    conversation_list_subscription, fetch_conversations_async, fetch_messages_async,
-    fetch_older_messages_async, send_new_sms_async, send_sms_async, view_conversation_list,
-    view_message_thread, view_new_message, ConversationListParams, MessageThreadParams,
-    NewMessageParams,
+    add_attachments, conversation_list_subscription, default_file_name, download_attachment_async,
+    export_sms_async, fetch_conversations_async, fetch_messages_async, fetch_older_messages_async,
+    open_attachment, open_store_async, pasted_images, prepare_attachments_async,
+    remove_pasted_images, retry_sms_async, save_pasted_image, scroll_to_message, search_async,
+    send_new_sms_async, send_sms_async, sendable_extensions, show_sms_notification_async,
+    sim_default_key, thread_sub_id, view_conversation_list, view_message_thread, view_new_message,
+    ClipboardImage, ConversationListParams, ExportFormat, ExportRequest, ExportSummary,
+    MessageThreadParams, NewMessageParams, OutgoingAttachment, SearchHit, SimCards,
+    SmsNotificationThread, SmsStore, ACTION_DEFAULT,
 };
 use crate::subscriptions::{
-    call_notification_subscription, conversation_message_subscription, dbus_signal_subscription,
-    sms_notification_subscription,
+    conversation_message_subscription, desktop_notification_subscription, event_subscription,
 };
 use crate::ui;
+use crate::views::daemon::view_daemon_status;
 use crate::views::helpers::{
     popup_container, DEFAULT_POPUP_WIDTH, POPUP_MAX_HEIGHT, WIDE_POPUP_WIDTH,
 };
+use crate::views::notification_history::{view_notification_history, NotificationHistoryParams};
 use crate::views::send_to::{view_send_to, SendToParams};
 use crate::views::settings::view_settings;
 use cosmic::app::Core;
@@ -41,15 +64,16 @@ use cosmic::{Application, Element};
 use kdeconnect_dbus::{
     contacts::ContactLookup,
     normalize_phone_number, phone_suffix,
-    plugins::{is_address_valid, ConversationSummary, NotificationInfo, SmsMessage},
+    plugins::{
+        is_address_valid, ConversationSummary, MediaChanges, MessageKey, MessageType,
+        NotificationInfo, SmsMessage,
+    },
+    KdeConnectClient, KdeConnectEvent,
 };
 use lru::LruCache;
 use std::collections::{HashMap, HashSet};
 use std::num::NonZeroUsize;
 use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use zbus::Connection;
 
 /// Messages that drive the applet's state changes.
 #[derive(Debug, Clone)]
@@ -64,9 +88,17 @@ pub enum Message {
     /// Device list was updated
     DevicesUpdated(Vec<DeviceInfo>),
     /// D-Bus connection established
-    DbusConnected(Arc<Mutex<Connection>>),
+    DbusConnected(KdeConnectClient),
     /// D-Bus connection failed
     DbusConnectionFailed(String),
+    /// KDE Connect daemon was found running, missing or stopped
+    DaemonStateChanged(DaemonState),
+    /// KDE Connect daemon released its bus name
+    DaemonStopped,
+    /// Start the KDE Connect daemon
+    StartDaemon,
+    /// Starting the KDE Connect daemon failed
+    DaemonStartFailed(String),
     /// Error occurred
     Error(String),
 
@@ -117,14 +149,48 @@ pub enum Message {
     RejectPairing(String),
     /// Pairing operation completed
     PairingResult(Result<String, String>),
-    /// D-Bus signal received indicating device state changed
+    /// Devices were added or removed, or signals may have been missed
     DbusSignalReceived,
+    /// KDE Connect reported a state change on a device
+    DeviceEvent(KdeConnectEvent),
+    /// A single device was re-read
+    DeviceUpdated(Result<DeviceInfo, String>),
+    /// A posted or updated notification was read
+    NotificationUpdated(String, Result<NotificationInfo, String>), // device_id, notification
 
     // Notification actions
     /// Dismiss a notification on a device
     DismissNotification(String, String), // device_id, notification_id
     /// Notification dismiss result
     DismissResult(Result<String, String>),
+    /// Open (or close) the reply field of a notification on a device
+    ToggleNotificationReply(String, String), // device_id, notification_id
+    /// Update the notification reply text
+    NotificationReplyInput(String),
+    /// Send the notification reply
+    SendNotificationReply,
+    /// Notification reply result (device_id, result)
+    NotificationReplyResult(String, Result<(), String>),
+    /// A phone notification was shown on the desktop (device_id, notification_id, desktop ID)
+    PhoneNotificationShown(String, String, Result<u32, String>),
+    /// An action of a desktop notification was invoked (desktop ID, action key)
+    DesktopNotificationAction(u32, String),
+    /// A desktop notification was closed
+    DesktopNotificationClosed(u32),
+
+    // Notification history
+    /// Open the notification history of a device
+    OpenNotificationHistory(String), // device_id
+    /// Close the notification history and return to the device page
+    CloseNotificationHistory,
+    /// Notification history of a device was read or recorded to
+    NotificationHistoryLoaded(String, Vec<HistoryEntry>), // device_id, entries
+    /// Update the notification history search text
+    NotificationHistorySearch(String),
+    /// Delete the notification history of the open device
+    ClearNotificationHistory,
+    /// Notification history deletion result (device_id, result)
+    NotificationHistoryCleared(String, Result<(), String>),
 
     // Clipboard actions
     /// Send current desktop clipboard to device
@@ -137,6 +203,24 @@ pub enum Message {
     ToggleSettings,
     /// Toggle a specific setting
     ToggleSetting(SettingKey),
+    /// Pick the device of the notification rule being written
+    RuleDraftDevice(usize),
+    /// Update the app name of the notification rule being written
+    RuleDraftAppName(String),
+    /// Update the title pattern of the notification rule being written
+    RuleDraftTitle(String),
+    /// Make the rule being written show instead of hide notifications
+    RuleDraftAllow(bool),
+    /// Make the rule being written only match silent notifications
+    RuleDraftSilentOnly(bool),
+    /// Add the notification rule being written
+    AddNotificationRule,
+    /// Remove a notification rule
+    RemoveNotificationRule(String, usize), // device_id, index
+    /// Hide all notifications of an app on a device
+    MuteNotificationApp(String, String), // device_id, app_name
+    /// Pick how long the notification history is kept (index into the choices)
+    SetHistoryRetention(usize),
 
     // SMS
     /// Open SMS view for a device
@@ -156,8 +240,52 @@ pub enum Message {
     ConversationsLoaded(Vec<ConversationSummary>),
     /// Contacts loaded asynchronously for a device
     ContactsLoaded(String, ContactLookup),
+    /// On-disk SMS store opened for a device
+    SmsStoreLoaded(SmsStore),
     /// User clicked "Load More" button in conversation list
     LoadMoreConversations,
+    /// Update SMS search text input
+    SmsSearchInput(String),
+    /// Typing paused; run the SMS search if the query is still this generation
+    SmsSearch(u64),
+    /// SMS search finished
+    SmsSearchResults {
+        generation: u64,
+        hits: Vec<SearchHit>,
+    },
+    /// Open the thread of a search result, scrolled to the matching message
+    OpenSearchResult { thread_id: i64, uid: Option<i32> },
+    /// Show or hide the SMS export format choices
+    ToggleSmsExportMenu,
+    /// Export the open thread, or all conversations from the conversation list
+    ExportSms(ExportFormat),
+    /// Export destination chosen (None if cancelled)
+    SmsExportPathSelected(Option<PathBuf>),
+    /// SMS export finished
+    SmsExportComplete(Result<ExportSummary, String>),
+    /// Download an MMS attachment from the phone and open it
+    OpenAttachment {
+        part_id: i64,
+        unique_identifier: String,
+    },
+    /// MMS attachment download finished (Ok contains the local file)
+    AttachmentDownloaded(Result<PathBuf, String>),
+    /// Opening a downloaded attachment finished
+    AttachmentOpened(Result<(), String>),
+    /// Pick files to attach to the message being composed
+    PickAttachments,
+    /// Attach an image from the clipboard
+    PasteAttachment,
+    /// Clipboard read for pasting (None if it holds no image)
+    ClipboardImagePasted(Option<ClipboardImage>),
+    /// Files checked for attaching (Err explains why a file can't be sent)
+    AttachmentsPrepared(Vec<Result<OutgoingAttachment, String>>),
+    /// Remove an attachment from the message being composed
+    RemoveAttachment(usize),
+    /// Pick the SIM for the current thread (index into the known SIMs)
+    SelectThreadSim(usize),
+    /// Pick the SIM for the new message (index into the known SIMs)
+    SelectNewMessageSim(usize),
     /// Messages loaded for a thread (thread_id, messages, total_count)
     MessagesLoaded(i64, Vec<SmsMessage>, Option<u64>),
     /// SMS-related error occurred
@@ -166,6 +294,8 @@ pub enum Message {
     SmsComposeInput(String),
     /// Send SMS in current thread
     SendSms,
+    /// Resend a failed message in the current thread (by uid)
+    RetrySms(i32),
     /// SMS send operation completed (Ok contains the sent message body for optimistic update)
     SmsSendResult(Result<String, String>),
     /// Delayed refresh of messages after sending (to give KDE Connect time to sync)
@@ -204,29 +334,53 @@ pub enum Message {
     CloseMediaView,
     /// Media info loaded from device
     MediaInfoLoaded(Option<MediaInfo>),
-    /// Toggle play/pause
-    MediaPlayPause,
+    /// Start playback
+    MediaPlay,
+    /// Pause playback
+    MediaPause,
+    /// Stop playback
+    MediaStop,
     /// Skip to next track
     MediaNext,
     /// Go to previous track
     MediaPrevious,
     /// Set volume
     MediaSetVolume(i32),
+    /// Set the loop mode
+    MediaSetLoopStatus(LoopStatus),
+    /// Turn shuffle on or off
+    MediaSetShuffle(bool),
     /// Select a different player
     MediaSelectPlayer(String),
+    /// Seek slider dragged to a position (milliseconds)
+    MediaSeek(i32),
+    /// Seek slider released: move playback to the dragged position
+    MediaSeekRelease,
+    /// Redraw the playback position while playing
+    MediaPositionTick,
     /// Media control action completed
     MediaActionResult(Result<String, String>),
-    /// Refresh media info (for auto-refresh)
+    /// Fallback poll of the media info, for when player signals are missing
     MediaRefresh,
+    /// Player properties of a device changed
+    MediaPropertiesChanged(String, MediaChanges),
+    /// Album art cached (URL, cached file)
+    MediaAlbumArtLoaded(String, Option<PathBuf>),
 
     // SMS Notifications
     /// New SMS received via D-Bus signal (device_id, message)
     SmsNotificationReceived(String, SmsMessage),
+    /// An SMS notification was shown on the desktop (thread, desktop ID)
+    SmsNotificationShown(SmsNotificationThread, Result<u32, String>),
+    /// A reply was typed into a desktop notification (desktop ID, text)
+    DesktopNotificationReplied(u32, String),
+    /// Result of a reply sent from an SMS notification (thread_id, result)
+    SmsNotificationReplyResult(i64, Result<String, String>),
 
     // Call Notifications
     /// Incoming or missed call received via D-Bus signal
     CallNotification {
-        device_name: String,
+        device_id: String,
         event: String,
         phone_number: String,
         contact_name: String,
@@ -285,6 +439,7 @@ pub enum SettingKey {
     CallShowNumber,
     CallShowName,
     FileNotifications,
+    MediaPlayerBridge,
 }
 
 /// Basic device information for display.
@@ -302,8 +457,49 @@ pub struct DeviceInfo {
     pub notifications: Vec<NotificationInfo>,
 }
 
-/// Information about current media playback.
+/// Reply being written to a phone notification.
 #[derive(Debug, Clone)]
+pub struct NotificationReply {
+    pub device_id: String,
+    pub notification_id: String,
+    pub text: String,
+    /// Whether the reply is being sent
+    pub sending: bool,
+}
+
+/// Notification rule being written in the settings view.
+#[derive(Debug, Clone, Default)]
+pub struct RuleDraft {
+    /// Devices the rule can be added to, in picker order
+    pub device_ids: Vec<String>,
+    /// Names of those devices, for the picker
+    pub device_names: Vec<String>,
+    /// Index of the picked device
+    pub device: Option<usize>,
+    pub app_name: String,
+    pub title_pattern: String,
+    /// Show matching notifications instead of hiding them
+    pub allow: bool,
+    pub silent_only: bool,
+    /// Why the rule could not be added
+    pub error: Option<String>,
+}
+
+/// Health of the KDE Connect daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaemonState {
+    /// Nothing can start `kdeconnectd`
+    NotInstalled,
+    /// Installed but not running
+    NotRunning,
+    /// Running and owning its D-Bus name
+    Running,
+    /// Went away while the applet was watching it, and its systemd unit failed
+    Crashed,
+}
+
+/// Information about current media playback.
+#[derive(Debug, Clone, Default)]
 pub struct MediaInfo {
     /// List of available players on the device.
     pub players: Vec<String>,
@@ -315,18 +511,34 @@ pub struct MediaInfo {
     pub artist: String,
     /// Track album.
     pub album: String,
+    /// URL of the track's album art reported by the daemon.
+    pub album_art_url: String,
+    /// Cached copy of the album art, if it could be read.
+    pub album_art: Option<PathBuf>,
     /// Whether playback is active.
     pub is_playing: bool,
     /// Current volume (0-100).
     pub volume: i32,
-    /// Current position in milliseconds.
+    /// Position in milliseconds when it was read.
     pub position: i64,
+    /// When `position` was read; playback has moved on since if playing.
+    pub position_time: Option<std::time::Instant>,
     /// Track length in milliseconds.
     pub length: i64,
+    /// Whether the player can seek.
+    pub can_seek: bool,
+    /// Can start playback.
+    pub can_play: bool,
+    /// Can pause playback.
+    pub can_pause: bool,
     /// Can go to next track.
     pub can_next: bool,
     /// Can go to previous track.
     pub can_previous: bool,
+    /// Loop mode, if the daemon reports one.
+    pub loop_status: Option<LoopStatus>,
+    /// Whether tracks play in random order, if the daemon reports it.
+    pub shuffle: Option<bool>,
 }
 
 /// View mode for the applet popup.
@@ -349,6 +561,8 @@ pub enum ViewMode {
     Settings,
     /// Media player controls
     MediaControls,
+    /// Notification history of a device
+    NotificationHistory,
 }
 
 /// Loading state for SMS operations with phase tracking.
@@ -383,8 +597,14 @@ pub struct ConnectApplet {
     error: Option<String>,
     /// Status message for user feedback (e.g., "Ping sent", "Pairing failed")
     status_message: Option<String>,
-    /// D-Bus connection (shared for async operations)
-    dbus_connection: Option<Arc<Mutex<Connection>>>,
+    /// KDE Connect client on the shared D-Bus connection (cheap to clone into tasks)
+    client: Option<KdeConnectClient>,
+    /// Health of the KDE Connect daemon (assumed running until checked)
+    daemon_state: DaemonState,
+    /// Whether a daemon start was requested and hasn't finished yet
+    daemon_starting: bool,
+    /// Bumped whenever the daemon comes back, so D-Bus subscriptions re-attach
+    daemon_generation: u32,
     /// Whether we're currently fetching devices
     loading: bool,
     /// Current view mode
@@ -395,6 +615,18 @@ pub struct ConnectApplet {
     pending_share_device: Option<String>,
     /// Text input for sharing
     share_text_input: String,
+    /// Reply being written to a notification, if any
+    notification_reply: Option<NotificationReply>,
+    /// Phone notifications this process shows on the desktop
+    mirrored_notifications: MirroredNotifications,
+    /// Compiled notification rules from the config
+    notification_filters: NotificationFilters,
+    /// Notification rule being written in the settings view
+    rule_draft: RuleDraft,
+    /// Notification history of the selected device, oldest first
+    notification_history: Vec<HistoryEntry>,
+    /// Notification history search text
+    notification_history_search: String,
     /// Timestamp of last D-Bus signal refresh (for debouncing)
     last_signal_refresh: std::time::Instant,
 
@@ -415,8 +647,8 @@ pub struct ConnectApplet {
     conversation_load_active: bool,
     /// Thread ID currently being loaded via subscription (for filtering signals)
     loading_thread_id: Option<i64>,
-    /// Set of message UIDs already displayed (for deduplication during incremental loading)
-    known_message_ids: HashSet<i32>,
+    /// Keys of the messages already displayed (for deduplication during incremental loading)
+    known_message_ids: HashSet<MessageKey>,
     /// Current conversation thread ID being viewed
     current_thread_id: Option<i64>,
     /// Current conversation addresses (all participants, for sending and header)
@@ -437,8 +669,34 @@ pub struct ConnectApplet {
     sms_compose_text: String,
     /// Whether SMS is currently being sent
     sms_sending: bool,
+    /// Failed message being resent, so the compose box is left alone when it succeeds
+    retrying_uid: Option<i32>,
     /// LRU cache of messages by thread_id for faster loading (limited to avoid unbounded growth)
     message_cache: LruCache<i64, Vec<SmsMessage>>,
+    /// On-disk store of the SMS device's conversations and messages
+    sms_store: Option<SmsStore>,
+    /// Text input for searching SMS messages
+    sms_search_query: String,
+    /// Results for the current SMS search
+    sms_search_results: Vec<SearchHit>,
+    /// Bumped on every query change, so stale searches are dropped
+    sms_search_generation: u64,
+    /// UID of the message a search result jumped to (highlighted in the thread)
+    highlighted_message_uid: Option<i32>,
+    /// Whether the SMS export format choices are shown
+    sms_export_menu_open: bool,
+    /// Export waiting for a destination: thread (None for all conversations) and format
+    pending_sms_export: Option<(Option<i64>, ExportFormat)>,
+    /// Progress or outcome of the last SMS export
+    sms_export_status: Option<String>,
+    /// Decoded MMS thumbnails by attachment identifier, kept so they are not decoded on every redraw
+    attachment_thumbnails: HashMap<String, widget::image::Handle>,
+    /// Progress or error of the last attachment download or attach
+    attachment_status: Option<String>,
+    /// Files attached to the SMS being composed (thread or new message)
+    outgoing_attachments: Vec<OutgoingAttachment>,
+    /// SIM subscriptions seen in the SMS device's messages
+    sms_sims: SimCards,
 
     // Message pagination state
     /// Number of messages currently loaded for pagination offset
@@ -459,6 +717,8 @@ pub struct ConnectApplet {
     new_message_recipient_valid: bool,
     /// Whether new message is being sent
     new_message_sending: bool,
+    /// SIM picked for the new message (None for the recipient's default)
+    new_message_sub_id: Option<i64>,
     /// Contact suggestions for new message: (contact_name, phone_number)
     contact_suggestions: Vec<(String, String)>,
 
@@ -473,6 +733,10 @@ pub struct ConnectApplet {
     media_loading: bool,
     /// User's explicit player selection (overrides D-Bus value until view is closed)
     media_selected_player: Option<String>,
+    /// When the player last signalled a change while the media view was open
+    media_last_signal: Option<std::time::Instant>,
+    /// Position the seek slider is being dragged to
+    media_seek_position: Option<i32>,
 
     // SendTo submenu state
     /// Device ID for SendTo view
@@ -483,6 +747,8 @@ pub struct ConnectApplet {
     // SMS notification deduplication
     /// Last seen SMS timestamp per thread_id to avoid duplicate notifications
     last_seen_sms: HashMap<i64, i64>,
+    /// Threads of the SMS notifications this process shows, by desktop ID
+    sms_notification_threads: HashMap<u32, SmsNotificationThread>,
 
     // File notification deduplication
     /// Last received file URL to avoid duplicate notifications
@@ -556,6 +822,183 @@ impl ConnectApplet {
             .map(|(name, phone, _)| (name, phone))
             .collect()
     }
+
+    /// Scroll the message thread to the message a search jumped to, or the newest.
+    fn scroll_thread<T: Send + 'static>(&self) -> cosmic::iced::Task<T> {
+        match self
+            .highlighted_message_uid
+            .filter(|uid| self.messages.iter().any(|m| m.uid == *uid))
+        {
+            Some(uid) => scroll_to_message(uid),
+            None => scrollable::snap_to(
+                widget::Id::new("message-thread"),
+                scrollable::RelativeOffset::END,
+            ),
+        }
+    }
+
+    /// Search SMS for the current query without blocking the UI.
+    fn search_sms(&self) -> cosmic::app::Task<Message> {
+        cosmic::app::Task::perform(
+            search_async(
+                self.sms_search_query.clone(),
+                self.conversations.clone(),
+                self.sms_store.clone(),
+                self.contacts.clone(),
+                self.sms_search_generation,
+            ),
+            cosmic::Action::App,
+        )
+    }
+
+    /// SIM the user last picked for these recipients on the SMS device.
+    fn saved_sim(&self, addresses: &[String]) -> Option<i64> {
+        let device_id = self.sms_device_id.as_deref()?;
+        self.config
+            .sms_sim_defaults
+            .get(&sim_default_key(device_id, addresses))
+            .copied()
+    }
+
+    /// Remember the SIM picked for these recipients.
+    fn remember_sim(&mut self, addresses: &[String], sub_id: i64) {
+        let Some(device_id) = self.sms_device_id.as_deref() else {
+            return;
+        };
+        let key = sim_default_key(device_id, addresses);
+        if self.config.sms_sim_defaults.insert(key, sub_id) != Some(sub_id) {
+            if let Err(err) = self.config.save() {
+                tracing::error!(?err, "Failed to save config");
+            }
+        }
+    }
+
+    /// Recompile the notification rules after an edit and save them.
+    fn save_notification_rules(&mut self) {
+        self.notification_filters = NotificationFilters::new(&self.config.notification_rules);
+        if let Err(err) = self.config.save() {
+            tracing::error!(?err, "Failed to save config");
+        }
+    }
+
+    /// Offer the paired devices, and any others that have rules, in the rule editor.
+    fn refresh_rule_devices(&mut self) {
+        let picked = self
+            .rule_draft
+            .device
+            .and_then(|i| self.rule_draft.device_ids.get(i))
+            .cloned();
+        let mut devices: Vec<(String, String)> = self
+            .devices
+            .iter()
+            .filter(|d| d.is_paired)
+            .map(|d| (d.id.clone(), d.name.clone()))
+            .collect();
+        for device_id in self.config.notification_rules.keys() {
+            if !devices.iter().any(|(id, _)| id == device_id) {
+                devices.push((device_id.clone(), device_id.clone()));
+            }
+        }
+        let draft = &mut self.rule_draft;
+        (draft.device_ids, draft.device_names) = devices.into_iter().unzip();
+        draft.device = picked
+            .and_then(|id| draft.device_ids.iter().position(|d| *d == id))
+            .or((!draft.device_ids.is_empty()).then_some(0));
+    }
+
+    /// Run a media action on the player shown in the media controls.
+    fn media_action(&self, action: MediaAction) -> cosmic::app::Task<Message> {
+        let (Some(client), Some(device_id)) = (&self.client, &self.media_device_id) else {
+            return cosmic::app::Task::none();
+        };
+        cosmic::app::Task::perform(
+            media_action_async(
+                client.clone(),
+                device_id.clone(),
+                action,
+                self.media_selected_player.clone(),
+            ),
+            cosmic::Action::App,
+        )
+    }
+
+    /// Show the thread of an SMS notification, opening the popup if needed.
+    ///
+    /// With `compose`, the compose field is focused for a reply.
+    fn open_notified_thread(
+        &mut self,
+        thread: SmsNotificationThread,
+        compose: bool,
+    ) -> cosmic::app::Task<Message> {
+        self.selected_device = Some(thread.device_id.clone());
+        let mut tasks = vec![
+            self.update(Message::OpenSmsView(thread.device_id)),
+            self.update(Message::OpenConversation(thread.thread_id)),
+        ];
+        // The conversation list may still be loading
+        if self.current_thread_id == Some(thread.thread_id)
+            && self.current_thread_addresses.is_none()
+        {
+            self.current_thread_addresses = Some(thread.addresses);
+        }
+        if compose {
+            tasks.push(widget::text_input::focus(widget::Id::new("sms-compose")));
+        }
+        if self.popup.is_none() {
+            tasks.push(self.update(Message::TogglePopup));
+        }
+        cosmic::app::Task::batch(tasks)
+    }
+
+    /// SIM to send the new message with: the one picked, or the recipient's default.
+    fn new_message_sim(&self) -> Option<i64> {
+        self.new_message_sub_id.or_else(|| {
+            self.new_message_recipient_valid
+                .then(|| self.saved_sim(&[self.new_message_recipient.clone()]))
+                .flatten()
+        })
+    }
+
+    /// Show conversations from a cache while the phone syncs in the background.
+    fn show_cached_conversations(&mut self, convs: Vec<ConversationSummary>) {
+        if !convs.is_empty() {
+            // Pre-populate last_seen_sms to prevent false notifications
+            for conv in &convs {
+                let current = self.last_seen_sms.get(&conv.thread_id).copied();
+                if current.is_none() || current < Some(conv.timestamp) {
+                    self.last_seen_sms.insert(conv.thread_id, conv.timestamp);
+                }
+            }
+
+            self.conversations = convs;
+            self.conversation_list_key = self.conversation_list_key.wrapping_add(1);
+        }
+        // Switch from "Connecting" spinner to showing data + sync indicator
+        // (conversation_sync_active remains true until the sync completes)
+        if matches!(
+            self.sms_loading_state,
+            SmsLoadingState::LoadingConversations(_)
+        ) {
+            self.sms_loading_state = SmsLoadingState::Idle;
+        }
+    }
+}
+
+/// Create image handles for the MMS thumbnails of `messages` not seen before.
+///
+/// A handle made from bytes gets a new id each time, so handles are created
+/// once and reused; otherwise every redraw would decode the images again.
+fn cache_thumbnails<'a>(
+    thumbnails: &mut HashMap<String, widget::image::Handle>,
+    messages: impl IntoIterator<Item = &'a SmsMessage>,
+) {
+    for attachment in messages.into_iter().flat_map(|m| &m.attachments) {
+        if attachment.is_image() && !attachment.thumbnail.is_empty() {
+            thumbnails
+                .entry(attachment.unique_identifier.clone())
+                .or_insert_with(|| widget::image::Handle::from_bytes(attachment.thumbnail.clone()));
+        }
+    }
 }
 
 impl Application for ConnectApplet {
@@ -576,6 +1019,7 @@ impl Application for ConnectApplet {
     fn init(core: Core, _flags: Self::Flags) -> (Self, cosmic::app::Task<Self::Message>) {
         // Load config from disk or use defaults
         let config = Config::load();
+        let notification_filters = NotificationFilters::new(&config.notification_rules);
 
         let app = ConnectApplet {
             core,
@@ -584,12 +1028,21 @@ impl Application for ConnectApplet {
             devices: Vec::new(),
             error: None,
             status_message: None,
-            dbus_connection: None,
+            client: None,
+            daemon_state: DaemonState::Running,
+            daemon_starting: false,
+            daemon_generation: 0,
             loading: true,
             view_mode: ViewMode::DeviceList,
             selected_device: None,
             pending_share_device: None,
             share_text_input: String::new(),
+            notification_reply: None,
+            mirrored_notifications: MirroredNotifications::default(),
+            notification_filters,
+            rule_draft: RuleDraft::default(),
+            notification_history: Vec::new(),
+            notification_history_search: String::new(),
             last_signal_refresh: std::time::Instant::now(),
             // SMS state
             sms_device_id: None,
@@ -611,9 +1064,22 @@ impl Application for ConnectApplet {
             conversations_displayed: 10,
             sms_compose_text: String::new(),
             sms_sending: false,
+            retrying_uid: None,
             message_cache: LruCache::new(
                 NonZeroUsize::new(crate::constants::sms::MESSAGE_CACHE_MAX_CONVERSATIONS).unwrap(),
             ),
+            sms_store: None,
+            sms_search_query: String::new(),
+            sms_search_results: Vec::new(),
+            sms_search_generation: 0,
+            highlighted_message_uid: None,
+            sms_export_menu_open: false,
+            pending_sms_export: None,
+            sms_export_status: None,
+            attachment_thumbnails: HashMap::new(),
+            attachment_status: None,
+            outgoing_attachments: Vec::new(),
+            sms_sims: SimCards::default(),
             // Message pagination state
             messages_loaded_count: 0,
             messages_has_more: true,
@@ -624,6 +1090,7 @@ impl Application for ConnectApplet {
             new_message_body: String::new(),
             new_message_recipient_valid: false,
             new_message_sending: false,
+            new_message_sub_id: None,
             contact_suggestions: Vec::new(),
             // Media controls state
             media_device_id: None,
@@ -631,11 +1098,14 @@ impl Application for ConnectApplet {
             media_info: None,
             media_loading: false,
             media_selected_player: None,
+            media_last_signal: None,
+            media_seek_position: None,
             // SendTo state
             sendto_device_id: None,
             sendto_device_type: None,
             // SMS notification deduplication
             last_seen_sms: HashMap::new(),
+            sms_notification_threads: HashMap::new(),
             // File notification deduplication
             last_received_file: None,
             // Long-press copy state
@@ -645,9 +1115,9 @@ impl Application for ConnectApplet {
         };
 
         // Connect to D-Bus on startup
-        let task = cosmic::app::Task::perform(async { Connection::session().await }, |result| {
+        let task = cosmic::app::Task::perform(KdeConnectClient::session(), |result| {
             cosmic::Action::App(match result {
-                Ok(conn) => Message::DbusConnected(Arc::new(Mutex::new(conn))),
+                Ok(client) => Message::DbusConnected(client),
                 Err(e) => Message::DbusConnectionFailed(e.to_string()),
             })
         });
@@ -690,25 +1160,71 @@ impl Application for ConnectApplet {
                     self.popup = None;
                 }
             }
-            Message::DbusConnected(conn) => {
+            Message::DbusConnected(client) => {
                 tracing::info!("D-Bus connection established");
-                self.dbus_connection = Some(conn.clone());
+                self.client = Some(client.clone());
                 self.error = None;
-                // Immediately fetch devices
-                return cosmic::app::Task::perform(fetch_devices_async(conn), cosmic::Action::App);
+                // Check the daemon before fetching devices from it
+                return cosmic::app::Task::perform(check_daemon_async(client), cosmic::Action::App);
             }
             Message::DbusConnectionFailed(err) => {
                 tracing::error!("D-Bus connection failed: {}", err);
                 self.error = Some(format!("Cannot connect to KDE Connect: {}", err));
                 self.loading = false;
             }
+            Message::DaemonStateChanged(state) => {
+                tracing::info!("KDE Connect daemon state: {:?}", state);
+                let was_running = self.daemon_state == DaemonState::Running;
+                self.daemon_state = state;
+                self.daemon_starting = false;
+                if state != DaemonState::Running {
+                    self.devices.clear();
+                    self.loading = false;
+                    return cosmic::app::Task::none();
+                }
+                if !was_running {
+                    // Re-create the subscriptions so they re-send their requests
+                    self.daemon_generation = self.daemon_generation.wrapping_add(1);
+                    self.status_message = None;
+                }
+                if let Some(client) = &self.client {
+                    self.error = None;
+                    self.loading = true;
+                    return cosmic::app::Task::perform(
+                        fetch_devices_async(client.clone()),
+                        cosmic::Action::App,
+                    );
+                }
+            }
+            Message::DaemonStopped => {
+                if let Some(client) = &self.client {
+                    return cosmic::app::Task::perform(
+                        daemon_stopped_async(client.clone()),
+                        cosmic::Action::App,
+                    );
+                }
+            }
+            Message::StartDaemon => {
+                if let Some(client) = &self.client {
+                    self.daemon_starting = true;
+                    self.status_message = None;
+                    return cosmic::app::Task::perform(
+                        start_daemon_async(client.clone()),
+                        cosmic::Action::App,
+                    );
+                }
+            }
+            Message::DaemonStartFailed(err) => {
+                self.daemon_starting = false;
+                self.status_message = Some(err);
+            }
             Message::RefreshDevices => {
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     tracing::debug!("Refreshing device list");
                     self.loading = true;
                     self.status_message = None;
                     return cosmic::app::Task::perform(
-                        fetch_devices_async(conn.clone()),
+                        fetch_devices_async(client.clone()),
                         cosmic::Action::App,
                     );
                 }
@@ -736,6 +1252,7 @@ impl Application for ConnectApplet {
                 self.selected_device = None;
                 self.view_mode = ViewMode::DeviceList;
                 self.share_text_input.clear();
+                self.notification_reply = None;
             }
             Message::OpenSendToView(device_id, device_type) => {
                 self.sendto_device_id = Some(device_id);
@@ -750,10 +1267,10 @@ impl Application for ConnectApplet {
 
             // Ping
             Message::SendPing(device_id) => {
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     self.status_message = Some("Sending ping...".to_string());
                     return cosmic::app::Task::perform(
-                        send_ping_async(conn.clone(), device_id),
+                        send_ping_async(client.clone(), device_id),
                         |result| cosmic::Action::App(Message::PingComplete(result)),
                     );
                 }
@@ -765,16 +1282,16 @@ impl Application for ConnectApplet {
                 }
                 Err(e) => {
                     tracing::error!("Ping failed: {}", e);
-                    self.status_message = Some(format!("Ping failed: {}", e));
+                    self.status_message = Some(format!("{}: {}", fl!("ping-failed"), e));
                 }
             },
 
             // Find My Phone
             Message::FindMyPhone(device_id) => {
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     self.status_message = Some(fl!("ringing-phone"));
                     return cosmic::app::Task::perform(
-                        find_my_phone_async(conn.clone(), device_id),
+                        find_my_phone_async(client.clone(), device_id),
                         |result| cosmic::Action::App(Message::FindMyPhoneComplete(result)),
                     );
                 }
@@ -804,13 +1321,13 @@ impl Application for ConnectApplet {
                 );
             }
             Message::FileSelected(path) => {
-                if let (Some(conn), Some(device_id)) =
-                    (&self.dbus_connection, self.pending_share_device.take())
+                if let (Some(client), Some(device_id)) =
+                    (&self.client, self.pending_share_device.take())
                 {
                     if let Some(path) = path {
                         self.status_message = Some("Sharing file...".to_string());
                         return cosmic::app::Task::perform(
-                            share_file_async(conn.clone(), device_id, path),
+                            share_file_async(client.clone(), device_id, path),
                             |result| cosmic::Action::App(Message::ShareComplete(result)),
                         );
                     }
@@ -820,11 +1337,11 @@ impl Application for ConnectApplet {
                 self.share_text_input = text;
             }
             Message::ShareText(device_id, text) => {
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     self.share_text_input.clear();
                     self.status_message = Some("Sharing text...".to_string());
                     return cosmic::app::Task::perform(
-                        share_text_async(conn.clone(), device_id, text),
+                        share_text_async(client.clone(), device_id, text),
                         |result| cosmic::Action::App(Message::ShareComplete(result)),
                     );
                 }
@@ -836,51 +1353,53 @@ impl Application for ConnectApplet {
                 }
                 Err(e) => {
                     tracing::error!("Share failed: {}", e);
-                    self.status_message = Some(format!("Share failed: {}", e));
+                    self.status_message = Some(format!("{}: {}", fl!("share-failed"), e));
                 }
             },
             Message::ConfigChanged(config) => {
                 tracing::info!("Config changed: {:?}", config);
                 self.config = config;
+                self.notification_filters =
+                    NotificationFilters::new(&self.config.notification_rules);
             }
 
             // Pairing
             Message::RequestPair(device_id) => {
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     tracing::info!("Requesting pairing with device: {}", device_id);
                     self.status_message = Some("Pairing request sent...".to_string());
                     return cosmic::app::Task::perform(
-                        request_pair_async(conn.clone(), device_id),
+                        request_pair_async(client.clone(), device_id),
                         cosmic::Action::App,
                     );
                 }
             }
             Message::Unpair(device_id) => {
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     tracing::info!("Unpairing from device: {}", device_id);
                     self.status_message = Some("Unpairing...".to_string());
                     return cosmic::app::Task::perform(
-                        unpair_async(conn.clone(), device_id),
+                        unpair_async(client.clone(), device_id),
                         cosmic::Action::App,
                     );
                 }
             }
             Message::AcceptPairing(device_id) => {
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     tracing::info!("Accepting pairing from device: {}", device_id);
                     self.status_message = Some("Accepting pairing...".to_string());
                     return cosmic::app::Task::perform(
-                        accept_pairing_async(conn.clone(), device_id),
+                        accept_pairing_async(client.clone(), device_id),
                         cosmic::Action::App,
                     );
                 }
             }
             Message::RejectPairing(device_id) => {
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     tracing::info!("Rejecting/cancelling pairing for device: {}", device_id);
                     self.status_message = Some("Rejecting pairing...".to_string());
                     return cosmic::app::Task::perform(
-                        reject_pairing_async(conn.clone(), device_id),
+                        reject_pairing_async(client.clone(), device_id),
                         cosmic::Action::App,
                     );
                 }
@@ -893,13 +1412,13 @@ impl Application for ConnectApplet {
                     }
                     Err(err) => {
                         tracing::error!("Pairing error: {}", err);
-                        self.status_message = Some(format!("Error: {}", err));
+                        self.status_message = Some(format!("{}: {}", fl!("pairing-failed"), err));
                     }
                 }
                 // Refresh devices to update pairing state
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     return cosmic::app::Task::perform(
-                        fetch_devices_async(conn.clone()),
+                        fetch_devices_async(client.clone()),
                         cosmic::Action::App,
                     );
                 }
@@ -914,26 +1433,255 @@ impl Application for ConnectApplet {
                     return cosmic::app::Task::none();
                 }
 
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     tracing::debug!("D-Bus signal received, refreshing devices");
                     self.last_signal_refresh = now;
                     return cosmic::app::Task::perform(
-                        fetch_devices_async(conn.clone()),
+                        fetch_devices_async(client.clone()),
+                        cosmic::Action::App,
+                    );
+                }
+            }
+            Message::DeviceEvent(event) => {
+                // Apply the values carried by the signal; only read what it left out
+                let Some(client) = &self.client else {
+                    return cosmic::app::Task::none();
+                };
+                // Close the desktop copies of notifications removed on the phone
+                let mut tasks: Vec<_> = self
+                    .mirrored_notifications
+                    .removed_by(&event)
+                    .into_iter()
+                    .map(|desktop_id| {
+                        cosmic::app::Task::perform(
+                            close_desktop_notification_async(client.connection(), desktop_id),
+                            cosmic::Action::App,
+                        )
+                    })
+                    .collect();
+                match apply_event(&mut self.devices, &event) {
+                    FollowUp::Nothing => {}
+                    FollowUp::Device(device_id) => {
+                        tasks.push(cosmic::app::Task::perform(
+                            fetch_device_async(client.clone(), device_id),
+                            cosmic::Action::App,
+                        ));
+                    }
+                    FollowUp::Notification {
+                        device_id,
+                        notification_id,
+                    } => {
+                        tasks.push(cosmic::app::Task::perform(
+                            fetch_notification_async(client.clone(), device_id, notification_id),
+                            cosmic::Action::App,
+                        ));
+                    }
+                }
+                return cosmic::app::Task::batch(tasks);
+            }
+            Message::DeviceUpdated(result) => match result {
+                Ok(info) => match self.devices.iter_mut().find(|d| d.id == info.id) {
+                    Some(device) => *device = info,
+                    None => self.devices.push(info),
+                },
+                Err(err) => {
+                    // Device may have gone away; fall back to a full refresh
+                    tracing::warn!("Failed to read device: {}", err);
+                    if let Some(client) = &self.client {
+                        return cosmic::app::Task::perform(
+                            fetch_devices_async(client.clone()),
+                            cosmic::Action::App,
+                        );
+                    }
+                }
+            },
+            Message::NotificationUpdated(device_id, result) => match result {
+                Ok(notification) => {
+                    let Some(device) = self.devices.iter_mut().find(|d| d.id == device_id) else {
+                        return cosmic::app::Task::none();
+                    };
+                    // New notifications are mirrored by one applet process;
+                    // updates only refresh a desktop notification this process shows
+                    let allowed = self.notification_filters.allows(&device_id, &notification);
+                    let forward = self.config.forward_notifications && allowed;
+                    let mirror = match device
+                        .notifications
+                        .iter_mut()
+                        .find(|n| n.id == notification.id)
+                    {
+                        Some(existing) => {
+                            *existing = notification.clone();
+                            self.mirrored_notifications
+                                .desktop_id(&device_id, &notification.id)
+                                .map(Some)
+                        }
+                        None => {
+                            device.notifications.push(notification.clone());
+                            (forward
+                                && should_show_phone_notification(&device_id, &notification.id))
+                            .then_some(None)
+                        }
+                    };
+                    let mut tasks = Vec::new();
+                    if allowed {
+                        tasks.push(cosmic::app::Task::perform(
+                            record_notification_async(
+                                device_id.clone(),
+                                notification.clone(),
+                                self.config.notification_history_days,
+                            ),
+                            cosmic::Action::App,
+                        ));
+                    }
+                    if let Some(replaces) = mirror.filter(|_| forward) {
+                        tasks.push(cosmic::app::Task::perform(
+                            show_phone_notification_async(device_id, notification, replaces),
+                            cosmic::Action::App,
+                        ));
+                    }
+                    return cosmic::app::Task::batch(tasks);
+                }
+                // Usually the notification was removed before it could be read
+                Err(err) => tracing::debug!("Failed to read notification: {}", err),
+            },
+            Message::PhoneNotificationShown(device_id, notification_id, result) => match result {
+                Ok(desktop_id) => {
+                    // It may have been removed on the phone while being shown
+                    let posted = self.devices.iter().any(|d| {
+                        d.id == device_id && d.notifications.iter().any(|n| n.id == notification_id)
+                    });
+                    self.mirrored_notifications
+                        .insert(desktop_id, device_id, notification_id);
+                    if !posted {
+                        if let Some(client) = &self.client {
+                            return cosmic::app::Task::perform(
+                                close_desktop_notification_async(client.connection(), desktop_id),
+                                cosmic::Action::App,
+                            );
+                        }
+                    }
+                }
+                Err(err) => tracing::warn!("Failed to show phone notification: {}", err),
+            },
+            Message::DesktopNotificationAction(desktop_id, action) => {
+                if let Some(thread) = self.sms_notification_threads.get(&desktop_id).cloned() {
+                    return match action.as_str() {
+                        ACTION_DEFAULT => self.open_notified_thread(thread, false),
+                        ACTION_REPLY => self.open_notified_thread(thread, true),
+                        _ => cosmic::app::Task::none(),
+                    };
+                }
+                let Some((device_id, notification_id)) =
+                    self.mirrored_notifications.get(desktop_id).cloned()
+                else {
+                    return cosmic::app::Task::none();
+                };
+                match action.as_str() {
+                    ACTION_DISMISS => {
+                        if let Some(client) = &self.client {
+                            tracing::info!(
+                                "Dismissing notification {} on {}",
+                                notification_id,
+                                device_id
+                            );
+                            return cosmic::app::Task::perform(
+                                dismiss_notification_async(
+                                    client.clone(),
+                                    device_id,
+                                    notification_id,
+                                ),
+                                cosmic::Action::App,
+                            );
+                        }
+                    }
+                    ACTION_REPLY => {
+                        // Write the reply on the device page
+                        self.selected_device = Some(device_id.clone());
+                        self.view_mode = ViewMode::DevicePage;
+                        self.share_text_input.clear();
+                        self.notification_reply = Some(NotificationReply {
+                            device_id,
+                            notification_id,
+                            text: String::new(),
+                            sending: false,
+                        });
+                        let focus =
+                            widget::text_input::focus(widget::Id::new("notification-reply"));
+                        if self.popup.is_some() {
+                            return focus;
+                        }
+                        return cosmic::app::Task::batch(vec![
+                            self.update(Message::TogglePopup),
+                            focus,
+                        ]);
+                    }
+                    _ => {}
+                }
+            }
+            Message::DesktopNotificationClosed(desktop_id) => {
+                self.mirrored_notifications.remove(desktop_id);
+                self.sms_notification_threads.remove(&desktop_id);
+            }
+
+            // Notification history
+            Message::OpenNotificationHistory(device_id) => {
+                self.selected_device = Some(device_id.clone());
+                self.notification_history.clear();
+                self.notification_history_search.clear();
+                self.view_mode = ViewMode::NotificationHistory;
+                return cosmic::app::Task::perform(
+                    load_history_async(device_id, self.config.notification_history_days),
+                    cosmic::Action::App,
+                );
+            }
+            Message::CloseNotificationHistory => {
+                self.view_mode = ViewMode::DevicePage;
+                self.notification_history.clear();
+                self.notification_history_search.clear();
+            }
+            Message::NotificationHistoryLoaded(device_id, entries) => {
+                // Recording answers with the history even when it isn't shown
+                if self.view_mode == ViewMode::NotificationHistory
+                    && self.selected_device.as_deref() == Some(device_id.as_str())
+                {
+                    self.notification_history = entries;
+                }
+            }
+            Message::NotificationHistorySearch(query) => {
+                self.notification_history_search = query;
+            }
+            Message::ClearNotificationHistory => {
+                if let Some(device_id) = self.selected_device.clone() {
+                    return cosmic::app::Task::perform(
+                        clear_history_async(device_id),
                         cosmic::Action::App,
                     );
                 }
             }
+            Message::NotificationHistoryCleared(device_id, result) => match result {
+                Ok(()) => {
+                    tracing::info!("Cleared notification history of {}", device_id);
+                    if self.selected_device.as_deref() == Some(device_id.as_str()) {
+                        self.notification_history.clear();
+                    }
+                    self.status_message = Some(fl!("history-cleared"));
+                }
+                Err(err) => {
+                    tracing::warn!("Failed to clear notification history: {}", err);
+                    self.status_message = Some(fl!("history-clear-failed"));
+                }
+            },
 
             // Notifications
             Message::DismissNotification(device_id, notification_id) => {
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     tracing::info!(
                         "Dismissing notification {} on {}",
                         notification_id,
                         device_id
                     );
                     return cosmic::app::Task::perform(
-                        dismiss_notification_async(conn.clone(), device_id, notification_id),
+                        dismiss_notification_async(client.clone(), device_id, notification_id),
                         cosmic::Action::App,
                     );
                 }
@@ -943,13 +1691,78 @@ impl Application for ConnectApplet {
                     Ok(msg) => tracing::info!("Dismiss result: {}", msg),
                     Err(err) => {
                         tracing::error!("Dismiss error: {}", err);
-                        self.status_message = Some(format!("Failed to dismiss: {}", err));
+                        self.status_message = Some(format!("{}: {}", fl!("dismiss-failed"), err));
                     }
                 }
                 // Refresh devices to update notification list
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
+                    return cosmic::app::Task::perform(
+                        fetch_devices_async(client.clone()),
+                        cosmic::Action::App,
+                    );
+                }
+            }
+            Message::ToggleNotificationReply(device_id, notification_id) => {
+                let is_open = self.notification_reply.as_ref().is_some_and(|reply| {
+                    reply.device_id == device_id && reply.notification_id == notification_id
+                });
+                if is_open {
+                    self.notification_reply = None;
+                } else {
+                    self.notification_reply = Some(NotificationReply {
+                        device_id,
+                        notification_id,
+                        text: String::new(),
+                        sending: false,
+                    });
+                    return widget::text_input::focus(widget::Id::new("notification-reply"));
+                }
+            }
+            Message::NotificationReplyInput(text) => {
+                if let Some(reply) = &mut self.notification_reply {
+                    reply.text = text;
+                }
+            }
+            Message::SendNotificationReply => {
+                if let (Some(client), Some(reply)) = (&self.client, &mut self.notification_reply) {
+                    if !reply.text.trim().is_empty() && !reply.sending {
+                        tracing::info!(
+                            "Replying to notification {} on {}",
+                            reply.notification_id,
+                            reply.device_id
+                        );
+                        reply.sending = true;
+                        return cosmic::app::Task::perform(
+                            reply_to_notification_async(
+                                client.clone(),
+                                reply.device_id.clone(),
+                                reply.notification_id.clone(),
+                                reply.text.clone(),
+                            ),
+                            cosmic::Action::App,
+                        );
+                    }
+                }
+            }
+            Message::NotificationReplyResult(device_id, result) => {
+                match result {
+                    Ok(()) => {
+                        self.notification_reply = None;
+                        self.status_message = Some(fl!("reply-sent"));
+                    }
+                    Err(err) => {
+                        tracing::error!("Notification reply error: {}", err);
+                        // Keep the text so the reply can be sent again
+                        if let Some(reply) = &mut self.notification_reply {
+                            reply.sending = false;
+                        }
+                        self.status_message = Some(format!("{}: {}", fl!("reply-failed"), err));
+                    }
+                }
+                // Refresh the device so the notification shows its current state
+                if let Some(client) = &self.client {
                     return cosmic::app::Task::perform(
-                        fetch_devices_async(conn.clone()),
+                        fetch_device_async(client.clone(), device_id),
                         cosmic::Action::App,
                     );
                 }
@@ -957,11 +1770,11 @@ impl Application for ConnectApplet {
 
             // Clipboard
             Message::SendClipboard(device_id) => {
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     tracing::info!("Sending clipboard to device: {}", device_id);
                     self.status_message = Some("Sending clipboard...".to_string());
                     return cosmic::app::Task::perform(
-                        send_clipboard_async(conn.clone(), device_id),
+                        send_clipboard_async(client.clone(), device_id),
                         cosmic::Action::App,
                     );
                 }
@@ -973,7 +1786,7 @@ impl Application for ConnectApplet {
                 }
                 Err(err) => {
                     tracing::error!("Clipboard error: {}", err);
-                    self.status_message = Some(format!("Clipboard error: {}", err));
+                    self.status_message = Some(format!("{}: {}", fl!("clipboard-failed"), err));
                 }
             },
 
@@ -983,6 +1796,7 @@ impl Application for ConnectApplet {
                     self.view_mode = ViewMode::DeviceList;
                 } else {
                     self.view_mode = ViewMode::Settings;
+                    self.refresh_rule_devices();
                 }
             }
             Message::ToggleSetting(key) => {
@@ -1021,6 +1835,9 @@ impl Application for ConnectApplet {
                     SettingKey::FileNotifications => {
                         self.config.file_notifications = !self.config.file_notifications;
                     }
+                    SettingKey::MediaPlayerBridge => {
+                        self.config.media_player_bridge = !self.config.media_player_bridge;
+                    }
                 }
                 tracing::debug!("Settings updated: {:?}", self.config);
                 // Save config to disk
@@ -1028,10 +1845,88 @@ impl Application for ConnectApplet {
                     tracing::error!(?err, "Failed to save config");
                 }
             }
+            Message::RuleDraftDevice(index) => {
+                self.rule_draft.device = Some(index);
+            }
+            Message::RuleDraftAppName(app_name) => {
+                self.rule_draft.app_name = app_name;
+                self.rule_draft.error = None;
+            }
+            Message::RuleDraftTitle(title_pattern) => {
+                self.rule_draft.title_pattern = title_pattern;
+                self.rule_draft.error = None;
+            }
+            Message::RuleDraftAllow(allow) => {
+                self.rule_draft.allow = allow;
+            }
+            Message::RuleDraftSilentOnly(silent_only) => {
+                self.rule_draft.silent_only = silent_only;
+                self.rule_draft.error = None;
+            }
+            Message::AddNotificationRule => {
+                let draft = &mut self.rule_draft;
+                let Some(device_id) = draft.device.and_then(|i| draft.device_ids.get(i)).cloned()
+                else {
+                    draft.error = Some(fl!("notification-rule-no-device"));
+                    return cosmic::app::Task::none();
+                };
+                let rule = NotificationRule {
+                    action: if draft.allow {
+                        RuleAction::Allow
+                    } else {
+                        RuleAction::Block
+                    },
+                    app_name: draft.app_name.trim().to_string(),
+                    title_pattern: draft.title_pattern.clone(),
+                    silent_only: draft.silent_only,
+                };
+                if let Err(err) = rule.validate() {
+                    draft.error = Some(err);
+                    return cosmic::app::Task::none();
+                }
+                draft.app_name.clear();
+                draft.title_pattern.clear();
+                draft.allow = false;
+                draft.silent_only = false;
+                self.config
+                    .notification_rules
+                    .entry(device_id)
+                    .or_default()
+                    .push(rule);
+                self.save_notification_rules();
+            }
+            Message::RemoveNotificationRule(device_id, index) => {
+                if let Some(rules) = self.config.notification_rules.get_mut(&device_id) {
+                    if index < rules.len() {
+                        rules.remove(index);
+                    }
+                    if rules.is_empty() {
+                        self.config.notification_rules.remove(&device_id);
+                    }
+                    self.save_notification_rules();
+                }
+            }
+            Message::MuteNotificationApp(device_id, app_name) => {
+                let rule = NotificationRule::mute(&app_name);
+                let rules = self.config.notification_rules.entry(device_id).or_default();
+                if !rules.contains(&rule) {
+                    rules.push(rule);
+                    self.save_notification_rules();
+                }
+                self.status_message = Some(fl!("app-muted", app = app_name));
+            }
+            Message::SetHistoryRetention(index) => {
+                if let Some(&days) = HISTORY_RETENTION_DAYS.get(index) {
+                    self.config.notification_history_days = days;
+                    if let Err(err) = self.config.save() {
+                        tracing::error!(?err, "Failed to save config");
+                    }
+                }
+            }
 
             // SMS
             Message::OpenSmsView(device_id) => {
-                if self.dbus_connection.is_some() {
+                if self.client.is_some() {
                     // Find device name for header
                     let device_name = self
                         .devices
@@ -1068,23 +1963,33 @@ impl Application for ConnectApplet {
                         self.conversations.clear();
                         self.conversations_displayed = 10;
                         self.message_cache.clear();
+                        self.attachment_thumbnails.clear();
+                        self.sms_sims.clear();
                         self.contacts = ContactLookup::default(); // Will be loaded async
                         tracing::info!(
                             "Opening SMS view for device: {} (subscription-based loading)",
                             device_id
                         );
 
-                        // Load contacts in parallel - subscription handles conversation loading
+                        // Load contacts and the on-disk store in parallel -
+                        // subscription handles conversation loading
+                        self.sms_store = None;
                         let device_id_for_contacts = device_id.clone();
-                        return cosmic::app::Task::perform(
-                            async move {
-                                let contacts =
-                                    ContactLookup::load_for_device(&device_id_for_contacts)
-                                        .await;
-                                Message::ContactsLoaded(device_id_for_contacts, contacts)
-                            },
-                            cosmic::Action::App,
-                        );
+                        return cosmic::app::Task::batch(vec![
+                            cosmic::app::Task::perform(
+                                async move {
+                                    let contacts =
+                                        ContactLookup::load_for_device(&device_id_for_contacts)
+                                            .await;
+                                    Message::ContactsLoaded(device_id_for_contacts, contacts)
+                                },
+                                cosmic::Action::App,
+                            ),
+                            cosmic::app::Task::perform(
+                                open_store_async(device_id),
+                                cosmic::Action::App,
+                            ),
+                        ]);
                     }
                 }
             }
@@ -1101,10 +2006,15 @@ impl Application for ConnectApplet {
                 self.conversation_list_subscription_active = false;
                 self.sms_compose_text.clear();
                 self.sms_sending = false;
+                self.sms_search_query.clear();
+                self.sms_search_results.clear();
+                self.sms_search_generation += 1;
+                self.sms_export_menu_open = false;
+                self.sms_export_status = None;
             }
             Message::OpenConversation(thread_id) => {
                 // Guard: need D-Bus connection and device ID for the subscription
-                if self.dbus_connection.is_some() && self.sms_device_id.is_some() {
+                if self.client.is_some() && self.sms_device_id.is_some() {
                     // Find the conversation for header info and deduplication
                     let conversation =
                         self.conversations.iter().find(|c| c.thread_id == thread_id);
@@ -1140,7 +2050,7 @@ impl Application for ConnectApplet {
                         self.messages = cached.clone();
                         // Populate known_message_ids from cache for deduplication
                         for msg in &self.messages {
-                            self.known_message_ids.insert(msg.uid);
+                            self.known_message_ids.insert(msg.key());
                         }
                         tracing::debug!(
                             "Using in-memory cached {} messages for thread {}",
@@ -1148,10 +2058,39 @@ impl Application for ConnectApplet {
                             thread_id
                         );
                         true
+                    } else if let Some(stored) = self
+                        .sms_store
+                        .as_ref()
+                        .map(|store| store.messages(thread_id))
+                        .filter(|stored| !stored.is_empty())
+                    {
+                        // Fall back to messages kept on disk
+                        tracing::debug!(
+                            "Using {} stored messages for thread {}",
+                            stored.len(),
+                            thread_id
+                        );
+                        self.known_message_ids
+                            .extend(stored.iter().map(SmsMessage::key));
+                        if self.current_thread_addresses.is_none() {
+                            // Threads found by search may not be in the conversation list
+                            self.current_thread_addresses =
+                                stored.first().map(|m| m.addresses.clone());
+                        }
+                        self.messages = stored;
+                        true
                     } else {
                         self.messages.clear();
                         false
                     };
+                    cache_thumbnails(&mut self.attachment_thumbnails, &self.messages);
+                    self.sms_sims.add_messages(&self.messages);
+                    // A SIM picked for this contact wins over the one the thread last used
+                    self.current_thread_sub_id = self
+                        .current_thread_addresses
+                        .as_deref()
+                        .and_then(|addresses| self.saved_sim(addresses))
+                        .or_else(|| thread_sub_id(&self.messages));
 
                     // Set up subscription-based loading state
                     // The subscription will fire the D-Bus request after setting up match rules
@@ -1169,10 +2108,7 @@ impl Application for ConnectApplet {
                         );
                         // Scroll to bottom for cached messages
                         // Subscription will fire D-Bus request and handle incoming signals
-                        return scrollable::snap_to(
-                            widget::Id::new("message-thread"),
-                            scrollable::RelativeOffset::END,
-                        );
+                        return self.scroll_thread();
                     } else {
                         // No in-memory cache - do subscription-based loading
                         self.sms_loading_state =
@@ -1200,19 +2136,27 @@ impl Application for ConnectApplet {
                 self.conversation_load_active = false;
                 self.loading_thread_id = None;
                 self.known_message_ids.clear();
+                self.highlighted_message_uid = None;
+                self.sms_export_menu_open = false;
+                self.sms_export_status = None;
+                self.attachment_status = None;
+                self.outgoing_attachments.clear();
 
                 // Increment key to reset scroll position
                 self.conversation_list_key = self.conversation_list_key.wrapping_add(1);
 
                 // Refresh conversations in background
-                if let (Some(conn), Some(device_id)) = (&self.dbus_connection, &self.sms_device_id)
-                {
+                if let (Some(client), Some(device_id)) = (&self.client, &self.sms_device_id) {
                     if self.conversations.is_empty() {
                         self.sms_loading_state =
                             SmsLoadingState::LoadingConversations(LoadingPhase::Connecting);
                     }
                     return cosmic::app::Task::perform(
-                        fetch_conversations_async(conn.clone(), device_id.clone()),
+                        fetch_conversations_async(
+                            client.clone(),
+                            device_id.clone(),
+                            self.sms_store.clone(),
+                        ),
                         cosmic::Action::App,
                     );
                 }
@@ -1224,26 +2168,7 @@ impl Application for ConnectApplet {
                     "Displaying {} cached conversations immediately",
                     convs.len()
                 );
-                if !convs.is_empty() {
-                    // Pre-populate last_seen_sms to prevent false notifications
-                    for conv in &convs {
-                        let current = self.last_seen_sms.get(&conv.thread_id).copied();
-                        if current.is_none() || current < Some(conv.timestamp) {
-                            self.last_seen_sms.insert(conv.thread_id, conv.timestamp);
-                        }
-                    }
-
-                    self.conversations = convs;
-                    self.conversation_list_key = self.conversation_list_key.wrapping_add(1);
-                }
-                // Switch from "Connecting" spinner to showing data + sync indicator
-                // (conversation_sync_active remains true until ConversationsLoaded arrives)
-                if matches!(
-                    self.sms_loading_state,
-                    SmsLoadingState::LoadingConversations(_)
-                ) {
-                    self.sms_loading_state = SmsLoadingState::Idle;
-                }
+                self.show_cached_conversations(convs);
             }
             Message::ConversationsLoaded(convs) => {
                 // Slow path: full sync complete from phone (legacy batch loading)
@@ -1290,6 +2215,10 @@ impl Application for ConnectApplet {
                     return cosmic::app::Task::none();
                 }
 
+                if let Some(store) = &self.sms_store {
+                    store.insert_conversations([&conversation]);
+                }
+
                 // Update or insert conversation by thread_id
                 if let Some(existing) = self
                     .conversations
@@ -1386,12 +2315,269 @@ impl Application for ConnectApplet {
                     );
                 }
             }
+            Message::SmsStoreLoaded(store) => {
+                // Only use the store if it is for the current SMS device
+                if self.sms_device_id.as_deref() != Some(store.device_id().as_str()) {
+                    return cosmic::app::Task::none();
+                }
+                // Keep what already arrived from the phone before the store was open
+                store.insert_conversations(&self.conversations);
+                if self.conversations.is_empty() {
+                    let stored = store.conversations();
+                    tracing::info!("Displaying {} stored conversations", stored.len());
+                    self.show_cached_conversations(stored);
+                }
+                self.sms_sims.extend(store.sub_ids());
+                self.sms_store = Some(store);
+                if !self.sms_search_query.is_empty() {
+                    self.sms_search_generation += 1;
+                    return self.search_sms();
+                }
+            }
             Message::LoadMoreConversations => {
                 // Show 10 more conversations (up to total available)
                 self.conversations_displayed =
                     (self.conversations_displayed + 10).min(self.conversations.len());
             }
-            Message::MessagesLoaded(thread_id, msgs, total_count) => {
+            Message::SmsSearchInput(query) => {
+                self.sms_search_generation += 1;
+                if query.trim().is_empty() {
+                    self.sms_search_results.clear();
+                    self.sms_search_query = query;
+                    return cosmic::app::Task::none();
+                }
+                self.sms_search_query = query;
+                // Search once typing pauses, not on every keystroke
+                let generation = self.sms_search_generation;
+                return cosmic::app::Task::perform(
+                    async move {
+                        tokio::time::sleep(std::time::Duration::from_millis(SEARCH_DEBOUNCE_MS))
+                            .await;
+                        Message::SmsSearch(generation)
+                    },
+                    cosmic::Action::App,
+                );
+            }
+            Message::SmsSearch(generation) => {
+                if generation == self.sms_search_generation {
+                    return self.search_sms();
+                }
+            }
+            Message::SmsSearchResults { generation, hits } => {
+                if generation == self.sms_search_generation {
+                    self.sms_search_results = hits;
+                }
+            }
+            Message::OpenSearchResult { thread_id, uid } => {
+                self.highlighted_message_uid = uid;
+                if uid.is_some() {
+                    // The cached page may not reach back to the match; the store has it
+                    self.message_cache.pop(&thread_id);
+                }
+                return cosmic::app::Task::perform(
+                    async move { Message::OpenConversation(thread_id) },
+                    cosmic::Action::App,
+                );
+            }
+            Message::ToggleSmsExportMenu => {
+                self.sms_export_menu_open = !self.sms_export_menu_open;
+            }
+            Message::ExportSms(format) => {
+                self.sms_export_menu_open = false;
+                // From a thread export that thread, from the list every conversation
+                let (thread_id, name) = match self.view_mode {
+                    ViewMode::MessageThread => (
+                        self.current_thread_id,
+                        self.current_thread_addresses
+                            .as_ref()
+                            .and_then(|addrs| addrs.first())
+                            .map(|addr| self.contacts.get_name_or_number(addr))
+                            .unwrap_or_default(),
+                    ),
+                    _ => (None, self.sms_device_name.clone().unwrap_or_default()),
+                };
+                self.pending_sms_export = Some((thread_id, format));
+                let file_name = default_file_name(&name, format);
+                return cosmic::app::Task::perform(
+                    async move {
+                        rfd::AsyncFileDialog::new()
+                            .set_file_name(file_name)
+                            .add_filter(format.label(), &[format.extension()])
+                            .save_file()
+                            .await
+                            .map(|f| f.path().to_path_buf())
+                    },
+                    |path| cosmic::Action::App(Message::SmsExportPathSelected(path)),
+                );
+            }
+            Message::SmsExportPathSelected(path) => {
+                if let (Some(client), Some(device_id), Some((thread_id, format)), Some(path)) = (
+                    &self.client,
+                    &self.sms_device_id,
+                    self.pending_sms_export.take(),
+                    path,
+                ) {
+                    let thread_ids = match thread_id {
+                        Some(thread_id) => vec![thread_id],
+                        None => {
+                            let mut ids: Vec<i64> =
+                                self.conversations.iter().map(|c| c.thread_id).collect();
+                            if let Some(store) = &self.sms_store {
+                                ids.extend(store.thread_ids());
+                            }
+                            ids.sort_unstable();
+                            ids.dedup();
+                            ids
+                        }
+                    };
+                    // Paging an out-of-reach phone would only wait for every page to time out
+                    let fetch_from_phone = self
+                        .devices
+                        .iter()
+                        .any(|d| d.id == *device_id && d.is_reachable);
+                    self.sms_export_status = Some(fl!("sms-exporting"));
+                    return cosmic::app::Task::perform(
+                        export_sms_async(
+                            client.clone(),
+                            ExportRequest {
+                                device_id: device_id.clone(),
+                                thread_ids,
+                                format,
+                                path,
+                                page_size: self.config.messages_per_page,
+                                fetch_from_phone,
+                            },
+                            self.contacts.clone(),
+                            self.sms_store.clone(),
+                        ),
+                        |result| cosmic::Action::App(Message::SmsExportComplete(result)),
+                    );
+                }
+            }
+            Message::SmsExportComplete(result) => {
+                self.sms_export_status = Some(match result {
+                    Ok(ExportSummary {
+                        path,
+                        count,
+                        incomplete: None,
+                    }) => fl!(
+                        "sms-export-done",
+                        count = count,
+                        path = path.display().to_string()
+                    ),
+                    Ok(ExportSummary {
+                        path,
+                        count,
+                        incomplete: Some(reason),
+                    }) => {
+                        tracing::warn!("SMS export may be incomplete: {}", reason);
+                        format!(
+                            "{}: {}",
+                            fl!(
+                                "sms-export-partial",
+                                count = count,
+                                path = path.display().to_string()
+                            ),
+                            reason
+                        )
+                    }
+                    Err(e) => {
+                        tracing::error!("SMS export failed: {}", e);
+                        format!("{}: {}", fl!("sms-export-failed"), e)
+                    }
+                });
+            }
+            Message::OpenAttachment {
+                part_id,
+                unique_identifier,
+            } => {
+                if let (Some(client), Some(device_id)) = (&self.client, &self.sms_device_id) {
+                    self.attachment_status = Some(fl!("attachment-downloading"));
+                    return cosmic::app::Task::perform(
+                        download_attachment_async(
+                            client.clone(),
+                            device_id.clone(),
+                            part_id,
+                            unique_identifier,
+                        ),
+                        cosmic::Action::App,
+                    );
+                }
+            }
+            Message::AttachmentDownloaded(result) => match result {
+                Ok(path) => {
+                    self.attachment_status = None;
+                    return cosmic::app::Task::perform(
+                        async move { open_attachment(&path).await },
+                        |result| cosmic::Action::App(Message::AttachmentOpened(result)),
+                    );
+                }
+                Err(e) => {
+                    tracing::error!("Attachment download failed: {}", e);
+                    self.attachment_status = Some(format!("{}: {}", fl!("attachment-failed"), e));
+                }
+            },
+            Message::AttachmentOpened(result) => {
+                if let Err(e) = result {
+                    tracing::error!("Failed to open attachment: {}", e);
+                    self.attachment_status = Some(format!("{}: {}", fl!("attachment-failed"), e));
+                }
+            }
+            Message::PickAttachments => {
+                return cosmic::app::Task::perform(
+                    async move {
+                        let files = rfd::AsyncFileDialog::new()
+                            .add_filter(fl!("attachment-filter"), &sendable_extensions())
+                            .pick_files()
+                            .await
+                            .unwrap_or_default();
+                        prepare_attachments_async(
+                            files.iter().map(|f| f.path().to_path_buf()).collect(),
+                        )
+                        .await
+                    },
+                    cosmic::Action::App,
+                );
+            }
+            Message::PasteAttachment => {
+                return clipboard::read_data::<ClipboardImage>()
+                    .map(|image| cosmic::Action::App(Message::ClipboardImagePasted(image)));
+            }
+            Message::ClipboardImagePasted(image) => match image {
+                Some(image) => {
+                    return cosmic::app::Task::perform(
+                        save_pasted_image(image.bytes, image.mime_type),
+                        cosmic::Action::App,
+                    );
+                }
+                None => self.attachment_status = Some(fl!("no-image-in-clipboard")),
+            },
+            Message::AttachmentsPrepared(prepared) => {
+                let errors = add_attachments(&mut self.outgoing_attachments, prepared);
+                self.attachment_status = if errors.is_empty() {
+                    None
+                } else {
+                    Some(errors.join("\n"))
+                };
+            }
+            Message::RemoveAttachment(index) => {
+                if index < self.outgoing_attachments.len() {
+                    self.outgoing_attachments.remove(index);
+                }
+            }
+            Message::SelectThreadSim(index) => {
+                if let Some(sub_id) = self.sms_sims.sub_id(index) {
+                    tracing::debug!("Using SIM sub_id {} for current thread", sub_id);
+                    self.current_thread_sub_id = Some(sub_id);
+                    if let Some(addresses) = self.current_thread_addresses.clone() {
+                        self.remember_sim(&addresses, sub_id);
+                    }
+                }
+            }
+            Message::SelectNewMessageSim(index) => {
+                self.new_message_sub_id = self.sms_sims.sub_id(index);
+            }
+            Message::MessagesLoaded(thread_id, msgs, total_count) => {
                 // Slow path: full sync complete from phone
                 if self.current_thread_id == Some(thread_id) {
                     tracing::info!(
@@ -1403,15 +2589,17 @@ impl Application for ConnectApplet {
                     );
                     // Only update if we got more messages than currently shown
                     if msgs.len() >= self.messages.len() {
-                        // Extract sub_id from the first message (for MMS group messaging)
-                        if let Some(first_msg) = msgs.first() {
-                            self.current_thread_sub_id = Some(first_msg.sub_id);
+                        // Extract sub_id from the messages (for MMS group messaging)
+                        // unless a SIM was already picked or known
+                        if self.current_thread_sub_id.is_none() {
+                            self.current_thread_sub_id = thread_sub_id(&msgs);
                             tracing::debug!(
-                                "Set sub_id to {} for thread {}",
-                                first_msg.sub_id,
+                                "Set sub_id to {:?} for thread {}",
+                                self.current_thread_sub_id,
                                 thread_id
                             );
                         }
+                        self.sms_sims.add_messages(&msgs);
 
                         // Update last_seen_sms with the newest message timestamp
                         // to prevent false notifications for messages we just loaded
@@ -1432,6 +2620,7 @@ impl Application for ConnectApplet {
                             Some(total) => (msgs.len() as u64) < total,
                             None => msgs.len() >= self.config.messages_per_page as usize,
                         };
+                        cache_thumbnails(&mut self.attachment_thumbnails, &msgs);
                         self.messages = msgs;
                     }
                     // Background sync complete - clear sync indicator
@@ -1444,10 +2633,7 @@ impl Application for ConnectApplet {
                     // Always scroll to bottom when messages are loaded/updated
                     // to keep the newest messages visible
                     if !self.messages.is_empty() {
-                        return scrollable::snap_to(
-                            widget::Id::new("message-thread"),
-                            scrollable::RelativeOffset::END,
-                        );
+                        return self.scroll_thread();
                     }
                 }
             }
@@ -1473,6 +2659,9 @@ impl Application for ConnectApplet {
                             total_count
                         );
 
+                        cache_thumbnails(&mut self.attachment_thumbnails, &older_msgs);
+                        self.sms_sims.add_messages(&older_msgs);
+
                         // Prepend older messages (they come sorted oldest first)
                         let mut combined = older_msgs;
                         combined.append(&mut self.messages);
@@ -1551,22 +2740,21 @@ impl Application for ConnectApplet {
                     self.content_height_before_load = Some(content_height);
 
                     // Trigger loading older messages (same logic as LoadMoreMessages)
-                    if let (Some(conn), Some(device_id), Some(thread_id)) = (
-                        &self.dbus_connection,
-                        &self.sms_device_id,
-                        self.current_thread_id,
-                    ) {
+                    if let (Some(client), Some(device_id), Some(thread_id)) =
+                        (&self.client, &self.sms_device_id, self.current_thread_id)
+                    {
                         self.sms_loading_state = SmsLoadingState::LoadingMoreMessages;
                         let start_index = self.messages_loaded_count;
                         let count = self.config.messages_per_page;
 
                         return cosmic::app::Task::perform(
                             fetch_older_messages_async(
-                                conn.clone(),
+                                client.clone(),
                                 device_id.clone(),
                                 thread_id,
                                 start_index,
                                 count,
+                                self.sms_store.clone(),
                             ),
                             cosmic::Action::App,
                         );
@@ -1645,8 +2833,12 @@ impl Application for ConnectApplet {
                     return cosmic::app::Task::none();
                 }
 
+                if let Some(store) = &self.sms_store {
+                    store.insert([&message]);
+                }
+
                 // Deduplication: skip if already have this message
-                if self.known_message_ids.contains(&message.uid) {
+                if self.known_message_ids.contains(&message.key()) {
                     tracing::debug!(
                         "Skipping duplicate message uid={} for thread {}",
                         message.uid,
@@ -1654,10 +2846,10 @@ impl Application for ConnectApplet {
                     );
                     return cosmic::app::Task::none();
                 }
-                self.known_message_ids.insert(message.uid);
+                self.known_message_ids.insert(message.key());
 
                 // Extract sub_id from first message (for MMS group messaging)
-                if self.current_thread_sub_id.is_none() {
+                if self.current_thread_sub_id.is_none() && message.sub_id >= 0 {
                     self.current_thread_sub_id = Some(message.sub_id);
                     tracing::debug!(
                         "Set sub_id to {} for thread {}",
@@ -1666,6 +2858,9 @@ impl Application for ConnectApplet {
                     );
                 }
 
+                cache_thumbnails(&mut self.attachment_thumbnails, [&message]);
+                self.sms_sims.add_messages([&message]);
+
                 // Insert message in sorted order by date
                 let insert_pos = self
                     .messages
@@ -1688,10 +2883,7 @@ impl Application for ConnectApplet {
 
                 // Scroll to bottom after each message to keep newest visible
                 // This prevents jarring jumps when older messages are inserted above
-                return scrollable::snap_to(
-                    widget::Id::new("message-thread"),
-                    scrollable::RelativeOffset::END,
-                );
+                return self.scroll_thread();
             }
             Message::ConversationLoadComplete { thread_id, total_count } => {
                 // Guard: Only process if still viewing this thread
@@ -1736,10 +2928,7 @@ impl Application for ConnectApplet {
 
                 // Scroll to bottom if we loaded messages
                 if !self.messages.is_empty() {
-                    return scrollable::snap_to(
-                        widget::Id::new("message-thread"),
-                        scrollable::RelativeOffset::END,
-                    );
+                    return self.scroll_thread();
                 }
             }
 
@@ -1756,21 +2945,21 @@ impl Application for ConnectApplet {
             Message::SendSms => {
                 tracing::info!("SendSms triggered");
                 tracing::info!(
-                    "State: conn={}, device_id={:?}, thread_id={:?}, addresses={:?}, text_empty={}, sending={}",
-                    self.dbus_connection.is_some(),
+                    "State: client={}, device_id={:?}, thread_id={:?}, addresses={:?}, text_empty={}, sending={}",
+                    self.client.is_some(),
                     self.sms_device_id,
                     self.current_thread_id,
                     self.current_thread_addresses,
                     self.sms_compose_text.is_empty(),
                     self.sms_sending
                 );
-                if let (Some(conn), Some(device_id), Some(thread_id), Some(addresses)) = (
-                    &self.dbus_connection,
+                if let (Some(client), Some(device_id), Some(thread_id), Some(addresses)) = (
+                    &self.client,
                     &self.sms_device_id,
                     self.current_thread_id,
                     &self.current_thread_addresses,
                 ) {
-                    if !self.sms_compose_text.is_empty()
+                    if (!self.sms_compose_text.is_empty() || !self.outgoing_attachments.is_empty())
                         && !self.sms_sending
                         && !addresses.is_empty()
                     {
@@ -1791,6 +2980,8 @@ impl Application for ConnectApplet {
                         }
 
                         let message_text = self.sms_compose_text.clone();
+                        let attachment_urls =
+                            self.outgoing_attachments.iter().map(|a| a.url()).collect();
                         let recipients = addresses.clone();
                         let sub_id = self.current_thread_sub_id.unwrap_or(-1);
                         self.sms_sending = true;
@@ -1799,15 +2990,25 @@ impl Application for ConnectApplet {
                             recipients.len(),
                             sub_id
                         );
+                        let pasted = pasted_images(&self.outgoing_attachments);
+                        let send = send_sms_async(
+                            client.clone(),
+                            device_id.clone(),
+                            thread_id,
+                            recipients,
+                            message_text,
+                            attachment_urls,
+                            sub_id,
+                        );
                         return cosmic::app::Task::perform(
-                            send_sms_async(
-                                conn.clone(),
-                                device_id.clone(),
-                                thread_id,
-                                recipients,
-                                message_text,
-                                sub_id,
-                            ),
+                            async move {
+                                let result = send.await;
+                                // Pasted images stay for another try if sending failed
+                                if matches!(result, Message::SmsSendResult(Ok(_))) {
+                                    remove_pasted_images(pasted).await;
+                                }
+                                result
+                            },
                             cosmic::Action::App,
                         );
                     } else {
@@ -1818,12 +3019,46 @@ impl Application for ConnectApplet {
                     tracing::warn!("SendSms missing required state");
                 }
             }
+            Message::RetrySms(uid) => {
+                let failed = self
+                    .messages
+                    .iter()
+                    .find(|m| m.uid == uid && m.message_type == MessageType::Failed);
+                if let (Some(client), Some(device_id), Some(thread_id), Some(failed)) = (
+                    &self.client,
+                    &self.sms_device_id,
+                    self.current_thread_id,
+                    failed,
+                ) {
+                    if self.sms_sending {
+                        return cosmic::app::Task::none();
+                    }
+                    let unique_addresses: HashSet<&str> =
+                        failed.addresses.iter().map(String::as_str).collect();
+                    if unique_addresses.len() > 1 {
+                        self.status_message = Some(fl!("group-sms-not-supported"));
+                        return cosmic::app::Task::none();
+                    }
+
+                    tracing::info!("Retrying failed message {} in thread {}", uid, thread_id);
+                    self.sms_sending = true;
+                    self.retrying_uid = Some(uid);
+                    return cosmic::app::Task::perform(
+                        retry_sms_async(client.clone(), device_id.clone(), failed.clone()),
+                        cosmic::Action::App,
+                    );
+                }
+            }
             Message::SmsSendResult(result) => {
                 self.sms_sending = false;
+                let retried = self.retrying_uid.take();
                 match result {
                     Ok(sent_body) => {
                         tracing::info!("SMS sent successfully");
-                        self.sms_compose_text.clear();
+                        if retried.is_none() {
+                            self.sms_compose_text.clear();
+                            self.outgoing_attachments.clear();
+                        }
                         self.status_message = Some(fl!("sms-sent"));
 
                         // Optimistic update: add the sent message to the local list immediately
@@ -1847,25 +3082,43 @@ impl Application for ConnectApplet {
                             self.conversations
                                 .sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
 
-                            let sent_message = SmsMessage {
-                                body: sent_body,
-                                addresses: self
-                                    .current_thread_addresses
-                                    .clone()
-                                    .unwrap_or_default(),
-                                date: now_ms,
-                                message_type: kdeconnect_dbus::plugins::MessageType::Sent,
-                                read: true,
-                                thread_id,
-                                uid: 0, // Placeholder for optimistic message; will be replaced on sync
-                                sub_id: self.current_thread_sub_id.unwrap_or(-1),
-                            };
-
-                            self.messages.push(sent_message.clone());
-
-                            // Update cache as well
-                            if let Some(cached) = self.message_cache.get_mut(&thread_id) {
-                                cached.push(sent_message);
+                            if let Some(uid) = retried {
+                                // The failed bubble turns into the sending one, so the
+                                // message isn't shown twice until the refresh
+                                let resend = |m: &mut SmsMessage| {
+                                    if m.uid == uid && m.message_type == MessageType::Failed {
+                                        m.message_type = MessageType::Outbox;
+                                        m.date = now_ms;
+                                    }
+                                };
+                                self.messages.iter_mut().for_each(resend);
+                                if let Some(cached) = self.message_cache.get_mut(&thread_id) {
+                                    cached.iter_mut().for_each(resend);
+                                }
+                            } else {
+                                let sent_message = SmsMessage {
+                                    body: sent_body,
+                                    addresses: self
+                                        .current_thread_addresses
+                                        .clone()
+                                        .unwrap_or_default(),
+                                    date: now_ms,
+                                    // Shown as sending until the refresh brings the phone's copy
+                                    message_type: MessageType::Outbox,
+                                    read: true,
+                                    thread_id,
+                                    // Placeholder for optimistic message; will be replaced on sync
+                                    uid: 0,
+                                    sub_id: self.current_thread_sub_id.unwrap_or(-1),
+                                    attachments: Vec::new(),
+                                };
+
+                                self.messages.push(sent_message.clone());
+
+                                // Update cache as well
+                                if let Some(cached) = self.message_cache.get_mut(&thread_id) {
+                                    cached.push(sent_message);
+                                }
                             }
 
                             // Trigger delayed refresh to sync with server
@@ -1897,15 +3150,14 @@ impl Application for ConnectApplet {
             Message::DelayedMessageRefresh(thread_id) => {
                 // Refresh messages after a delay to sync sent message from server
                 if self.current_thread_id == Some(thread_id) {
-                    if let (Some(conn), Some(device_id)) =
-                        (&self.dbus_connection, &self.sms_device_id)
-                    {
+                    if let (Some(client), Some(device_id)) = (&self.client, &self.sms_device_id) {
                         return cosmic::app::Task::perform(
                             fetch_messages_async(
-                                conn.clone(),
+                                client.clone(),
                                 device_id.clone(),
                                 thread_id,
                                 self.config.messages_per_page,
+                                self.sms_store.clone(),
                             ),
                             cosmic::Action::App,
                         );
@@ -1920,6 +3172,9 @@ impl Application for ConnectApplet {
                 self.new_message_body.clear();
                 self.new_message_recipient_valid = false;
                 self.new_message_sending = false;
+                self.new_message_sub_id = None;
+                self.outgoing_attachments.clear();
+                self.attachment_status = None;
                 // Clear any previous suggestions; they will be populated by search
                 self.contact_suggestions.clear();
                 // Focus the recipient input field
@@ -1931,6 +3186,9 @@ impl Application for ConnectApplet {
                 self.new_message_body.clear();
                 self.new_message_recipient_valid = false;
                 self.new_message_sending = false;
+                self.new_message_sub_id = None;
+                self.outgoing_attachments.clear();
+                self.attachment_status = None;
             }
             Message::NewMessageRecipientInput(text) => {
                 self.new_message_recipient_valid = is_address_valid(&text);
@@ -1949,17 +3207,40 @@ impl Application for ConnectApplet {
                 tracing::debug!("Selected contact: {}", name);
             }
             Message::SendNewMessage => {
-                if let (Some(conn), Some(device_id)) = (&self.dbus_connection, &self.sms_device_id)
+                if let (Some(client), Some(device_id)) =
+                    (self.client.clone(), self.sms_device_id.clone())
                 {
                     if self.new_message_recipient_valid
-                        && !self.new_message_body.is_empty()
+                        && (!self.new_message_body.is_empty()
+                            || !self.outgoing_attachments.is_empty())
                         && !self.new_message_sending
                     {
                         let recipient = self.new_message_recipient.clone();
                         let message = self.new_message_body.clone();
+                        let attachment_urls =
+                            self.outgoing_attachments.iter().map(|a| a.url()).collect();
+                        let sub_id = self.new_message_sim();
+                        if let Some(picked) = self.new_message_sub_id {
+                            self.remember_sim(&[recipient.clone()], picked);
+                        }
                         self.new_message_sending = true;
+                        let pasted = pasted_images(&self.outgoing_attachments);
+                        let send = send_new_sms_async(
+                            client,
+                            device_id,
+                            recipient,
+                            message,
+                            attachment_urls,
+                            sub_id,
+                        );
                         return cosmic::app::Task::perform(
-                            send_new_sms_async(conn.clone(), device_id.clone(), recipient, message),
+                            async move {
+                                let result = send.await;
+                                if matches!(result, Message::NewMessageSendResult(Ok(_))) {
+                                    remove_pasted_images(pasted).await;
+                                }
+                                result
+                            },
                             cosmic::Action::App,
                         );
                     }
@@ -1974,24 +3255,29 @@ impl Application for ConnectApplet {
                         // Clear fields and return to conversation list
                         self.new_message_recipient.clear();
                         self.new_message_body.clear();
+                        self.outgoing_attachments.clear();
                         self.new_message_recipient_valid = false;
+                        self.new_message_sub_id = None;
                         self.view_mode = ViewMode::ConversationList;
                         // Refresh conversations to show the new thread
                         // Show loading state since new conversation won't be in cache
-                        if let (Some(conn), Some(device_id)) =
-                            (&self.dbus_connection, &self.sms_device_id)
+                        if let (Some(client), Some(device_id)) = (&self.client, &self.sms_device_id)
                         {
                             self.sms_loading_state =
                                 SmsLoadingState::LoadingConversations(LoadingPhase::Requesting);
                             return cosmic::app::Task::perform(
-                                fetch_conversations_async(conn.clone(), device_id.clone()),
+                                fetch_conversations_async(
+                                    client.clone(),
+                                    device_id.clone(),
+                                    self.sms_store.clone(),
+                                ),
                                 cosmic::Action::App,
                             );
                         }
                     }
                     Err(err) => {
                         tracing::error!("New message send error: {}", err);
-                        self.status_message = Some(format!("Send failed: {}", err));
+                        self.status_message = Some(format!("{}: {}", fl!("sms-failed"), err));
                     }
                 }
             }
@@ -2010,11 +3296,13 @@ impl Application for ConnectApplet {
                 self.media_info = None;
                 self.media_loading = true;
                 self.media_selected_player = None;
+                self.media_last_signal = None;
+                self.media_seek_position = None;
                 self.view_mode = ViewMode::MediaControls;
 
-                if let Some(conn) = &self.dbus_connection {
+                if let Some(client) = &self.client {
                     return cosmic::app::Task::perform(
-                        fetch_media_info_async(conn.clone(), device_id),
+                        fetch_media_info_async(client.clone(), device_id),
                         cosmic::Action::App,
                     );
                 }
@@ -2026,6 +3314,8 @@ impl Application for ConnectApplet {
                 self.media_info = None;
                 self.media_loading = false;
                 self.media_selected_player = None;
+                self.media_last_signal = None;
+                self.media_seek_position = None;
             }
             Message::MediaInfoLoaded(info) => {
                 self.media_loading = false;
@@ -2040,125 +3330,168 @@ impl Application for ConnectApplet {
                     (info, _) => info,
                 };
             }
-            Message::MediaPlayPause => {
-                if let (Some(conn), Some(device_id)) =
-                    (&self.dbus_connection, &self.media_device_id)
-                {
+            Message::MediaPlay => return self.media_action(MediaAction::Play),
+            Message::MediaPause => return self.media_action(MediaAction::Pause),
+            Message::MediaStop => return self.media_action(MediaAction::Stop),
+            Message::MediaNext => return self.media_action(MediaAction::Next),
+            Message::MediaPrevious => return self.media_action(MediaAction::Previous),
+            Message::MediaSetVolume(volume) => {
+                if let (Some(client), Some(device_id)) = (&self.client, &self.media_device_id) {
+                    // Update local state immediately for responsive UI
+                    if let Some(ref mut info) = self.media_info {
+                        info.volume = volume;
+                    }
                     let ensure_player = self.media_selected_player.clone();
                     return cosmic::app::Task::perform(
                         media_action_async(
-                            conn.clone(),
+                            client.clone(),
                             device_id.clone(),
-                            MediaAction::PlayPause,
+                            MediaAction::SetVolume(volume),
                             ensure_player,
                         ),
                         cosmic::Action::App,
                     );
                 }
             }
-            Message::MediaNext => {
-                if let (Some(conn), Some(device_id)) =
-                    (&self.dbus_connection, &self.media_device_id)
-                {
-                    let ensure_player = self.media_selected_player.clone();
-                    return cosmic::app::Task::perform(
-                        media_action_async(
-                            conn.clone(),
-                            device_id.clone(),
-                            MediaAction::Next,
-                            ensure_player,
-                        ),
-                        cosmic::Action::App,
-                    );
+            Message::MediaSetLoopStatus(status) => {
+                // Update local state immediately for responsive UI
+                if let Some(ref mut info) = self.media_info {
+                    info.loop_status = Some(status);
                 }
+                return self.media_action(MediaAction::SetLoopStatus(status));
             }
-            Message::MediaPrevious => {
-                if let (Some(conn), Some(device_id)) =
-                    (&self.dbus_connection, &self.media_device_id)
-                {
-                    let ensure_player = self.media_selected_player.clone();
-                    return cosmic::app::Task::perform(
-                        media_action_async(
-                            conn.clone(),
-                            device_id.clone(),
-                            MediaAction::Previous,
-                            ensure_player,
-                        ),
-                        cosmic::Action::App,
-                    );
+            Message::MediaSetShuffle(shuffle) => {
+                if let Some(ref mut info) = self.media_info {
+                    info.shuffle = Some(shuffle);
                 }
+                return self.media_action(MediaAction::SetShuffle(shuffle));
             }
-            Message::MediaSetVolume(volume) => {
-                if let (Some(conn), Some(device_id)) =
-                    (&self.dbus_connection, &self.media_device_id)
-                {
-                    // Update local state immediately for responsive UI
+            Message::MediaSelectPlayer(player) => {
+                if let (Some(client), Some(device_id)) = (&self.client, &self.media_device_id) {
+                    // Track user's explicit selection (persists until view is closed)
+                    self.media_selected_player = Some(player.clone());
+                    // Update local state immediately
                     if let Some(ref mut info) = self.media_info {
-                        info.volume = volume;
+                        info.current_player = player.clone();
                     }
-                    let ensure_player = self.media_selected_player.clone();
                     return cosmic::app::Task::perform(
                         media_action_async(
-                            conn.clone(),
+                            client.clone(),
                             device_id.clone(),
-                            MediaAction::SetVolume(volume),
-                            ensure_player,
+                            MediaAction::SelectPlayer(player),
+                            None, // SelectPlayer doesn't need ensure_player
                         ),
                         cosmic::Action::App,
                     );
                 }
             }
-            Message::MediaSelectPlayer(player) => {
-                if let (Some(conn), Some(device_id)) =
-                    (&self.dbus_connection, &self.media_device_id)
-                {
-                    // Track user's explicit selection (persists until view is closed)
-                    self.media_selected_player = Some(player.clone());
-                    // Update local state immediately
+            Message::MediaSeek(position) => {
+                self.media_seek_position = Some(position);
+            }
+            Message::MediaSeekRelease => {
+                let Some(position) = self.media_seek_position.take() else {
+                    return cosmic::app::Task::none();
+                };
+                if let (Some(client), Some(device_id)) = (&self.client, &self.media_device_id) {
+                    // Update local state immediately so the slider doesn't jump back
                     if let Some(ref mut info) = self.media_info {
-                        info.current_player = player.clone();
+                        info.position = i64::from(position);
+                        info.position_time = Some(std::time::Instant::now());
                     }
+                    let ensure_player = self.media_selected_player.clone();
                     return cosmic::app::Task::perform(
                         media_action_async(
-                            conn.clone(),
+                            client.clone(),
                             device_id.clone(),
-                            MediaAction::SelectPlayer(player),
-                            None, // SelectPlayer doesn't need ensure_player
+                            MediaAction::SetPosition(position),
+                            ensure_player,
                         ),
                         cosmic::Action::App,
                     );
                 }
             }
+            // The view interpolates the position; updating is enough to redraw it
+            Message::MediaPositionTick => {}
             Message::MediaActionResult(result) => {
                 if let Err(err) = result {
-                    self.status_message = Some(format!("Media error: {}", err));
+                    self.status_message = Some(format!("{}: {}", fl!("media-action-failed"), err));
                 }
-                // Refresh media info after action
-                if let (Some(conn), Some(device_id)) =
-                    (&self.dbus_connection, &self.media_device_id)
-                {
-                    return cosmic::app::Task::perform(
-                        fetch_media_info_async(conn.clone(), device_id.clone()),
-                        cosmic::Action::App,
-                    );
+                // The player signals the change itself, unless it hasn't
+                // signalled anything since the view was opened
+                if self.media_last_signal.is_none() {
+                    if let (Some(client), Some(device_id)) = (&self.client, &self.media_device_id) {
+                        return cosmic::app::Task::perform(
+                            fetch_media_info_async(client.clone(), device_id.clone()),
+                            cosmic::Action::App,
+                        );
+                    }
                 }
             }
             Message::MediaRefresh => {
-                // Auto-refresh when in media view
-                if self.view_mode == ViewMode::MediaControls {
-                    if let (Some(conn), Some(device_id)) =
-                        (&self.dbus_connection, &self.media_device_id)
-                    {
+                // Only poll when the player has gone quiet, in case its signals are lost
+                let interval =
+                    std::time::Duration::from_secs(refresh::MEDIA_FALLBACK_INTERVAL_SECS);
+                let quiet = self
+                    .media_last_signal
+                    .map_or(true, |last| last.elapsed() >= interval);
+                if self.view_mode == ViewMode::MediaControls && quiet {
+                    if let (Some(client), Some(device_id)) = (&self.client, &self.media_device_id) {
                         return cosmic::app::Task::perform(
-                            fetch_media_info_async(conn.clone(), device_id.clone()),
+                            fetch_media_info_async(client.clone(), device_id.clone()),
                             cosmic::Action::App,
                         );
                     }
                 }
             }
+            Message::MediaPropertiesChanged(device_id, changes) => {
+                if self.media_device_id.as_ref() != Some(&device_id) {
+                    return cosmic::app::Task::none();
+                }
+                self.media_last_signal = Some(std::time::Instant::now());
+                match &mut self.media_info {
+                    Some(info) if !changes.is_empty() => {
+                        let art_url = changes
+                            .album_art_url
+                            .clone()
+                            .filter(|url| url != &info.album_art_url && !url.is_empty());
+                        apply_media_changes(info, changes, std::time::Instant::now());
+                        // Preserve user's explicit player selection if set
+                        if let Some(selected) = &self.media_selected_player {
+                            if info.players.contains(selected) {
+                                info.current_player = selected.clone();
+                            }
+                        }
+                        if let Some(url) = art_url {
+                            return cosmic::app::Task::perform(
+                                cache_album_art_async(url),
+                                cosmic::Action::App,
+                            );
+                        }
+                    }
+                    // Signals without values, or nothing loaded yet: read the whole state
+                    _ => {
+                        if let Some(client) = &self.client {
+                            return cosmic::app::Task::perform(
+                                fetch_media_info_async(client.clone(), device_id),
+                                cosmic::Action::App,
+                            );
+                        }
+                    }
+                }
+            }
+            Message::MediaAlbumArtLoaded(url, path) => {
+                // The track may have changed again in the meantime
+                if let Some(info) = self.media_info.as_mut().filter(|i| i.album_art_url == url) {
+                    info.album_art = path;
+                }
+            }
 
             // SMS Notifications
             Message::SmsNotificationReceived(device_id, message) => {
+                if !self.config.sms_notifications {
+                    return cosmic::app::Task::none();
+                }
+
                 // Freshness check: only notify for messages received within the last 30 seconds.
                 // This prevents false notifications when fetching historical messages and handles
                 // cross-process deduplication (COSMIC spawns multiple applet instances).
@@ -2182,59 +3515,97 @@ impl Application for ConnectApplet {
                 // Update last seen timestamp for this thread
                 self.last_seen_sms.insert(message.thread_id, message.date);
 
-                // Capture config settings for the async block
-                let show_sender = self.config.sms_notification_show_sender;
-                let show_content = self.config.sms_notification_show_content;
-                let message_body = message.body.clone();
-                let primary_address = message.primary_address().to_string();
-
                 // Show notification asynchronously (loads contacts without blocking UI)
                 return cosmic::app::Task::perform(
-                    async move {
-                        // Load contacts asynchronously to resolve sender name
-                        let contacts = ContactLookup::load_for_device(&device_id).await;
-                        let sender_name = contacts.get_name_or_number(&primary_address);
-
-                        // Build notification based on privacy settings
-                        let summary = if show_sender {
-                            fl!("sms-notification-title-from", sender = sender_name)
-                        } else {
-                            fl!("sms-notification-title")
-                        };
-
-                        let body = if show_content {
-                            message_body
-                        } else {
-                            fl!("sms-notification-body-hidden")
-                        };
-
-                        // Use spawn_blocking to run notify_rust in a blocking context
-                        // to avoid "Cannot start a runtime from within a runtime" panics
-                        let result = tokio::task::spawn_blocking(move || {
-                            notify_rust::Notification::new()
-                                .summary(&summary)
-                                .body(&body)
-                                .icon("phone-symbolic")
-                                .appname("Connected")
-                                .show()
+                    show_sms_notification_async(
+                        self.client.as_ref().map(|c| c.connection()),
+                        device_id,
+                        message,
+                        self.config.sms_notification_show_sender,
+                        self.config.sms_notification_show_content,
+                    ),
+                    cosmic::Action::App,
+                );
+            }
+            Message::SmsNotificationShown(thread, result) => match result {
+                Ok(desktop_id) => {
+                    self.sms_notification_threads.insert(desktop_id, thread);
+                }
+                Err(err) => tracing::warn!("Failed to show SMS notification: {}", err),
+            },
+            Message::DesktopNotificationReplied(desktop_id, text) => {
+                let (Some(client), Some(thread)) = (
+                    &self.client,
+                    self.sms_notification_threads.get(&desktop_id).cloned(),
+                ) else {
+                    return cosmic::app::Task::none();
+                };
+                if text.trim().is_empty() {
+                    return cosmic::app::Task::none();
+                }
+                let thread_id = thread.thread_id;
+                tracing::info!("Replying to SMS thread {} from notification", thread_id);
+                return cosmic::app::Task::perform(
+                    send_sms_async(
+                        client.clone(),
+                        thread.device_id,
+                        thread_id,
+                        thread.addresses,
+                        text,
+                        Vec::new(),
+                        thread.sub_id,
+                    ),
+                    // Keep the result apart from sends of the compose field
+                    move |message| {
+                        cosmic::Action::App(match message {
+                            Message::SmsSendResult(result) => {
+                                Message::SmsNotificationReplyResult(thread_id, result)
+                            }
+                            other => other,
                         })
-                        .await;
-
-                        if let Ok(Err(e)) = result {
-                            tracing::warn!("Failed to show SMS notification: {}", e);
-                        }
                     },
-                    |_| cosmic::Action::App(Message::RefreshDevices),
                 );
             }
+            Message::SmsNotificationReplyResult(thread_id, result) => match result {
+                Ok(sent_body) => {
+                    self.status_message = Some(fl!("reply-sent"));
+                    if let Some(conv) = self
+                        .conversations
+                        .iter_mut()
+                        .find(|c| c.thread_id == thread_id)
+                    {
+                        conv.last_message = sent_body;
+                    }
+                    // The open thread picks the reply up from the phone
+                    self.message_cache.pop(&thread_id);
+                    return cosmic::app::Task::perform(
+                        async move {
+                            tokio::time::sleep(std::time::Duration::from_secs(
+                                refresh::POST_SEND_DELAY_SECS,
+                            ))
+                            .await;
+                            thread_id
+                        },
+                        |tid| cosmic::Action::App(Message::DelayedMessageRefresh(tid)),
+                    );
+                }
+                Err(err) => {
+                    tracing::error!("SMS reply from notification failed: {}", err);
+                    self.status_message = Some(format!("{}: {}", fl!("sms-failed"), err));
+                }
+            },
 
             // Call Notifications
             Message::CallNotification {
-                device_name,
+                device_id,
                 event,
                 phone_number,
                 contact_name,
             } => {
+                if !self.config.call_notifications {
+                    return cosmic::app::Task::none();
+                }
+
                 // Build notification based on event type and privacy settings
                 let (summary, icon, urgency) = match event.as_str() {
                     "callReceived" => {
@@ -2269,6 +3640,14 @@ impl Application for ConnectApplet {
                     }
                 };
 
+                // Look up actual device name from cached devices
+                let device_name = self
+                    .devices
+                    .iter()
+                    .find(|d| d.id == device_id)
+                    .map(|d| d.name.clone())
+                    .unwrap_or(device_id);
+
                 tracing::info!(
                     "Call notification: {} - {} from {}",
                     event,
@@ -2378,6 +3757,16 @@ impl Application for ConnectApplet {
             _ => DEFAULT_POPUP_WIDTH,
         };
 
+        // Without a daemon there is nothing else to show
+        if self.daemon_state != DaemonState::Running {
+            let content = view_daemon_status(
+                self.daemon_state,
+                self.daemon_starting,
+                self.status_message.as_deref(),
+            );
+            return popup_container(content, popup_width, self.core.applet.anchor);
+        }
+
         // Handle error state first
         if let Some(err) = &self.error {
             let content: Element<Message> = widget::container(
@@ -2402,7 +3791,7 @@ impl Application for ConnectApplet {
 
         // Route to appropriate view based on view mode
         let content: Element<Message> = match &self.view_mode {
-            ViewMode::Settings => view_settings(&self.config),
+            ViewMode::Settings => view_settings(&self.config, &self.rule_draft),
             ViewMode::ConversationList => view_conversation_list(ConversationListParams {
                 device_name: self.sms_device_name.as_deref(),
                 conversations: &self.conversations,
@@ -2410,6 +3799,10 @@ impl Application for ConnectApplet {
                 contacts: &self.contacts,
                 loading_state: &self.sms_loading_state,
                 sync_active: self.conversation_sync_active,
+                search_query: &self.sms_search_query,
+                search_results: &self.sms_search_results,
+                export_menu_open: self.sms_export_menu_open,
+                export_status: self.sms_export_status.as_deref(),
             }),
             ViewMode::MessageThread => view_message_thread(MessageThreadParams {
                 thread_addresses: self.current_thread_addresses.as_deref(),
@@ -2421,6 +3814,14 @@ impl Application for ConnectApplet {
                 sync_active: self.message_sync_active,
                 pressed_bubble_uid: self.pressed_bubble_uid,
                 show_copy_hint: self.show_copy_hint,
+                highlighted_uid: self.highlighted_message_uid,
+                export_menu_open: self.sms_export_menu_open,
+                export_status: self.sms_export_status.as_deref(),
+                thumbnails: &self.attachment_thumbnails,
+                attachment_status: self.attachment_status.as_deref(),
+                outgoing_attachments: &self.outgoing_attachments,
+                sims: &self.sms_sims,
+                sub_id: self.current_thread_sub_id,
             }),
             ViewMode::NewMessage => view_new_message(NewMessageParams {
                 recipient: &self.new_message_recipient,
@@ -2428,11 +3829,28 @@ impl Application for ConnectApplet {
                 recipient_valid: self.new_message_recipient_valid,
                 sending: self.new_message_sending,
                 contact_suggestions: &self.contact_suggestions,
+                attachments: &self.outgoing_attachments,
+                attachment_status: self.attachment_status.as_deref(),
+                sims: &self.sms_sims,
+                sub_id: self.new_message_sim(),
             }),
             ViewMode::MediaControls => view_media_controls(MediaControlsParams {
                 device_name: self.media_device_name.as_deref(),
                 media_info: self.media_info.as_ref(),
                 media_loading: self.media_loading,
+                seek_position: self.media_seek_position,
+                now: std::time::Instant::now(),
+            }),
+            ViewMode::NotificationHistory => view_notification_history(NotificationHistoryParams {
+                device_name: self
+                    .selected_device
+                    .as_ref()
+                    .and_then(|id| self.devices.iter().find(|d| &d.id == id))
+                    .map(|d| d.name.as_str()),
+                entries: &self.notification_history,
+                search_query: &self.notification_history_search,
+                retention_days: self.config.notification_history_days,
+                status_message: self.status_message.as_deref(),
             }),
             ViewMode::SendTo => view_send_to(SendToParams {
                 device_type: self.sendto_device_type.as_deref().unwrap_or("device"),
@@ -2443,11 +3861,17 @@ impl Application for ConnectApplet {
             ViewMode::DevicePage => {
                 if let Some(device_id) = &self.selected_device {
                     if let Some(device) = self.devices.iter().find(|d| &d.id == device_id) {
-                        ui::device_page::view(device, self.status_message.as_deref())
+                        ui::device_page::view(
+                            device,
+                            self.status_message.as_deref(),
+                            self.notification_reply.as_ref(),
+                            &self.notification_filters,
+                        )
                     } else {
                         ui::device_list::view(
                             &self.devices,
                             &self.config,
+                            &self.notification_filters,
                             self.status_message.as_deref(),
                         )
                     }
@@ -2455,6 +3879,7 @@ impl Application for ConnectApplet {
                     ui::device_list::view(
                         &self.devices,
                         &self.config,
+                        &self.notification_filters,
                         self.status_message.as_deref(),
                     )
                 }
@@ -2478,6 +3903,7 @@ impl Application for ConnectApplet {
                     ui::device_list::view(
                         &self.devices,
                         &self.config,
+                        &self.notification_filters,
                         self.status_message.as_deref(),
                     )
                 }
@@ -2489,8 +3915,6 @@ impl Application for ConnectApplet {
 
     fn subscription(&self) -> Subscription<Self::Message> {
         let mut subscriptions = vec![
-            // Subscribe to D-Bus signals for device state changes
-            Subscription::run(dbus_signal_subscription),
             // Watch for config changes from external sources
             self.core
                 .watch_config::<Config>(crate::config::APP_ID)
@@ -2502,28 +3926,56 @@ impl Application for ConnectApplet {
                 }),
         ];
 
-        // Add media refresh timer when in media view
+        // Subscribe to KDE Connect events for device state changes and notifications
+        // Runs on the shared connection, so it starts once that is established
+        let Some(client) = self.client.clone() else {
+            return Subscription::batch(subscriptions);
+        };
+        subscriptions.push(Subscription::run_with_id(
+            "kdeconnect_events",
+            event_subscription(client.clone()),
+        ));
+
+        // Watch the actions and closing of mirrored phone and SMS notifications
+        if self.config.forward_notifications || self.config.sms_notifications {
+            subscriptions.push(Subscription::run_with_id(
+                "desktop_notifications",
+                desktop_notification_subscription(client.connection()),
+            ));
+        }
+
+        // Let desktop media controls drive the players of connected phones
+        if self.config.media_player_bridge {
+            let connected = self
+                .devices
+                .iter()
+                .filter(|d| d.is_reachable && d.is_paired);
+            for device in connected {
+                let (id, name) = (device.id.clone(), device.name.clone());
+                subscriptions.push(Subscription::run_with_id(
+                    ("mpris_bridge", id.clone(), self.daemon_generation),
+                    mpris_bridge_subscription(client.clone(), id, name),
+                ));
+            }
+        }
+
+        // Player changes arrive as events; poll slowly in case they stop
         if self.view_mode == ViewMode::MediaControls {
             subscriptions.push(
                 cosmic::iced::time::every(std::time::Duration::from_secs(
-                    refresh::MEDIA_INTERVAL_SECS,
+                    refresh::MEDIA_FALLBACK_INTERVAL_SECS,
                 ))
                 .map(|_| Message::MediaRefresh),
             );
-        }
-
-        // Add SMS notification subscription when enabled and devices are connected
-        if self.config.sms_notifications
-            && self.devices.iter().any(|d| d.is_reachable && d.is_paired)
-        {
-            subscriptions.push(Subscription::run(sms_notification_subscription));
-        }
-
-        // Add call notification subscription when enabled and devices are connected
-        if self.config.call_notifications
-            && self.devices.iter().any(|d| d.is_reachable && d.is_paired)
-        {
-            subscriptions.push(Subscription::run(call_notification_subscription));
+            let playing = self.media_info.as_ref().is_some_and(|info| info.is_playing);
+            if playing {
+                subscriptions.push(
+                    cosmic::iced::time::every(std::time::Duration::from_secs(
+                        refresh::MEDIA_POSITION_INTERVAL_SECS,
+                    ))
+                    .map(|_| Message::MediaPositionTick),
+                );
+            }
         }
 
         // Add conversation list subscription for incremental loading
@@ -2531,8 +3983,12 @@ impl Application for ConnectApplet {
         if self.conversation_list_subscription_active {
             if let Some(device_id) = self.sms_device_id.clone() {
                 subscriptions.push(Subscription::run_with_id(
-                    ("conversation_list", device_id.clone()),
-                    conversation_list_subscription(device_id),
+                    (
+                        "conversation_list",
+                        device_id.clone(),
+                        self.daemon_generation,
+                    ),
+                    conversation_list_subscription(client.clone(), device_id),
                 ));
             }
         }
@@ -2546,15 +4002,17 @@ impl Application for ConnectApplet {
             {
                 let messages_per_page = self.config.messages_per_page;
                 subscriptions.push(Subscription::run_with_id(
-                    ("conversation_messages", thread_id),
-                    conversation_message_subscription(thread_id, device_id, messages_per_page),
+                    ("conversation_messages", thread_id, self.daemon_generation),
+                    conversation_message_subscription(
+                        client.clone(),
+                        thread_id,
+                        device_id,
+                        messages_per_page,
+                    ),
                 ));
             }
         }
 
-        // Note: File notifications are handled in the main dbus_signal_subscription
-        // to avoid issues with multiple D-Bus connections and match rules
-
         Subscription::batch(subscriptions)
     }
 
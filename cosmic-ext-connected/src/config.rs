@@ -1,7 +1,9 @@
 //! Configuration management for the Connected applet.
 
+use crate::device::filter::NotificationRule;
 use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Application ID for configuration storage.
 pub const APP_ID: &str = "io.github.nwxnw.connected";
@@ -32,6 +34,15 @@ pub struct Config {
     pub call_notification_show_name: bool,
     /// Enable desktop notifications for received files
     pub file_notifications: bool,
+    /// SIM subscription ID to send with, by device and recipients
+    /// (see `sms::sim_default_key`)
+    pub sms_sim_defaults: BTreeMap<String, i64>,
+    /// Rules hiding phone notifications, by device ID, checked in order
+    pub notification_rules: BTreeMap<String, Vec<NotificationRule>>,
+    /// Days phone notifications are kept in the history
+    pub notification_history_days: u32,
+    /// Publish the phone's media player as a desktop MPRIS player
+    pub media_player_bridge: bool,
 }
 
 impl Default for Config {
@@ -48,6 +59,10 @@ impl Default for Config {
             call_notification_show_number: true,
             call_notification_show_name: true,
             file_notifications: true,
+            sms_sim_defaults: BTreeMap::new(),
+            notification_rules: BTreeMap::new(),
+            notification_history_days: 7,
+            media_player_bridge: true,
         }
     }
 }
@@ -57,9 +72,14 @@ impl Config {
     pub fn load() -> Self {
         match cosmic_config::Config::new(APP_ID, Self::VERSION) {
             Ok(config_handler) => {
-                let config = Self::get_entry(&config_handler).unwrap_or_else(|err| {
-                    tracing::error!(?err, "Failed to load config, using defaults");
-                    Self::default()
+                // Entries that are missing (e.g. added since the config was
+                // written) or unreadable keep their defaults
+                let config = Self::get_entry(&config_handler).unwrap_or_else(|(errors, config)| {
+                    tracing::warn!(
+                        ?errors,
+                        "Some config entries failed to load, using defaults"
+                    );
+                    config
                 });
                 tracing::info!("Loaded config: {:?}", config);
                 config
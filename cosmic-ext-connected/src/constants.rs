@@ -11,6 +11,13 @@ pub mod dbus {
     /// Debounce interval for device refresh after D-Bus signals (seconds).
     /// Prevents rapid refreshes when multiple signals arrive in quick succession.
     pub const SIGNAL_REFRESH_DEBOUNCE_SECS: u64 = 3;
+
+    /// Delay after asking to start the daemon before checking that it runs (seconds).
+    pub const DAEMON_START_CHECK_SECS: u64 = 5;
+
+    /// Delay after the daemon went away before asking systemd how it ended (milliseconds).
+    /// The daemon releases its bus name before systemd sees the process exit.
+    pub const DAEMON_EXIT_CHECK_MS: u64 = 500;
 }
 
 /// SMS conversation and message loading constants.
@@ -46,6 +53,29 @@ pub mod sms {
     /// Maximum number of conversation message threads to cache.
     /// When this limit is reached, the least recently accessed conversation is evicted.
     pub const MESSAGE_CACHE_MAX_CONVERSATIONS: usize = 10;
+
+    /// Maximum number of SMS search results shown.
+    pub const MAX_SEARCH_RESULTS: usize = 50;
+
+    /// Characters of context shown on each side of an SMS search match.
+    pub const SEARCH_SNIPPET_CONTEXT_CHARS: usize = 20;
+
+    /// Pause in typing before the SMS search runs (milliseconds).
+    pub const SEARCH_DEBOUNCE_MS: u64 = 250;
+
+    /// Timeout for downloading an MMS attachment from the phone (seconds).
+    pub const ATTACHMENT_DOWNLOAD_TIMEOUT_SECS: u64 = 60;
+
+    /// Size of MMS attachment thumbnails in message bubbles (pixels).
+    pub const ATTACHMENT_THUMBNAIL_SIZE: f32 = 160.0;
+
+    /// Maximum total size of the files attached to an outgoing MMS (bytes).
+    /// The daemon sends them base64 encoded in a single packet; the phone
+    /// recompresses images to the carrier's limit.
+    pub const MAX_OUTGOING_ATTACHMENT_BYTES: u64 = 5 * 1024 * 1024;
+
+    /// Age after which a pasted image that was never sent is deleted (seconds).
+    pub const PASTED_IMAGE_MAX_AGE_SECS: u64 = 24 * 60 * 60;
 }
 
 /// Refresh and polling interval constants.
@@ -53,12 +83,34 @@ pub mod refresh {
     /// Delay after sending SMS before refreshing the thread (seconds).
     pub const POST_SEND_DELAY_SECS: u64 = 2;
 
-    /// Interval for refreshing media player state (seconds).
-    pub const MEDIA_INTERVAL_SECS: u64 = 2;
+    /// Interval for polling media player state when its signals have gone
+    /// quiet (seconds).
+    pub const MEDIA_FALLBACK_INTERVAL_SECS: u64 = 10;
+
+    /// Interval for redrawing the interpolated playback position (seconds).
+    pub const MEDIA_POSITION_INTERVAL_SECS: u64 = 1;
 }
 
 /// Notification display constants.
 pub mod notifications {
     /// Timeout for file received notifications (milliseconds).
     pub const FILE_TIMEOUT_MS: u32 = 5000;
+
+    /// Days a cached phone notification icon is kept after its last use.
+    pub const ICON_CACHE_MAX_AGE_DAYS: u64 = 7;
+
+    /// Most notifications kept in the history of a device.
+    pub const HISTORY_MAX_ENTRIES: usize = 500;
+
+    /// Retention choices for the notification history, in days.
+    pub const HISTORY_RETENTION_DAYS: [u32; 4] = [1, 7, 30, 90];
+}
+
+/// Media control constants.
+pub mod media {
+    /// Days a cached album art image is kept after its last use.
+    pub const ALBUM_ART_CACHE_MAX_AGE_DAYS: u64 = 30;
+
+    /// Size of the album art in the media controls (pixels).
+    pub const ALBUM_ART_SIZE: f32 = 96.0;
 }
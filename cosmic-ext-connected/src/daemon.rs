@@ -0,0 +1,97 @@
+//! Health of the KDE Connect daemon and starting it from the applet.
+
+use crate::app::{DaemonState, Message};
+use crate::constants::dbus::{DAEMON_EXIT_CHECK_MS, DAEMON_START_CHECK_SECS};
+use crate::fl;
+use kdeconnect_dbus::{DaemonExit, DaemonProxy, DaemonStatus, KdeConnectClient};
+
+/// Check whether the daemon is installed and running.
+pub async fn check_daemon_async(client: KdeConnectClient) -> Message {
+    match DaemonProxy::status(&client.connection()).await {
+        Ok(DaemonStatus::Running) => Message::DaemonStateChanged(DaemonState::Running),
+        Ok(DaemonStatus::NotRunning) => Message::DaemonStateChanged(DaemonState::NotRunning),
+        Ok(DaemonStatus::NotInstalled) => Message::DaemonStateChanged(DaemonState::NotInstalled),
+        Err(e) => {
+            // Can't tell; let the device fetch report what is wrong
+            tracing::warn!("Failed to check KDE Connect daemon status: {}", e);
+            Message::DaemonStateChanged(DaemonState::Running)
+        }
+    }
+}
+
+/// Find out whether the daemon that just went away crashed or was quit.
+pub async fn daemon_stopped_async(client: KdeConnectClient) -> Message {
+    tokio::time::sleep(tokio::time::Duration::from_millis(DAEMON_EXIT_CHECK_MS)).await;
+    let conn = client.connection();
+    // It may have been restarted in the meantime
+    if let Ok(DaemonStatus::Running) = DaemonProxy::status(&conn).await {
+        return Message::DaemonStateChanged(DaemonState::Running);
+    }
+    match DaemonProxy::last_exit(&conn).await {
+        DaemonExit::Crashed => Message::DaemonStateChanged(DaemonState::Crashed),
+        DaemonExit::Clean => Message::DaemonStateChanged(DaemonState::NotRunning),
+    }
+}
+
+/// Start the daemon, then check that it came up.
+///
+/// The event subscription reports the daemon as soon as it claims its bus
+/// name; the delayed check covers starts that fail after being accepted.
+pub async fn start_daemon_async(client: KdeConnectClient) -> Message {
+    if let Err(e) = DaemonProxy::start(&client.connection()).await {
+        tracing::warn!("Failed to start KDE Connect daemon: {}", e);
+        return Message::DaemonStartFailed(fl!("daemon-start-failed", details = e.to_string()));
+    }
+    tracing::info!("KDE Connect daemon start requested");
+    tokio::time::sleep(tokio::time::Duration::from_secs(DAEMON_START_CHECK_SECS)).await;
+    check_daemon_async(client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kdeconnect_dbus::mock::MockDaemon;
+
+    #[tokio::test]
+    async fn check_reports_missing_and_stopped_daemon() {
+        let mock = MockDaemon::start().await.unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+
+        mock.set_running(false).await.unwrap();
+        assert!(matches!(
+            check_daemon_async(client.clone()).await,
+            Message::DaemonStateChanged(DaemonState::NotRunning)
+        ));
+
+        mock.set_installed(false).await.unwrap();
+        assert!(matches!(
+            check_daemon_async(client).await,
+            Message::DaemonStateChanged(DaemonState::NotInstalled)
+        ));
+    }
+
+    #[tokio::test]
+    async fn stopped_daemon_is_only_crashed_when_its_unit_failed() {
+        let mock = MockDaemon::start().await.unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+        mock.set_running(false).await.unwrap();
+
+        // Not run by systemd
+        assert!(matches!(
+            daemon_stopped_async(client.clone()).await,
+            Message::DaemonStateChanged(DaemonState::NotRunning)
+        ));
+
+        mock.set_unit(Some("inactive")).await.unwrap();
+        assert!(matches!(
+            daemon_stopped_async(client.clone()).await,
+            Message::DaemonStateChanged(DaemonState::NotRunning)
+        ));
+
+        mock.set_unit(Some("failed")).await.unwrap();
+        assert!(matches!(
+            daemon_stopped_async(client).await,
+            Message::DaemonStateChanged(DaemonState::Crashed)
+        ));
+    }
+}
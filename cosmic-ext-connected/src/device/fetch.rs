@@ -1,43 +1,29 @@
 //! Device fetching and information retrieval.
 
 use crate::app::{DeviceInfo, Message};
-use kdeconnect_dbus::{
-    plugins::{BatteryProxy, NotificationInfo, NotificationProxy, NotificationsProxy},
-    DaemonProxy, DeviceProxy,
-};
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use zbus::Connection;
+use crate::device::icons::cache_icons;
+use crate::errors::describe;
+use kdeconnect_dbus::{plugins::NotificationInfo, DeviceHandle, KdeConnectClient};
 
 /// Fetch all devices from the KDE Connect daemon via D-Bus.
-pub async fn fetch_devices_async(conn: Arc<Mutex<Connection>>) -> Message {
-    let conn = conn.lock().await;
-
-    // Get the daemon proxy
-    let daemon = match DaemonProxy::new(&conn).await {
-        Ok(d) => d,
-        Err(e) => {
-            return Message::Error(format!("Failed to connect to KDE Connect daemon: {}", e));
-        }
-    };
-
-    // Get list of all device IDs
-    let device_ids = match daemon.devices().await {
-        Ok(ids) => ids,
+pub async fn fetch_devices_async(client: KdeConnectClient) -> Message {
+    // Get handles for all known devices
+    let handles = match client.devices().await {
+        Ok(handles) => handles,
         Err(e) => {
-            return Message::Error(format!("Failed to get device list: {}", e));
+            return Message::Error(describe(&e));
         }
     };
 
-    tracing::debug!("Found {} device(s)", device_ids.len());
+    tracing::debug!("Found {} device(s)", handles.len());
 
     // Fetch info for each device
     let mut devices = Vec::new();
-    for device_id in device_ids {
-        match fetch_device_info(&conn, &device_id).await {
+    for handle in handles {
+        match fetch_device_info(&handle).await {
             Ok(info) => devices.push(info),
             Err(e) => {
-                tracing::warn!("Failed to get info for device {}: {}", device_id, e);
+                tracing::warn!("Failed to get info for device {}: {}", handle.id(), e);
             }
         }
     }
@@ -45,13 +31,33 @@ pub async fn fetch_devices_async(conn: Arc<Mutex<Connection>>) -> Message {
     Message::DevicesUpdated(devices)
 }
 
-/// Fetch information for a single device.
-pub async fn fetch_device_info(conn: &Connection, device_id: &str) -> Result<DeviceInfo, String> {
-    let device = DeviceProxy::for_device(conn, device_id)
+/// Re-read a single device after an event that didn't carry its new state.
+pub async fn fetch_device_async(client: KdeConnectClient, device_id: String) -> Message {
+    Message::DeviceUpdated(fetch_device_info(&client.device(&device_id)).await)
+}
+
+/// Read a notification that was posted or updated on a device.
+pub async fn fetch_notification_async(
+    client: KdeConnectClient,
+    device_id: String,
+    notification_id: String,
+) -> Message {
+    let mut result = client
+        .device(&device_id)
+        .notification(&notification_id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| describe(&e));
+    if let Ok(notification) = &mut result {
+        cache_icons(std::slice::from_mut(notification)).await;
+    }
+    Message::NotificationUpdated(device_id, result)
+}
 
-    let id = device_id.to_string();
+/// Fetch information for a single device.
+pub async fn fetch_device_info(handle: &DeviceHandle) -> Result<DeviceInfo, String> {
+    let device = handle.proxy().await.map_err(|e| e.to_string())?;
+
+    let id = handle.id().to_string();
     let name = device.name().await.map_err(|e| e.to_string())?;
     let device_type = device
         .device_type()
@@ -64,14 +70,14 @@ pub async fn fetch_device_info(conn: &Connection, device_id: &str) -> Result<Dev
 
     // Try to get battery info if available
     let (battery_level, battery_charging) = if is_reachable && is_paired {
-        fetch_battery_info(conn, device_id).await
+        fetch_battery_info(handle).await
     } else {
         (None, None)
     };
 
     // Fetch notifications if device is connected and paired
     let notifications = if is_reachable && is_paired {
-        fetch_notifications(conn, device_id).await
+        fetch_notifications(handle).await
     } else {
         Vec::new()
     };
@@ -91,142 +97,38 @@ pub async fn fetch_device_info(conn: &Connection, device_id: &str) -> Result<Dev
 }
 
 /// Fetch battery information for a device.
-pub async fn fetch_battery_info(conn: &Connection, device_id: &str) -> (Option<i32>, Option<bool>) {
-    let path = format!(
-        "{}/devices/{}/battery",
-        kdeconnect_dbus::BASE_PATH,
-        device_id
-    );
-
-    tracing::debug!("Fetching battery info from path: {}", path);
-
-    let builder = match BatteryProxy::builder(conn).path(path.as_str()) {
-        Ok(b) => b,
-        Err(e) => {
-            tracing::warn!("Failed to create battery proxy builder: {}", e);
-            return (None, None);
-        }
-    };
-
-    let battery = match builder.build().await {
-        Ok(b) => b,
-        Err(e) => {
-            tracing::warn!("Failed to build battery proxy: {}", e);
-            return (None, None);
-        }
-    };
-
-    let charge = match battery.charge().await {
-        Ok(c) => {
-            tracing::debug!("Battery charge: {}", c);
-            Some(c)
-        }
-        Err(e) => {
-            tracing::warn!("Failed to get battery charge: {}", e);
-            None
-        }
-    };
-
-    let is_charging = match battery.is_charging().await {
-        Ok(c) => {
-            tracing::debug!("Battery is_charging: {}", c);
-            Some(c)
+pub async fn fetch_battery_info(device: &DeviceHandle) -> (Option<i32>, Option<bool>) {
+    match device.battery_status().await {
+        Ok(status) => {
+            tracing::debug!(
+                "Battery charge: {}, charging: {}",
+                status.charge,
+                status.is_charging
+            );
+            (Some(status.charge), Some(status.is_charging))
         }
         Err(e) => {
-            tracing::warn!("Failed to get is_charging: {}", e);
-            None
+            tracing::warn!("Failed to get battery status for {}: {}", device.id(), e);
+            (None, None)
         }
-    };
-
-    (charge, is_charging)
+    }
 }
 
 /// Fetch notifications for a device.
-pub async fn fetch_notifications(conn: &Connection, device_id: &str) -> Vec<NotificationInfo> {
-    let notifications_path = format!(
-        "{}/devices/{}/notifications",
-        kdeconnect_dbus::BASE_PATH,
-        device_id
-    );
-
-    // Get the notifications proxy
-    let notifications_proxy = match NotificationsProxy::builder(conn)
-        .path(notifications_path.as_str())
-        .ok()
-        .map(|b| b.build())
-    {
-        Some(fut) => match fut.await {
-            Ok(p) => p,
-            Err(e) => {
-                tracing::warn!("Failed to create notifications proxy: {}", e);
-                return Vec::new();
-            }
-        },
-        None => {
-            tracing::warn!("Failed to build notifications proxy path");
-            return Vec::new();
+pub async fn fetch_notifications(device: &DeviceHandle) -> Vec<NotificationInfo> {
+    match device.notifications().await {
+        Ok(mut notifications) => {
+            tracing::debug!(
+                "Found {} notifications for device {}",
+                notifications.len(),
+                device.id()
+            );
+            cache_icons(&mut notifications).await;
+            notifications
         }
-    };
-
-    // Get list of active notification IDs
-    let notification_ids = match notifications_proxy.active_notifications().await {
-        Ok(ids) => ids,
         Err(e) => {
-            tracing::warn!("Failed to get active notifications: {}", e);
-            return Vec::new();
+            tracing::warn!("Failed to get notifications for {}: {}", device.id(), e);
+            Vec::new()
         }
-    };
-
-    tracing::debug!(
-        "Found {} notifications for device {}",
-        notification_ids.len(),
-        device_id
-    );
-
-    // Fetch info for each notification
-    let mut notifications = Vec::new();
-    for notif_id in notification_ids {
-        let notif_path = format!(
-            "{}/devices/{}/notifications/{}",
-            kdeconnect_dbus::BASE_PATH,
-            device_id,
-            notif_id
-        );
-
-        let notif_proxy = match NotificationProxy::builder(conn)
-            .path(notif_path.as_str())
-            .ok()
-            .map(|b| b.build())
-        {
-            Some(fut) => match fut.await {
-                Ok(p) => p,
-                Err(e) => {
-                    tracing::warn!(
-                        "Failed to create notification proxy for {}: {}",
-                        notif_id,
-                        e
-                    );
-                    continue;
-                }
-            },
-            None => continue,
-        };
-
-        let app_name = notif_proxy.app_name().await.unwrap_or_default();
-        let title = notif_proxy.title().await.unwrap_or_default();
-        let text = notif_proxy.text().await.unwrap_or_default();
-        let dismissable = notif_proxy.dismissable().await.unwrap_or(false);
-        let reply_id = notif_proxy.reply_id().await.unwrap_or_default();
-
-        notifications.push(NotificationInfo {
-            id: notif_id,
-            app_name,
-            title,
-            text,
-            dismissable,
-            repliable: !reply_id.is_empty(),
-        });
     }
-
-    notifications
 }
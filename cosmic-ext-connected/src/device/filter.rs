@@ -0,0 +1,205 @@
+//! Per-device rules for hiding phone notifications.
+//!
+//! Rules are checked in order and the first one that matches decides whether
+//! a notification is shown, both in the device page list and as a desktop
+//! notification. Notifications no rule matches are shown, so allow rules are
+//! only needed as exceptions to a broader block rule placed after them.
+
+use crate::fl;
+use kdeconnect_dbus::plugins::NotificationInfo;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// What a matching rule does with a notification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleAction {
+    /// Hide the notification.
+    #[default]
+    Block,
+    /// Show the notification.
+    Allow,
+}
+
+/// A notification filter rule, stored in the config.
+///
+/// Empty conditions match everything; a rule needs at least one condition.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationRule {
+    pub action: RuleAction,
+    /// App name, matched whole and ignoring case.
+    pub app_name: String,
+    /// Regular expression searched for in the title.
+    pub title_pattern: String,
+    /// Only match notifications the phone showed silently.
+    pub silent_only: bool,
+}
+
+impl NotificationRule {
+    /// Rule hiding every notification of an app.
+    pub fn mute(app_name: &str) -> Self {
+        Self {
+            action: RuleAction::Block,
+            app_name: app_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Check that the rule has a condition and a valid title pattern.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.app_name.trim().is_empty() && self.title_pattern.is_empty() && !self.silent_only {
+            return Err(fl!("notification-rule-empty"));
+        }
+        if !self.title_pattern.is_empty() {
+            Regex::new(&self.title_pattern).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// A rule ready to be matched against notifications.
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    action: RuleAction,
+    app_name: String,
+    title: Option<Regex>,
+    silent_only: bool,
+}
+
+impl CompiledRule {
+    fn new(rule: &NotificationRule) -> Result<Self, regex::Error> {
+        let title = if rule.title_pattern.is_empty() {
+            None
+        } else {
+            Some(Regex::new(&rule.title_pattern)?)
+        };
+        Ok(Self {
+            action: rule.action,
+            app_name: rule.app_name.trim().to_lowercase(),
+            title,
+            silent_only: rule.silent_only,
+        })
+    }
+
+    fn matches(&self, notification: &NotificationInfo) -> bool {
+        (self.app_name.is_empty() || notification.app_name.to_lowercase() == self.app_name)
+            && self
+                .title
+                .as_ref()
+                .map_or(true, |title| title.is_match(&notification.title))
+            && (!self.silent_only || notification.silent)
+    }
+}
+
+/// The rules of every device, compiled once per config change.
+#[derive(Debug, Clone, Default)]
+pub struct NotificationFilters {
+    by_device: HashMap<String, Vec<CompiledRule>>,
+}
+
+impl NotificationFilters {
+    /// Compile the configured rules; rules with an invalid pattern are skipped.
+    pub fn new(rules: &BTreeMap<String, Vec<NotificationRule>>) -> Self {
+        let by_device = rules
+            .iter()
+            .map(|(device_id, rules)| {
+                let compiled = rules
+                    .iter()
+                    .filter_map(|rule| match CompiledRule::new(rule) {
+                        Ok(compiled) => Some(compiled),
+                        Err(e) => {
+                            tracing::warn!("Skipping notification rule for {}: {}", device_id, e);
+                            None
+                        }
+                    })
+                    .collect();
+                (device_id.clone(), compiled)
+            })
+            .collect();
+        Self { by_device }
+    }
+
+    /// Whether a notification of a device should be shown.
+    pub fn allows(&self, device_id: &str, notification: &NotificationInfo) -> bool {
+        self.by_device
+            .get(device_id)
+            .and_then(|rules| rules.iter().find(|rule| rule.matches(notification)))
+            .map_or(true, |rule| rule.action == RuleAction::Allow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(app_name: &str, title: &str, silent: bool) -> NotificationInfo {
+        NotificationInfo {
+            id: "n1".to_string(),
+            app_name: app_name.to_string(),
+            title: title.to_string(),
+            text: String::new(),
+            icon_path: None,
+            dismissable: true,
+            repliable: false,
+            silent,
+        }
+    }
+
+    fn filters(rules: Vec<NotificationRule>) -> NotificationFilters {
+        NotificationFilters::new(&BTreeMap::from([("abc".to_string(), rules)]))
+    }
+
+    #[test]
+    fn muted_apps_are_hidden_on_that_device_only() {
+        let filters = filters(vec![NotificationRule::mute("Google Play Store")]);
+        let update = notification("google play store", "Updated 3 apps", false);
+        assert!(!filters.allows("abc", &update));
+        assert!(filters.allows("def", &update));
+        assert!(filters.allows("abc", &notification("Signal", "Alice", false)));
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let filters = filters(vec![
+            NotificationRule {
+                action: RuleAction::Allow,
+                app_name: "WhatsApp".to_string(),
+                title_pattern: "^Family".to_string(),
+                silent_only: false,
+            },
+            NotificationRule::mute("WhatsApp"),
+        ]);
+        assert!(filters.allows("abc", &notification("WhatsApp", "Family group", false)));
+        assert!(!filters.allows("abc", &notification("WhatsApp", "Work group", false)));
+    }
+
+    #[test]
+    fn title_and_silent_conditions() {
+        let filters = filters(vec![
+            NotificationRule {
+                title_pattern: "(?i)sale".to_string(),
+                ..Default::default()
+            },
+            NotificationRule {
+                silent_only: true,
+                ..Default::default()
+            },
+        ]);
+        assert!(!filters.allows("abc", &notification("Shop", "Big SALE today", false)));
+        assert!(!filters.allows("abc", &notification("Backup", "Done", true)));
+        assert!(filters.allows("abc", &notification("Backup", "Done", false)));
+    }
+
+    #[test]
+    fn rules_are_validated() {
+        assert!(NotificationRule::mute("Chat").validate().is_ok());
+        assert!(NotificationRule::default().validate().is_err());
+        let invalid = NotificationRule {
+            title_pattern: "(unclosed".to_string(),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+        // Rules edited by hand with an invalid pattern are skipped
+        assert!(filters(vec![invalid]).allows("abc", &notification("Chat", "(unclosed", false)));
+    }
+}
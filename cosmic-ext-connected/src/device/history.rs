@@ -0,0 +1,304 @@
+//! Local history of phone notifications, one file per device.
+//!
+//! A notification is gone from the phone's active list once dismissed, so
+//! every notification is recorded when it is posted or updated. The history
+//! is a JSON Lines file under the XDG data directory, oldest entry first,
+//! pruned to the retention setting and [`HISTORY_MAX_ENTRIES`] on each write.
+//!
+//! Every applet process sees the same notification signals, so writes take an
+//! exclusive `flock` and skip a notification whose last entry has the same
+//! content, whichever process recorded it.
+
+use crate::app::Message;
+use crate::constants::notifications::HISTORY_MAX_ENTRIES;
+use crate::log_store::{log_path, rewrite, write_line, LogLock};
+use kdeconnect_dbus::plugins::NotificationInfo;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A recorded phone notification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub notification_id: String,
+    pub app_name: String,
+    pub title: String,
+    pub text: String,
+    /// When the notification was recorded, in milliseconds since the epoch.
+    pub time: i64,
+}
+
+impl HistoryEntry {
+    fn new(notification: &NotificationInfo, time: i64) -> Self {
+        Self {
+            notification_id: notification.id.clone(),
+            app_name: notification.app_name.clone(),
+            title: notification.title.clone(),
+            text: notification.text.clone(),
+            time,
+        }
+    }
+
+    fn same_content(&self, other: &HistoryEntry) -> bool {
+        self.app_name == other.app_name && self.title == other.title && self.text == other.text
+    }
+}
+
+/// Entries matching `query` in the app name, title or text (case-insensitive),
+/// newest first.
+pub fn search<'a>(entries: &'a [HistoryEntry], query: &str) -> Vec<&'a HistoryEntry> {
+    let query = query.trim().to_lowercase();
+    entries
+        .iter()
+        .rev()
+        .filter(|entry| {
+            query.is_empty()
+                || [&entry.app_name, &entry.title, &entry.text]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(&query))
+        })
+        .collect()
+}
+
+/// History file of a device.
+fn history_path(device_id: &str) -> Option<PathBuf> {
+    log_path("notification-history", device_id)
+}
+
+/// Record a notification and return the device's history.
+pub async fn record_notification_async(
+    device_id: String,
+    notification: NotificationInfo,
+    retention_days: u32,
+) -> Message {
+    let path = history_path(&device_id);
+    let entries = tokio::task::spawn_blocking(move || {
+        let path = path?;
+        let entry = HistoryEntry::new(&notification, now_ms());
+        record_at(&path, entry, retention_days)
+            .map_err(|e| {
+                tracing::warn!("Failed to record notification in {}: {}", path.display(), e);
+            })
+            .ok()
+    })
+    .await
+    .ok()
+    .flatten();
+    match entries {
+        Some(entries) => Message::NotificationHistoryLoaded(device_id, entries),
+        // Show what was recorded before
+        None => load_history_async(device_id, retention_days).await,
+    }
+}
+
+/// Load the history of a device.
+pub async fn load_history_async(device_id: String, retention_days: u32) -> Message {
+    let path = history_path(&device_id);
+    let entries = tokio::task::spawn_blocking(move || {
+        let path = path?;
+        read_entries(&path)
+            .map_err(|e| {
+                tracing::warn!(
+                    "Failed to read notification history {}: {}",
+                    path.display(),
+                    e
+                );
+            })
+            .ok()
+    })
+    .await
+    .ok()
+    .flatten()
+    .unwrap_or_default();
+    // Entries past the retention are only pruned on the next write
+    let entries = retained(entries, retention_days, now_ms());
+    Message::NotificationHistoryLoaded(device_id, entries)
+}
+
+/// Delete the history of a device.
+pub async fn clear_history_async(device_id: String) -> Message {
+    let path = history_path(&device_id);
+    let result = tokio::task::spawn_blocking(move || match path {
+        Some(path) => clear_at(&path),
+        None => Ok(()),
+    })
+    .await;
+    let result = match result {
+        Ok(cleared) => cleared.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    Message::NotificationHistoryCleared(device_id, result)
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Entries within the retention and the size limit.
+fn retained(mut entries: Vec<HistoryEntry>, retention_days: u32, now: i64) -> Vec<HistoryEntry> {
+    let oldest = now - i64::from(retention_days) * 24 * 60 * 60 * 1000;
+    entries.retain(|entry| entry.time > oldest);
+    let excess = entries.len().saturating_sub(HISTORY_MAX_ENTRIES);
+    entries.drain(..excess);
+    entries
+}
+
+/// Append an entry to the history at `path`, pruning it, and return all
+/// retained entries.
+fn record_at(
+    path: &Path,
+    entry: HistoryEntry,
+    retention_days: u32,
+) -> io::Result<Vec<HistoryEntry>> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let _lock = LogLock::acquire(path)?;
+    let mut entries = read_entries(path)?;
+    let recorded = entries
+        .iter()
+        .rev()
+        .find(|e| e.notification_id == entry.notification_id)
+        .is_some_and(|last| last.same_content(&entry));
+    if recorded {
+        return Ok(retained(entries, retention_days, entry.time));
+    }
+
+    let now = entry.time;
+    entries.push(entry);
+    let total = entries.len();
+    let entries = retained(entries, retention_days, now);
+    if entries.len() == total {
+        append(path, &entries[total - 1])?;
+    } else {
+        rewrite(path, |out| {
+            entries.iter().try_for_each(|entry| write_line(out, entry))
+        })?;
+    }
+    Ok(entries)
+}
+
+/// Delete the history at `path`.
+fn clear_at(path: &Path) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let _lock = LogLock::acquire(path)?;
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Read a history file; a missing file reads as empty.
+fn read_entries(path: &Path) -> io::Result<Vec<HistoryEntry>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        match serde_json::from_str(&line?) {
+            Ok(entry) => entries.push(entry),
+            // A write cut short by a crash leaves a partial last line
+            Err(e) => tracing::debug!("Skipping unreadable notification history entry: {}", e),
+        }
+    }
+    Ok(entries)
+}
+
+fn append(path: &Path, entry: &HistoryEntry) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut out = BufWriter::new(file);
+    write_line(&mut out, entry)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+    fn temp_history() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "connected-notification-history-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("abc.jsonl")
+    }
+
+    fn entry(id: &str, title: &str, text: &str, time: i64) -> HistoryEntry {
+        HistoryEntry {
+            notification_id: id.to_string(),
+            app_name: "Chat".to_string(),
+            title: title.to_string(),
+            text: text.to_string(),
+            time,
+        }
+    }
+
+    #[test]
+    fn repeated_content_is_recorded_once() {
+        let path = temp_history();
+        let now = now_ms();
+        record_at(&path, entry("n1", "Alice", "Hi", now), 7).unwrap();
+        // Another applet process seeing the same signal
+        record_at(&path, entry("n1", "Alice", "Hi", now + 5), 7).unwrap();
+        // Messaging apps update one notification per conversation
+        record_at(&path, entry("n1", "Alice", "Are you there?", now + 10), 7).unwrap();
+
+        let entries = read_entries(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].text, "Are you there?");
+
+        clear_at(&path).unwrap();
+        assert!(read_entries(&path).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn history_is_pruned_to_retention_and_size() {
+        let path = temp_history();
+        let now = now_ms();
+        record_at(&path, entry("old", "Old", "", now - 8 * DAY_MS), 30).unwrap();
+        let entries = record_at(&path, entry("new", "New", "", now), 7).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(read_entries(&path).unwrap(), entries);
+
+        let many: Vec<HistoryEntry> = (0..HISTORY_MAX_ENTRIES + 5)
+            .map(|i| entry(&i.to_string(), "", "", now))
+            .collect();
+        let kept = retained(many, 7, now);
+        assert_eq!(kept.len(), HISTORY_MAX_ENTRIES);
+        assert_eq!(kept[0].notification_id, "5");
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn search_matches_any_field_newest_first() {
+        let entries = vec![
+            entry("n1", "Alice", "Dinner at eight?", 1),
+            entry("n2", "Bob", "Running late", 2),
+            entry("n3", "Alice", "See you", 3),
+        ];
+        let hits: Vec<&str> = search(&entries, "alice")
+            .iter()
+            .map(|e| e.notification_id.as_str())
+            .collect();
+        assert_eq!(hits, vec!["n3", "n1"]);
+        assert_eq!(search(&entries, "DINNER").len(), 1);
+        assert_eq!(search(&entries, "chat").len(), 3);
+        assert_eq!(search(&entries, " ").len(), 3);
+    }
+}
@@ -0,0 +1,128 @@
+//! Icons of phone notifications.
+//!
+//! The daemon saves the icon sent with a notification to a temporary
+//! directory, named after a hash of the image. Icons are kept in the
+//! [`file_cache`](crate::file_cache) for as long as a notification shows
+//! them, and for [`ICON_CACHE_MAX_AGE_DAYS`] after.
+//!
+//! Notifications without an icon get a themed icon picked by app name.
+
+use crate::constants::notifications::ICON_CACHE_MAX_AGE_DAYS;
+use crate::file_cache::{cache_dir, cache_file, remove_stale};
+use kdeconnect_dbus::plugins::NotificationInfo;
+use std::path::{Path, PathBuf};
+
+/// Themed icon for notifications of unknown apps.
+const DEFAULT_ICON: &str = "notification-symbolic";
+
+/// Themed icons by words in the app name, checked in order.
+const FALLBACK_ICONS: &[(&[&str], &str)] = &[
+    (
+        &[
+            "messag", "sms", "whatsapp", "signal", "telegram", "discord", "slack", "element",
+        ],
+        "mail-message-new-symbolic",
+    ),
+    (&["mail", "outlook"], "mail-unread-symbolic"),
+    (&["phone", "dialer"], "call-start-symbolic"),
+    (&["calendar"], "x-office-calendar-symbolic"),
+    (&["clock", "alarm"], "alarm-symbolic"),
+    (
+        &["music", "spotify", "podcast", "player"],
+        "audio-x-generic-symbolic",
+    ),
+    (&["photo", "camera", "gallery"], "camera-photo-symbolic"),
+    (&["maps", "navigation"], "mark-location-symbolic"),
+    (&["store", "update"], "system-software-update-symbolic"),
+];
+
+/// Themed icon for a notification that came without one.
+pub fn fallback_icon(app_name: &str) -> &'static str {
+    let app_name = app_name.to_lowercase();
+    FALLBACK_ICONS
+        .iter()
+        .find(|(words, _)| words.iter().any(|word| app_name.contains(word)))
+        .map(|(_, icon)| *icon)
+        .unwrap_or(DEFAULT_ICON)
+}
+
+/// Directory of the cached notification icons.
+fn icon_cache_dir() -> Option<PathBuf> {
+    cache_dir("notification-icons")
+}
+
+/// Point notifications at cached copies of their icons.
+pub async fn cache_icons(notifications: &mut [NotificationInfo]) {
+    let Some(dir) = icon_cache_dir() else {
+        return;
+    };
+    for notification in notifications {
+        if let Some(icon_path) = &notification.icon_path {
+            notification.icon_path = Some(cache_icon(&dir, icon_path).await);
+        }
+    }
+}
+
+/// Cached copy of an icon in `dir`.
+///
+/// Falls back to the daemon's file if the copy fails.
+async fn cache_icon(dir: &Path, source: &Path) -> PathBuf {
+    match cache_file(dir, source).await {
+        Ok(cached) => cached,
+        Err(e) => {
+            tracing::debug!("Failed to cache icon {}: {}", source.display(), e);
+            source.to_path_buf()
+        }
+    }
+}
+
+/// Remove cached icons that no notification has used for a while.
+pub fn remove_stale_icons() {
+    let Some(dir) = icon_cache_dir() else {
+        return;
+    };
+    let removed = remove_stale(&dir, ICON_CACHE_MAX_AGE_DAYS);
+    if removed > 0 {
+        tracing::info!("Removed {} stale notification icon(s)", removed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_cache::temp_dir;
+
+    #[test]
+    fn apps_without_icons_get_a_themed_one() {
+        assert_eq!(fallback_icon("WhatsApp"), "mail-message-new-symbolic");
+        assert_eq!(
+            fallback_icon("Google Messages"),
+            "mail-message-new-symbolic"
+        );
+        assert_eq!(fallback_icon("Gmail"), "mail-unread-symbolic");
+        assert_eq!(fallback_icon("Phone"), "call-start-symbolic");
+        assert_eq!(fallback_icon("Some Game"), DEFAULT_ICON);
+    }
+
+    #[tokio::test]
+    async fn icons_are_copied_once() {
+        let daemon_dir = temp_dir("icons-daemon");
+        let cache_dir = temp_dir("icons-cache").join("icons");
+        let source = daemon_dir.join("1a2b.png");
+        std::fs::write(&source, b"png").unwrap();
+
+        let cached = cache_icon(&cache_dir, &source).await;
+        assert_eq!(cached, cache_dir.join("1a2b.png"));
+        assert_eq!(std::fs::read(&cached).unwrap(), b"png");
+
+        // The copy stays usable after the daemon's file is gone
+        std::fs::remove_file(&source).unwrap();
+        assert_eq!(cache_icon(&cache_dir, &source).await, cached);
+
+        let missing = daemon_dir.join("3c4d.png");
+        assert_eq!(cache_icon(&cache_dir, &missing).await, missing);
+
+        let _ = std::fs::remove_dir_all(daemon_dir);
+        let _ = std::fs::remove_dir_all(cache_dir.parent().unwrap());
+    }
+}
@@ -0,0 +1,218 @@
+//! Mirroring phone notifications as desktop notifications.
+//!
+//! Newly posted phone notifications are shown through the desktop
+//! notification server, with "Dismiss on phone" and "Reply" actions when the
+//! phone offers them. The server reports invoked actions and closed
+//! notifications by the ID it assigned, so [`MirroredNotifications`] maps
+//! those IDs back to the phone notification.
+//!
+//! Only the applet process that showed a notification knows its desktop ID;
+//! that process alone updates, closes and handles actions for it.
+
+use crate::app::Message;
+use crate::device::icons::fallback_icon;
+use crate::fl;
+use kdeconnect_dbus::plugins::NotificationInfo;
+use kdeconnect_dbus::KdeConnectEvent;
+use std::collections::HashMap;
+
+/// Action key of the "Dismiss on phone" action.
+pub const ACTION_DISMISS: &str = "dismiss";
+
+/// Action key of the "Reply" action.
+pub const ACTION_REPLY: &str = "reply";
+
+/// The parts of the desktop notification server the applet needs.
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+pub trait DesktopNotifications {
+    /// Optional features the server supports, e.g. `inline-reply`.
+    #[zbus(name = "GetCapabilities")]
+    fn get_capabilities(&self) -> zbus::Result<Vec<String>>;
+
+    /// Close a notification.
+    #[zbus(name = "CloseNotification")]
+    fn close_notification(&self, id: u32) -> zbus::Result<()>;
+
+    /// Signal emitted when the user invokes an action of a notification.
+    #[zbus(signal, name = "ActionInvoked")]
+    fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+
+    /// Signal emitted when a notification is closed, for any reason.
+    #[zbus(signal, name = "NotificationClosed")]
+    fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+
+    /// Signal emitted when the user sends an inline reply (a Plasma extension).
+    #[zbus(signal, name = "NotificationReplied")]
+    fn notification_replied(&self, id: u32, text: String) -> zbus::Result<()>;
+}
+
+/// A phone notification: device ID and notification ID.
+pub type PhoneNotification = (String, String);
+
+/// Desktop notifications shown for phone notifications, by desktop ID.
+#[derive(Debug, Default)]
+pub struct MirroredNotifications {
+    shown: HashMap<u32, PhoneNotification>,
+}
+
+impl MirroredNotifications {
+    /// Record the desktop notification shown for a phone notification.
+    pub fn insert(&mut self, desktop_id: u32, device_id: String, notification_id: String) {
+        self.shown.insert(desktop_id, (device_id, notification_id));
+    }
+
+    /// Phone notification a desktop notification was shown for.
+    pub fn get(&self, desktop_id: u32) -> Option<&PhoneNotification> {
+        self.shown.get(&desktop_id)
+    }
+
+    /// Forget a desktop notification once it is closed.
+    pub fn remove(&mut self, desktop_id: u32) -> Option<PhoneNotification> {
+        self.shown.remove(&desktop_id)
+    }
+
+    /// Desktop ID of the notification shown for a phone notification.
+    pub fn desktop_id(&self, device_id: &str, notification_id: &str) -> Option<u32> {
+        self.shown
+            .iter()
+            .find(|(_, (device, id))| device == device_id && id == notification_id)
+            .map(|(&desktop_id, _)| desktop_id)
+    }
+
+    /// Desktop notifications to close because the event removed their phone
+    /// notification.
+    pub fn removed_by(&self, event: &KdeConnectEvent) -> Vec<u32> {
+        match event {
+            KdeConnectEvent::NotificationRemoved {
+                device_id,
+                notification_id,
+            } => self
+                .desktop_id(device_id, notification_id)
+                .into_iter()
+                .collect(),
+            KdeConnectEvent::NotificationsCleared { device_id } => self
+                .shown
+                .iter()
+                .filter(|(_, (device, _))| device == device_id)
+                .map(|(&desktop_id, _)| desktop_id)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Show a phone notification on the desktop.
+///
+/// With `replaces`, the existing desktop notification is updated in place.
+pub async fn show_phone_notification_async(
+    device_id: String,
+    notification: NotificationInfo,
+    replaces: Option<u32>,
+) -> Message {
+    let notification_id = notification.id.clone();
+    // Use spawn_blocking to run notify_rust in a blocking context
+    // to avoid "Cannot start a runtime from within a runtime" panics
+    let result = tokio::task::spawn_blocking(move || {
+        let mut desktop = notify_rust::Notification::new();
+        desktop
+            .summary(summary(&notification))
+            .body(&notification.text)
+            .icon(fallback_icon(&notification.app_name))
+            .appname(&notification.app_name);
+        if let Some(icon_path) = &notification.icon_path {
+            desktop.image_path(&icon_path.to_string_lossy());
+        }
+        if notification.dismissable {
+            desktop.action(ACTION_DISMISS, &fl!("dismiss-on-phone"));
+        }
+        if notification.repliable {
+            desktop.action(ACTION_REPLY, &fl!("reply"));
+        }
+        if let Some(desktop_id) = replaces {
+            desktop.id(desktop_id);
+        }
+        desktop.show().map(|handle| handle.id())
+    })
+    .await;
+
+    let result = match result {
+        Ok(shown) => shown.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    Message::PhoneNotificationShown(device_id, notification_id, result)
+}
+
+/// Close a desktop notification whose phone notification went away.
+pub async fn close_desktop_notification_async(
+    connection: zbus::Connection,
+    desktop_id: u32,
+) -> Message {
+    let result = match DesktopNotificationsProxy::new(&connection).await {
+        Ok(proxy) => proxy.close_notification(desktop_id).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to close desktop notification {}: {}", desktop_id, e);
+    }
+    Message::DesktopNotificationClosed(desktop_id)
+}
+
+/// Title of the desktop notification; some apps only set the body.
+fn summary(notification: &NotificationInfo) -> &str {
+    if notification.title.is_empty() {
+        &notification.app_name
+    } else {
+        &notification.title
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mirrored() -> MirroredNotifications {
+        let mut mirrored = MirroredNotifications::default();
+        mirrored.insert(7, "abc".to_string(), "n1".to_string());
+        mirrored.insert(8, "abc".to_string(), "n2".to_string());
+        mirrored.insert(9, "def".to_string(), "n1".to_string());
+        mirrored
+    }
+
+    #[test]
+    fn desktop_ids_map_back_to_phone_notifications() {
+        let mut mirrored = mirrored();
+        assert_eq!(mirrored.desktop_id("def", "n1"), Some(9));
+        assert_eq!(
+            mirrored.get(8),
+            Some(&("abc".to_string(), "n2".to_string()))
+        );
+        assert!(mirrored.remove(8).is_some());
+        assert_eq!(mirrored.desktop_id("abc", "n2"), None);
+    }
+
+    #[test]
+    fn removed_phone_notifications_close_their_desktop_copies() {
+        let mirrored = mirrored();
+        let removed = mirrored.removed_by(&KdeConnectEvent::NotificationRemoved {
+            device_id: "abc".to_string(),
+            notification_id: "n1".to_string(),
+        });
+        assert_eq!(removed, vec![7]);
+
+        let mut cleared = mirrored.removed_by(&KdeConnectEvent::NotificationsCleared {
+            device_id: "abc".to_string(),
+        });
+        cleared.sort_unstable();
+        assert_eq!(cleared, vec![7, 8]);
+
+        let posted = mirrored.removed_by(&KdeConnectEvent::NotificationPosted {
+            device_id: "abc".to_string(),
+            notification_id: "n1".to_string(),
+        });
+        assert!(posted.is_empty());
+    }
+}
@@ -2,6 +2,11 @@
 
 pub mod actions;
 pub mod fetch;
+pub mod filter;
+pub mod history;
+pub mod icons;
+pub mod mirror;
+pub mod state;
 
 pub use actions::*;
 pub use fetch::*;
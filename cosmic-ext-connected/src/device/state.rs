@@ -0,0 +1,249 @@
+//! Applying KDE Connect events to the cached device list.
+//!
+//! Signals carry the values that changed, so most events update the matching
+//! [`DeviceInfo`] in place instead of re-reading every device from the daemon.
+
+use crate::app::DeviceInfo;
+use kdeconnect_dbus::KdeConnectEvent;
+
+/// What still has to be read from the daemon after applying an event.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FollowUp {
+    /// The event carried everything that changed.
+    Nothing,
+    /// Re-read one device.
+    Device(String),
+    /// Read one notification and add or replace it in the device's list.
+    Notification {
+        device_id: String,
+        notification_id: String,
+    },
+}
+
+/// Apply an event to the device it is about.
+///
+/// Events for devices that aren't in the list yet ask for that device to be
+/// read; device lifecycle events are left to a full refresh.
+pub fn apply_event(devices: &mut [DeviceInfo], event: &KdeConnectEvent) -> FollowUp {
+    let Some(device_id) = event.device_id() else {
+        return FollowUp::Nothing;
+    };
+    let Some(device) = devices.iter_mut().find(|d| d.id == device_id) else {
+        return match event {
+            KdeConnectEvent::DeviceChanged { .. }
+            | KdeConnectEvent::ReachableChanged { .. }
+            | KdeConnectEvent::PairStateChanged { .. } => FollowUp::Device(device_id.to_string()),
+            _ => FollowUp::Nothing,
+        };
+    };
+
+    match event {
+        KdeConnectEvent::DeviceChanged { name, .. } => match name {
+            Some(name) => {
+                device.name = name.clone();
+                FollowUp::Nothing
+            }
+            None => FollowUp::Device(device.id.clone()),
+        },
+        KdeConnectEvent::ReachableChanged { reachable, .. } => {
+            if device.is_reachable == *reachable {
+                return FollowUp::Nothing;
+            }
+            device.is_reachable = *reachable;
+            connection_changed(device)
+        }
+        KdeConnectEvent::PairStateChanged {
+            paired,
+            requested,
+            requested_by_peer,
+            ..
+        } => {
+            if paired.is_none() && requested.is_none() && requested_by_peer.is_none() {
+                return FollowUp::Device(device.id.clone());
+            }
+            let was_paired = device.is_paired;
+            device.is_paired = paired.unwrap_or(device.is_paired);
+            device.is_pair_requested = requested.unwrap_or(device.is_pair_requested);
+            device.is_pair_requested_by_peer =
+                requested_by_peer.unwrap_or(device.is_pair_requested_by_peer);
+            if device.is_paired == was_paired {
+                FollowUp::Nothing
+            } else {
+                connection_changed(device)
+            }
+        }
+        KdeConnectEvent::BatteryChanged {
+            charge,
+            is_charging,
+            ..
+        } => {
+            if charge.is_none() && is_charging.is_none() {
+                return FollowUp::Device(device.id.clone());
+            }
+            if charge.is_some() {
+                device.battery_level = *charge;
+            }
+            if is_charging.is_some() {
+                device.battery_charging = *is_charging;
+            }
+            FollowUp::Nothing
+        }
+        KdeConnectEvent::NotificationPosted {
+            notification_id, ..
+        } => FollowUp::Notification {
+            device_id: device.id.clone(),
+            notification_id: notification_id.clone(),
+        },
+        KdeConnectEvent::NotificationRemoved {
+            notification_id, ..
+        } => {
+            device.notifications.retain(|n| &n.id != notification_id);
+            FollowUp::Nothing
+        }
+        KdeConnectEvent::NotificationsCleared { .. } => {
+            device.notifications.clear();
+            FollowUp::Nothing
+        }
+        _ => FollowUp::Nothing,
+    }
+}
+
+/// Battery and notifications are only read for connected, paired devices.
+///
+/// Drop them when the device goes away; read them when it becomes usable.
+fn connection_changed(device: &mut DeviceInfo) -> FollowUp {
+    if device.is_reachable && device.is_paired {
+        FollowUp::Device(device.id.clone())
+    } else {
+        device.battery_level = None;
+        device.battery_charging = None;
+        device.notifications.clear();
+        FollowUp::Nothing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kdeconnect_dbus::plugins::NotificationInfo;
+
+    fn device() -> DeviceInfo {
+        DeviceInfo {
+            id: "abc".to_string(),
+            name: "Pixel".to_string(),
+            device_type: "phone".to_string(),
+            is_reachable: true,
+            is_paired: true,
+            is_pair_requested: false,
+            is_pair_requested_by_peer: false,
+            battery_level: Some(80),
+            battery_charging: Some(false),
+            notifications: vec![NotificationInfo {
+                id: "n1".to_string(),
+                app_name: "Chat".to_string(),
+                title: "Bob".to_string(),
+                text: "hi".to_string(),
+                icon_path: None,
+                dismissable: true,
+                repliable: false,
+                silent: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn battery_values_are_applied_in_place() {
+        let mut devices = vec![device()];
+        let follow_up = apply_event(
+            &mut devices,
+            &KdeConnectEvent::BatteryChanged {
+                device_id: "abc".to_string(),
+                charge: Some(15),
+                is_charging: None,
+            },
+        );
+        assert_eq!(follow_up, FollowUp::Nothing);
+        assert_eq!(devices[0].battery_level, Some(15));
+        assert_eq!(devices[0].battery_charging, Some(false));
+    }
+
+    #[test]
+    fn disconnecting_drops_battery_and_notifications() {
+        let mut devices = vec![device()];
+        let event = KdeConnectEvent::ReachableChanged {
+            device_id: "abc".to_string(),
+            reachable: false,
+        };
+        assert_eq!(apply_event(&mut devices, &event), FollowUp::Nothing);
+        assert!(!devices[0].is_reachable);
+        assert_eq!(devices[0].battery_level, None);
+        assert!(devices[0].notifications.is_empty());
+
+        let event = KdeConnectEvent::ReachableChanged {
+            device_id: "abc".to_string(),
+            reachable: true,
+        };
+        assert_eq!(
+            apply_event(&mut devices, &event),
+            FollowUp::Device("abc".to_string())
+        );
+    }
+
+    #[test]
+    fn pairing_request_updates_flags() {
+        let mut devices = vec![device()];
+        devices[0].is_paired = false;
+        let follow_up = apply_event(
+            &mut devices,
+            &KdeConnectEvent::PairStateChanged {
+                device_id: "abc".to_string(),
+                paired: Some(false),
+                requested: Some(false),
+                requested_by_peer: Some(true),
+            },
+        );
+        assert_eq!(follow_up, FollowUp::Nothing);
+        assert!(devices[0].is_pair_requested_by_peer);
+    }
+
+    #[test]
+    fn notifications_are_read_one_at_a_time() {
+        let mut devices = vec![device()];
+        let follow_up = apply_event(
+            &mut devices,
+            &KdeConnectEvent::NotificationPosted {
+                device_id: "abc".to_string(),
+                notification_id: "n2".to_string(),
+            },
+        );
+        assert_eq!(
+            follow_up,
+            FollowUp::Notification {
+                device_id: "abc".to_string(),
+                notification_id: "n2".to_string(),
+            }
+        );
+
+        apply_event(
+            &mut devices,
+            &KdeConnectEvent::NotificationRemoved {
+                device_id: "abc".to_string(),
+                notification_id: "n1".to_string(),
+            },
+        );
+        assert!(devices[0].notifications.is_empty());
+    }
+
+    #[test]
+    fn unknown_devices_are_read() {
+        let mut devices = vec![device()];
+        let follow_up = apply_event(
+            &mut devices,
+            &KdeConnectEvent::ReachableChanged {
+                device_id: "other".to_string(),
+                reachable: true,
+            },
+        );
+        assert_eq!(follow_up, FollowUp::Device("other".to_string()));
+    }
+}
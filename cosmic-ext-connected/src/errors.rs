@@ -0,0 +1,32 @@
+//! User-facing messages for KDE Connect errors.
+//!
+//! Actions report failures as strings; these helpers turn the typed errors
+//! from `kdeconnect_dbus` into localized text that tells the user what to do
+//! about them instead of echoing the raw D-Bus error.
+
+use crate::fl;
+use kdeconnect_dbus::Error;
+
+/// Localized, actionable description of an error.
+pub fn describe(err: &Error) -> String {
+    match err {
+        Error::DaemonNotRunning => fl!("error-daemon-not-running"),
+        Error::DeviceNotFound(_) => fl!("error-device-not-found"),
+        Error::PluginNotAvailable { plugin, .. } => fl!(
+            "error-plugin-not-available",
+            plugin = plugin.trim_start_matches("kdeconnect_").to_string()
+        ),
+        Error::DeviceNotReachable(_) => fl!("error-device-not-reachable"),
+        Error::DeviceNotPaired(_) => fl!("error-device-not-paired"),
+        Error::DBus(e) => fl!("error-dbus", details = e.to_string()),
+    }
+}
+
+/// Localized description of a failed call on one of a device's plugins.
+pub fn describe_plugin_call(
+    err: zbus::Error,
+    device_id: &str,
+    plugin: kdeconnect_dbus::Plugin,
+) -> String {
+    describe(&Error::from_device_call(err, device_id, Some(plugin)))
+}
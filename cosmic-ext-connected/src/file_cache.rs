@@ -0,0 +1,105 @@
+//! Copies of files the daemon keeps only for a while.
+//!
+//! The daemon writes notification icons and album art to directories it
+//! prunes on its own. They are copied to the applet's cache so they stay
+//! readable for as long as something shows them; each use refreshes the
+//! copy's modification time, and copies unused for a number of days are
+//! removed on start-up.
+
+use crate::config::APP_ID;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Directory of the cached copies of one kind of file.
+pub fn cache_dir(kind: &str) -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(APP_ID).join(kind))
+}
+
+/// Copy `source` into `dir`, or mark an existing copy as used, and return
+/// the copy.
+pub async fn cache_file(dir: &Path, source: &Path) -> io::Result<PathBuf> {
+    let file_name = source
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?;
+    let cached = dir.join(file_name);
+    if tokio::fs::try_exists(&cached).await.unwrap_or(false) {
+        touch(&cached).await?;
+    } else {
+        tokio::fs::create_dir_all(dir).await?;
+        tokio::fs::copy(source, &cached).await?;
+    }
+    Ok(cached)
+}
+
+/// Set a file's modification time to now.
+async fn touch(path: &Path) -> io::Result<()> {
+    let file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+    file.into_std().await.set_modified(SystemTime::now())
+}
+
+/// Remove the copies in `dir` unused for `max_age_days`; returns how many.
+pub fn remove_stale(dir: &Path, max_age_days: u64) -> usize {
+    remove_files_older_than(dir, Duration::from_secs(max_age_days * 24 * 60 * 60))
+}
+
+/// Remove the files in `dir` not modified within `max_age`; returns how many.
+fn remove_files_older_than(dir: &Path, max_age: Duration) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if stale && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// Empty directory under the system's temporary directory, for tests.
+///
+/// `name` must be unique across the tests that run at the same time.
+#[cfg(test)]
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("connected-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_unused_copies_are_removed() {
+        let dir = temp_dir("file-cache-stale");
+        let old = dir.join("old.png");
+        let recent = dir.join("recent.png");
+        std::fs::write(&old, b"png").unwrap();
+        std::fs::write(&recent, b"png").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+
+        assert_eq!(remove_files_older_than(&dir, Duration::from_secs(60)), 1);
+        assert!(!old.exists());
+        assert!(recent.exists());
+        assert_eq!(
+            remove_files_older_than(&dir.join("missing"), Duration::ZERO),
+            0
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,88 @@
+//! JSON Lines logs kept per device under the XDG data directory.
+//!
+//! Used by the SMS store and the notification history. COSMIC may run
+//! several applet processes, so whoever writes a log holds a [`LogLock`].
+
+use crate::config::APP_ID;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// Log of a device in the `kind` directory (`None` without a data directory).
+pub fn log_path(kind: &str, device_id: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| {
+        dir.join(APP_ID)
+            .join(kind)
+            .join(format!("{}.jsonl", file_stem(device_id)))
+    })
+}
+
+/// Keep device IDs from escaping the log directory.
+fn file_stem(device_id: &str) -> String {
+    device_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Write one value as a line of JSON.
+pub fn write_line(out: &mut impl Write, value: &impl Serialize) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    out.write_all(b"\n")
+}
+
+/// Replace the log at `path` with what `write` writes.
+///
+/// The new log is written to a temporary file first, so a crash never
+/// leaves half of it behind.
+pub fn rewrite(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let tmp = path.with_extension("jsonl.tmp");
+    {
+        let mut out = BufWriter::new(File::create(&tmp)?);
+        write(&mut out)?;
+        out.into_inner()?.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)
+}
+
+/// Exclusive lock on a log, held across processes until dropped.
+pub struct LogLock(File);
+
+impl LogLock {
+    pub fn acquire(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_extension("lock"))?;
+        // SAFETY: flock is a standard POSIX system call that operates on valid file descriptors
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // The lock is released when the file is closed
+        Ok(Self(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_ids_stay_inside_the_log_directory() {
+        assert_eq!(file_stem("../abc-123_x"), "___abc-123_x");
+        let path = log_path("sms", "a/b").unwrap();
+        assert!(path.ends_with("sms/a_b.jsonl"));
+    }
+}
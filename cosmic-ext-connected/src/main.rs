@@ -6,8 +6,12 @@
 mod app;
 mod config;
 mod constants;
+mod daemon;
 mod device;
+mod errors;
+mod file_cache;
 mod i18n;
+mod log_store;
 mod media;
 mod notifications;
 mod sms;
@@ -30,5 +34,7 @@ fn main() -> cosmic::iced::Result {
     i18n::init(&requested_languages);
 
     tracing::info!("Starting Connected applet");
+    device::icons::remove_stale_icons();
+    media::album_art::remove_stale_album_art();
     cosmic::applet::run::<ConnectApplet>(())
 }
@@ -0,0 +1,121 @@
+//! Album art of the phone's current track.
+//!
+//! The daemon downloads the art of the playing track and reports it as a
+//! `file://` URL into its own cache, which it prunes as tracks change. The
+//! images are kept in the [`file_cache`](crate::file_cache), so a track
+//! played again within [`ALBUM_ART_CACHE_MAX_AGE_DAYS`] shows its art
+//! without another download.
+//!
+//! Art the daemon reports by another scheme isn't fetched; the view shows a
+//! placeholder instead.
+
+use crate::app::Message;
+use crate::constants::media::ALBUM_ART_CACHE_MAX_AGE_DAYS;
+use crate::file_cache::{cache_dir, cache_file, remove_stale};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Directory of the cached album art.
+fn album_art_cache_dir() -> Option<PathBuf> {
+    cache_dir("album-art")
+}
+
+/// Local file an album art URL (or plain path) points at.
+fn local_path(url: &str) -> Option<PathBuf> {
+    if url.starts_with('/') {
+        return Some(PathBuf::from(url));
+    }
+    Url::parse(url).ok()?.to_file_path().ok()
+}
+
+/// Cached copy of the album art at `url`, if it is a local file.
+pub async fn cache_album_art(url: &str) -> Option<PathBuf> {
+    let source = local_path(url)?;
+    let dir = album_art_cache_dir()?;
+    cache_art(&dir, &source).await
+}
+
+/// Cache the album art at `url` for the track showing it.
+pub async fn cache_album_art_async(url: String) -> Message {
+    let path = cache_album_art(&url).await;
+    Message::MediaAlbumArtLoaded(url, path)
+}
+
+/// Cached copy of `source` in `dir`.
+///
+/// Falls back to the daemon's file if the copy fails, and returns `None` when
+/// neither is readable.
+async fn cache_art(dir: &Path, source: &Path) -> Option<PathBuf> {
+    match cache_file(dir, source).await {
+        Ok(cached) => Some(cached),
+        Err(e) => {
+            tracing::debug!("Failed to cache album art {}: {}", source.display(), e);
+            tokio::fs::try_exists(source)
+                .await
+                .unwrap_or(false)
+                .then(|| source.to_path_buf())
+        }
+    }
+}
+
+/// Remove cached album art that no track has shown for a while.
+pub fn remove_stale_album_art() {
+    let Some(dir) = album_art_cache_dir() else {
+        return;
+    };
+    let removed = remove_stale(&dir, ALBUM_ART_CACHE_MAX_AGE_DAYS);
+    if removed > 0 {
+        tracing::info!("Removed {} stale album art image(s)", removed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_cache::temp_dir;
+
+    #[test]
+    fn only_local_urls_are_read() {
+        assert_eq!(
+            local_path("file:///home/u/.cache/kdeconnect.daemon/albumart/1a2b"),
+            Some(PathBuf::from(
+                "/home/u/.cache/kdeconnect.daemon/albumart/1a2b"
+            ))
+        );
+        assert_eq!(
+            local_path("/tmp/art.png"),
+            Some(PathBuf::from("/tmp/art.png"))
+        );
+        assert_eq!(
+            local_path("file:///home/u/My%20Music/caf%C3%A9.jpg"),
+            Some(PathBuf::from("/home/u/My Music/café.jpg"))
+        );
+        assert_eq!(local_path("https://example.com/art.png"), None);
+        assert_eq!(local_path(""), None);
+    }
+
+    #[tokio::test]
+    async fn art_is_copied_per_track() {
+        let daemon_dir = temp_dir("album-art-daemon");
+        let cache_dir = temp_dir("album-art-cache").join("album-art");
+        let first = daemon_dir.join("1a2b");
+        let second = daemon_dir.join("3c4d");
+        std::fs::write(&first, b"jpg1").unwrap();
+        std::fs::write(&second, b"jpg2").unwrap();
+
+        let cached = cache_art(&cache_dir, &first).await.unwrap();
+        assert_eq!(cached, cache_dir.join("1a2b"));
+        assert_eq!(
+            std::fs::read(cache_art(&cache_dir, &second).await.unwrap()).unwrap(),
+            b"jpg2"
+        );
+
+        // The daemon drops the art of earlier tracks
+        std::fs::remove_file(&first).unwrap();
+        assert_eq!(cache_art(&cache_dir, &first).await, Some(cached));
+        assert_eq!(cache_art(&cache_dir, &daemon_dir.join("5e6f")).await, None);
+
+        let _ = std::fs::remove_dir_all(daemon_dir);
+        let _ = std::fs::remove_dir_all(cache_dir.parent().unwrap());
+    }
+}
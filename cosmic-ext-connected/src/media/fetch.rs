@@ -1,44 +1,78 @@
 //! Media information fetching and control actions.
 
 use crate::app::{MediaInfo, Message};
-use kdeconnect_dbus::plugins::MprisRemoteProxy;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use zbus::Connection;
+use crate::errors::{describe, describe_plugin_call};
+use crate::media::album_art::cache_album_art;
+use kdeconnect_dbus::plugins::MediaChanges;
+use kdeconnect_dbus::{KdeConnectClient, Plugin};
+use std::time::Instant;
 
 /// Media control action types.
 pub enum MediaAction {
     PlayPause,
+    Play,
+    Pause,
+    Stop,
     Next,
     Previous,
     SetVolume(i32),
+    SetLoopStatus(LoopStatus),
+    SetShuffle(bool),
+    /// Move playback to a position in milliseconds.
+    SetPosition(i32),
     SelectPlayer(String),
 }
 
+/// What the player repeats once a track ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopStatus {
+    None,
+    Track,
+    Playlist,
+}
+
+impl LoopStatus {
+    /// Parse the daemon's `loopStatus` value.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "None" => Some(Self::None),
+            "Track" => Some(Self::Track),
+            "Playlist" => Some(Self::Playlist),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Track => "Track",
+            Self::Playlist => "Playlist",
+        }
+    }
+
+    /// The mode the loop button switches to: none, playlist, track.
+    pub fn next(self) -> Self {
+        match self {
+            Self::None => Self::Playlist,
+            Self::Playlist => Self::Track,
+            Self::Track => Self::None,
+        }
+    }
+}
+
 /// Fetch media information from a device.
-pub async fn fetch_media_info_async(conn: Arc<Mutex<Connection>>, device_id: String) -> Message {
-    let conn = conn.lock().await;
-    let path = format!(
-        "{}/devices/{}/mprisremote",
-        kdeconnect_dbus::BASE_PATH,
-        device_id
-    );
-
-    let proxy = match MprisRemoteProxy::builder(&conn)
-        .path(path.as_str())
-        .ok()
-        .map(|b| b.build())
-    {
-        Some(fut) => match fut.await {
-            Ok(p) => p,
-            Err(e) => {
-                tracing::debug!("Failed to create mpris proxy: {}", e);
-                return Message::MediaInfoLoaded(None);
-            }
-        },
-        None => {
-            tracing::debug!("Failed to build mpris proxy path");
-            return Message::MediaInfoLoaded(None);
+pub async fn fetch_media_info_async(client: KdeConnectClient, device_id: String) -> Message {
+    Message::MediaInfoLoaded(fetch_media_info(&client, &device_id).await)
+}
+
+/// Read the state of a device's media player, or `None` if the device has no
+/// media plugin.
+pub async fn fetch_media_info(client: &KdeConnectClient, device_id: &str) -> Option<MediaInfo> {
+    let proxy = match client.device(device_id).mpris_remote_proxy().await {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::debug!("Failed to create mpris proxy: {}", e);
+            return None;
         }
     };
 
@@ -53,54 +87,138 @@ pub async fn fetch_media_info_async(conn: Arc<Mutex<Connection>>, device_id: Str
     // D-Bus returns i32 for position/length, convert to i64
     let position = proxy.position().await.unwrap_or(0) as i64;
     let length = proxy.length().await.unwrap_or(0) as i64;
-    // Note: canGoNext/canGoPrevious are per-player properties not exposed on the main interface.
-    // We default to true to allow actions; the phone will handle if unsupported.
-    let can_next = true;
-    let can_previous = true;
+    let can_seek = proxy.can_seek().await.unwrap_or(false);
+    // Older daemons don't report these; allow the actions and let the phone ignore them
+    let can_play = proxy.can_play().await.unwrap_or(true);
+    let can_pause = proxy.can_pause().await.unwrap_or(true);
+    let can_next = proxy.can_go_next().await.unwrap_or(true);
+    let can_previous = proxy.can_go_previous().await.unwrap_or(true);
+    // ...but hide the loop and shuffle toggles
+    let loop_status = proxy
+        .loop_status()
+        .await
+        .ok()
+        .and_then(|status| LoopStatus::parse(&status));
+    let shuffle = proxy.shuffle().await.ok();
+    // Older daemons don't have album art
+    let album_art_url = proxy.album_art_url().await.unwrap_or_default();
+    let album_art = cache_album_art(&album_art_url).await;
 
-    Message::MediaInfoLoaded(Some(MediaInfo {
+    Some(MediaInfo {
         players,
         current_player,
         title,
         artist,
         album,
+        album_art_url,
+        album_art,
         is_playing,
         volume,
         position,
+        position_time: Some(Instant::now()),
         length,
+        can_seek,
+        can_play,
+        can_pause,
         can_next,
         can_previous,
-    }))
+        loop_status,
+        shuffle,
+    })
+}
+
+/// Playback position at `now` in milliseconds, counting the time played
+/// since the position was read.
+pub fn position_at(info: &MediaInfo, now: Instant) -> i64 {
+    let played = match info.position_time {
+        Some(read) if info.is_playing => now.saturating_duration_since(read).as_millis() as i64,
+        _ => 0,
+    };
+    let position = info.position + played;
+    if info.length > 0 {
+        position.min(info.length)
+    } else {
+        position
+    }
+}
+
+/// Update media information with the properties a player signal carried at `now`.
+pub fn apply_media_changes(info: &mut MediaInfo, changes: MediaChanges, now: Instant) {
+    // Keep the interpolated position when playback starts or stops without a new one
+    if changes.position.is_none() && changes.is_playing.is_some_and(|p| p != info.is_playing) {
+        info.position = position_at(info, now);
+        info.position_time = Some(now);
+    }
+    if let Some(players) = changes.player_list {
+        info.players = players;
+    }
+    if let Some(player) = changes.player {
+        info.current_player = player;
+    }
+    if let Some(title) = changes.title {
+        info.title = title;
+    }
+    if let Some(artist) = changes.artist {
+        info.artist = artist;
+    }
+    if let Some(album) = changes.album {
+        info.album = album;
+    }
+    // The new art is cached separately; show the placeholder until then
+    if let Some(url) = changes.album_art_url {
+        if url != info.album_art_url {
+            info.album_art_url = url;
+            info.album_art = None;
+        }
+    }
+    if let Some(is_playing) = changes.is_playing {
+        info.is_playing = is_playing;
+    }
+    if let Some(volume) = changes.volume {
+        info.volume = volume;
+    }
+    if let Some(position) = changes.position {
+        info.position = position as i64;
+        info.position_time = Some(now);
+    }
+    if let Some(length) = changes.length {
+        info.length = length as i64;
+    }
+    if let Some(can_seek) = changes.can_seek {
+        info.can_seek = can_seek;
+    }
+    if let Some(can_play) = changes.can_play {
+        info.can_play = can_play;
+    }
+    if let Some(can_pause) = changes.can_pause {
+        info.can_pause = can_pause;
+    }
+    if let Some(can_next) = changes.can_go_next {
+        info.can_next = can_next;
+    }
+    if let Some(can_previous) = changes.can_go_previous {
+        info.can_previous = can_previous;
+    }
+    if let Some(status) = changes.loop_status {
+        info.loop_status = LoopStatus::parse(&status);
+    }
+    if let Some(shuffle) = changes.shuffle {
+        info.shuffle = Some(shuffle);
+    }
 }
 
 /// Execute a media control action on a device.
 /// If `ensure_player` is provided, the player will be selected before performing the action.
 pub async fn media_action_async(
-    conn: Arc<Mutex<Connection>>,
+    client: KdeConnectClient,
     device_id: String,
     action: MediaAction,
     ensure_player: Option<String>,
 ) -> Message {
-    let conn = conn.lock().await;
-    let path = format!(
-        "{}/devices/{}/mprisremote",
-        kdeconnect_dbus::BASE_PATH,
-        device_id
-    );
-
-    let proxy = match MprisRemoteProxy::builder(&conn)
-        .path(path.as_str())
-        .ok()
-        .map(|b| b.build())
-    {
-        Some(fut) => match fut.await {
-            Ok(p) => p,
-            Err(e) => {
-                return Message::MediaActionResult(Err(format!("Failed to create proxy: {}", e)));
-            }
-        },
-        None => {
-            return Message::MediaActionResult(Err("Failed to build proxy path".to_string()));
+    let proxy = match client.device(&device_id).mpris_remote_proxy().await {
+        Ok(p) => p,
+        Err(e) => {
+            return Message::MediaActionResult(Err(describe(&e)));
         }
     };
 
@@ -114,14 +232,101 @@ pub async fn media_action_async(
 
     let result = match action {
         MediaAction::PlayPause => proxy.send_action("PlayPause").await,
+        MediaAction::Play => proxy.send_action("Play").await,
+        MediaAction::Pause => proxy.send_action("Pause").await,
+        MediaAction::Stop => proxy.send_action("Stop").await,
         MediaAction::Next => proxy.send_action("Next").await,
         MediaAction::Previous => proxy.send_action("Previous").await,
         MediaAction::SetVolume(vol) => proxy.set_volume(vol).await,
+        MediaAction::SetLoopStatus(status) => proxy.set_loop_status(status.as_str()).await,
+        MediaAction::SetShuffle(shuffle) => proxy.set_shuffle(shuffle).await,
+        MediaAction::SetPosition(position) => proxy.set_position(position).await,
         MediaAction::SelectPlayer(player) => proxy.set_player(&player).await,
     };
 
     match result {
         Ok(()) => Message::MediaActionResult(Ok("OK".to_string())),
-        Err(e) => Message::MediaActionResult(Err(format!("Action failed: {}", e))),
+        Err(e) => Message::MediaActionResult(Err(describe_plugin_call(
+            e,
+            &device_id,
+            Plugin::MprisRemote,
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_update_only_signalled_fields() {
+        let mut info = MediaInfo {
+            players: vec!["Spotify".to_string()],
+            current_player: "Spotify".to_string(),
+            title: "Old song".to_string(),
+            artist: "Band".to_string(),
+            is_playing: true,
+            volume: 50,
+            position: 90_000,
+            length: 180_000,
+            ..Default::default()
+        };
+        apply_media_changes(
+            &mut info,
+            MediaChanges {
+                title: Some("New song".to_string()),
+                position: Some(0),
+                length: Some(200_000),
+                ..Default::default()
+            },
+            Instant::now(),
+        );
+        assert_eq!(info.title, "New song");
+        assert_eq!(info.artist, "Band");
+        assert_eq!((info.position, info.length), (0, 200_000));
+        assert!(info.is_playing);
+        assert_eq!(info.volume, 50);
+
+        apply_media_changes(
+            &mut info,
+            MediaChanges {
+                can_go_next: Some(false),
+                loop_status: Some("Track".to_string()),
+                shuffle: Some(true),
+                ..Default::default()
+            },
+            Instant::now(),
+        );
+        assert!(!info.can_next);
+        assert_eq!(info.loop_status, Some(LoopStatus::Track));
+        assert_eq!(info.shuffle, Some(true));
+    }
+
+    #[test]
+    fn position_advances_only_while_playing() {
+        let read = Instant::now();
+        let mut info = MediaInfo {
+            is_playing: true,
+            position: 10_000,
+            position_time: Some(read),
+            length: 12_000,
+            ..Default::default()
+        };
+        let later = read + std::time::Duration::from_millis(1_500);
+        assert_eq!(position_at(&info, later), 11_500);
+        // Clamped to the track length
+        assert_eq!(
+            position_at(&info, later + later.duration_since(read)),
+            12_000
+        );
+
+        let pause = MediaChanges {
+            is_playing: Some(false),
+            ..Default::default()
+        };
+        apply_media_changes(&mut info, pause, later);
+        assert_eq!(info.position, 11_500);
+        let much_later = later + std::time::Duration::from_secs(60);
+        assert_eq!(position_at(&info, much_later), 11_500);
     }
 }
@@ -1,6 +1,8 @@
 //! Media controls for KDE Connect devices.
 
+pub mod album_art;
 pub mod fetch;
+pub mod mpris;
 pub mod views;
 
 pub use fetch::*;
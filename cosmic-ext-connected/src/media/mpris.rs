@@ -0,0 +1,653 @@
+//! The phone's media player as a local MPRIS player.
+//!
+//! While the phone has a player, each bridged device owns
+//! `org.mpris.MediaPlayer2.KdeConnect_<device>` on the session bus and serves
+//! the MPRIS interfaces, so the desktop's media controls, media keys and
+//! `playerctl` can control it. The state follows the mprisremote signals the
+//! same way the media view does, and commands are forwarded with
+//! [`media_action_async`].
+//!
+//! Every MPRIS player lives at `/org/mpris/MediaPlayer2`, so each device gets
+//! a connection of its own. Applet processes queue for the name; whichever
+//! asked first serves the player, and the next takes over when it exits.
+
+use crate::app::{MediaInfo, Message};
+use crate::config::APP_ID;
+use crate::fl;
+use crate::media::album_art::cache_album_art;
+use crate::media::fetch::{
+    apply_media_changes, fetch_media_info, media_action_async, position_at, LoopStatus, MediaAction,
+};
+use futures_util::{Stream, StreamExt};
+use kdeconnect_dbus::{KdeConnectClient, KdeConnectEvent};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+use tokio::sync::mpsc::UnboundedSender;
+use zbus::fdo::RequestNameFlags;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{interface, Connection};
+
+/// Object path of every MPRIS player.
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// Track ID meaning "no track", from the MPRIS specification.
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+/// Bus name of the bridged player of a device.
+pub fn bus_name(device_id: &str) -> String {
+    let id: String = device_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("org.mpris.MediaPlayer2.KdeConnect_{}", id)
+}
+
+/// MPRIS `PlaybackStatus` of a player.
+fn playback_status(info: &MediaInfo) -> &'static str {
+    if info.is_playing {
+        "Playing"
+    } else if info.title.is_empty() {
+        "Stopped"
+    } else {
+        "Paused"
+    }
+}
+
+/// MPRIS track ID of the current track, derived from its metadata.
+fn track_id(info: &MediaInfo) -> OwnedObjectPath {
+    if info.title.is_empty() {
+        return OwnedObjectPath::try_from(NO_TRACK).expect("valid object path");
+    }
+    let mut hasher = DefaultHasher::new();
+    (&info.current_player, &info.title, &info.artist, &info.album).hash(&mut hasher);
+    let path = format!("/io/github/nwxnw/connected/track/t{:016x}", hasher.finish());
+    OwnedObjectPath::try_from(path).expect("valid object path")
+}
+
+/// MPRIS `Metadata` of the current track.
+fn metadata(info: &MediaInfo) -> HashMap<String, OwnedValue> {
+    let mut values = vec![("mpris:trackid", Value::from(track_id(info)))];
+    if info.length > 0 {
+        // MPRIS times are in microseconds
+        values.push(("mpris:length", Value::from(info.length * 1000)));
+    }
+    if !info.title.is_empty() {
+        values.push(("xesam:title", Value::from(info.title.clone())));
+    }
+    if !info.artist.is_empty() {
+        values.push(("xesam:artist", Value::from(vec![info.artist.clone()])));
+    }
+    if !info.album.is_empty() {
+        values.push(("xesam:album", Value::from(info.album.clone())));
+    }
+    if let Some(path) = &info.album_art {
+        let url = format!("file://{}", path.display());
+        values.push(("mpris:artUrl", Value::from(url)));
+    }
+    values
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), OwnedValue::try_from(value).ok()?)))
+        .collect()
+}
+
+/// The `org.mpris.MediaPlayer2` interface of a bridged player.
+struct MprisRoot {
+    identity: String,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl MprisRoot {
+    /// The phone's player can't be shown on the desktop.
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn identity(&self) -> String {
+        self.identity.clone()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn desktop_entry(&self) -> String {
+        APP_ID.to_string()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// The `org.mpris.MediaPlayer2.Player` interface of a bridged player.
+struct MprisPlayer {
+    client: KdeConnectClient,
+    device_id: String,
+    info: MediaInfo,
+    /// Where the results of forwarded actions go.
+    results: UnboundedSender<Message>,
+}
+
+impl MprisPlayer {
+    /// Forward an action to the phone, on whatever player the daemon has selected.
+    ///
+    /// The call runs on a task of its own: the object server holds the
+    /// interface's lock while a method runs, and [`Self::update`] would wait
+    /// for the round trip to the daemon.
+    fn forward(&self, action: MediaAction) {
+        let result = media_action_async(self.client.clone(), self.device_id.clone(), action, None);
+        let results = self.results.clone();
+        tokio::spawn(async move {
+            // The receiver is only gone once the bridge is shutting down
+            let _ = results.send(result.await);
+        });
+    }
+
+    /// Move playback to `position` in microseconds.
+    fn seek_to(&self, position: i64) {
+        let position = (position / 1000).clamp(0, i64::from(i32::MAX)) as i32;
+        self.forward(MediaAction::SetPosition(position));
+    }
+
+    /// Replace the player state, signalling what changed.
+    async fn update(
+        &mut self,
+        info: MediaInfo,
+        seeked: bool,
+        emitter: &SignalEmitter<'_>,
+    ) -> zbus::Result<()> {
+        let old = std::mem::replace(&mut self.info, info);
+        if playback_status(&old) != playback_status(&self.info) {
+            self.playback_status_changed(emitter).await?;
+        }
+        if metadata(&old) != metadata(&self.info) {
+            self.metadata_changed(emitter).await?;
+        }
+        if old.volume != self.info.volume {
+            self.volume_changed(emitter).await?;
+        }
+        if old.can_seek != self.info.can_seek {
+            self.can_seek_changed(emitter).await?;
+        }
+        if old.can_play != self.info.can_play {
+            self.can_play_changed(emitter).await?;
+        }
+        if old.can_pause != self.info.can_pause {
+            self.can_pause_changed(emitter).await?;
+        }
+        if old.loop_status != self.info.loop_status {
+            self.loop_status_changed(emitter).await?;
+        }
+        if old.shuffle != self.info.shuffle {
+            self.shuffle_changed(emitter).await?;
+        }
+        if old.can_next != self.info.can_next {
+            self.can_go_next_changed(emitter).await?;
+        }
+        if old.can_previous != self.info.can_previous {
+            self.can_go_previous_changed(emitter).await?;
+        }
+        if seeked {
+            Self::seeked(emitter, self.info.position * 1000).await?;
+        }
+        Ok(())
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl MprisPlayer {
+    fn next(&self) {
+        self.forward(MediaAction::Next);
+    }
+
+    fn previous(&self) {
+        self.forward(MediaAction::Previous);
+    }
+
+    fn play_pause(&self) {
+        self.forward(MediaAction::PlayPause);
+    }
+
+    fn play(&self) {
+        if self.info.can_play {
+            self.forward(MediaAction::Play);
+        }
+    }
+
+    fn pause(&self) {
+        if self.info.can_pause {
+            self.forward(MediaAction::Pause);
+        }
+    }
+
+    fn stop(&self) {
+        self.forward(MediaAction::Stop);
+    }
+
+    /// Seek by `offset` microseconds.
+    fn seek(&self, offset: i64) {
+        if !self.info.can_seek {
+            return;
+        }
+        let position = position_at(&self.info, Instant::now()) * 1000 + offset;
+        // Seeking past the end skips to the next track, as the spec asks
+        if self.info.length > 0 && position > self.info.length * 1000 {
+            self.forward(MediaAction::Next);
+        } else {
+            self.seek_to(position);
+        }
+    }
+
+    /// Move to `position` microseconds, if `track` is still the current track.
+    fn set_position(&self, track: ObjectPath<'_>, position: i64) {
+        let in_track = self.info.length <= 0 || position <= self.info.length * 1000;
+        if self.info.can_seek && track == *track_id(&self.info) && position >= 0 && in_track {
+            self.seek_to(position);
+        }
+    }
+
+    fn open_uri(&self, _uri: String) -> zbus::fdo::Result<()> {
+        Err(zbus::fdo::Error::NotSupported(
+            "Opening URIs on the phone is not supported".to_string(),
+        ))
+    }
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        playback_status(&self.info).to_string()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        metadata(&self.info)
+    }
+
+    /// "None" when the daemon doesn't report a loop mode.
+    #[zbus(property)]
+    fn loop_status(&self) -> String {
+        self.info
+            .loop_status
+            .unwrap_or(LoopStatus::None)
+            .as_str()
+            .to_string()
+    }
+
+    #[zbus(property)]
+    fn set_loop_status(&mut self, loop_status: String) {
+        if self.info.loop_status.is_none() {
+            return;
+        }
+        if let Some(status) = LoopStatus::parse(&loop_status) {
+            self.forward(MediaAction::SetLoopStatus(status));
+        }
+    }
+
+    #[zbus(property)]
+    fn shuffle(&self) -> bool {
+        self.info.shuffle.unwrap_or(false)
+    }
+
+    #[zbus(property)]
+    fn set_shuffle(&mut self, shuffle: bool) {
+        if self.info.shuffle.is_some() {
+            self.forward(MediaAction::SetShuffle(shuffle));
+        }
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        f64::from(self.info.volume) / 100.0
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, volume: f64) {
+        let volume = (volume.clamp(0.0, 1.0) * 100.0).round() as i32;
+        self.forward(MediaAction::SetVolume(volume));
+    }
+
+    /// Interpolated like the media view's position; MPRIS doesn't signal it.
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        position_at(&self.info, Instant::now()) * 1000
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.info.can_next
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.info.can_previous
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.info.can_play
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.info.can_pause
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.info.can_seek
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// Create a stream that bridges a device's media player onto the desktop.
+///
+/// The stream yields the results of the actions desktop clients send the
+/// player; the bridge stops when the stream is dropped.
+pub fn mpris_bridge_subscription(
+    client: KdeConnectClient,
+    device_id: String,
+    device_name: String,
+) -> impl Stream<Item = Message> {
+    let (results, received) = tokio::sync::mpsc::unbounded_channel();
+    let bridge = futures_util::stream::once(run_bridge(client, device_id, device_name, results))
+        .filter_map(|()| std::future::ready(None));
+    let received = futures_util::stream::unfold(received, |mut received| async move {
+        received.recv().await.map(|message| (message, received))
+    });
+    futures_util::stream::select(bridge, received)
+}
+
+/// Follow the phone's player, publishing the bridged player while it has one.
+async fn run_bridge(
+    client: KdeConnectClient,
+    device_id: String,
+    device_name: String,
+    results: UnboundedSender<Message>,
+) {
+    let mut events = std::pin::pin!(client.events());
+    let mut info: Option<MediaInfo> = None;
+    let mut bridge: Option<Connection> = None;
+
+    while let Some(event) = events.next().await {
+        let seeked = match event {
+            // Signals may have been missed while (re)subscribing
+            KdeConnectEvent::Connected => {
+                info = fetch_media_info(&client, &device_id).await;
+                false
+            }
+            KdeConnectEvent::MediaChanged {
+                device_id: from,
+                changes,
+            } if from == device_id => match info.as_mut() {
+                Some(current) if !changes.is_empty() => {
+                    let seeked = changes.position.is_some();
+                    let art_url = changes.album_art_url.clone();
+                    apply_media_changes(current, changes, Instant::now());
+                    if let Some(url) = art_url.filter(|_| current.album_art.is_none()) {
+                        current.album_art = cache_album_art(&url).await;
+                    }
+                    seeked
+                }
+                _ => {
+                    info = fetch_media_info(&client, &device_id).await;
+                    false
+                }
+            },
+            _ => continue,
+        };
+
+        let playing_info = info.as_ref().filter(|info| !info.players.is_empty());
+        match (playing_info, &bridge) {
+            (None, Some(_)) => {
+                tracing::debug!("Withdrawing MPRIS player of {}", device_id);
+                bridge = None;
+            }
+            (None, None) => {}
+            (Some(info), None) => {
+                let player = MprisPlayer {
+                    client: client.clone(),
+                    device_id: device_id.clone(),
+                    info: info.clone(),
+                    results: results.clone(),
+                };
+                match publish(&device_id, &device_name, player).await {
+                    Ok(connection) => bridge = Some(connection),
+                    Err(e) => tracing::warn!("Failed to publish MPRIS player: {}", e),
+                }
+            }
+            (Some(info), Some(connection)) => {
+                if let Err(e) = update(connection, info.clone(), seeked).await {
+                    tracing::warn!("Failed to update MPRIS player: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Serve the player on a new session bus connection and queue for its name.
+async fn publish(
+    device_id: &str,
+    device_name: &str,
+    player: MprisPlayer,
+) -> zbus::Result<Connection> {
+    let root = MprisRoot {
+        identity: fl!("mpris-identity", device = device_name),
+    };
+    let connection = zbus::connection::Builder::session()?
+        .serve_at(MPRIS_PATH, root)?
+        .serve_at(MPRIS_PATH, player)?
+        .build()
+        .await?;
+    let name = bus_name(device_id);
+    let reply = connection
+        .request_name_with_flags(name.as_str(), RequestNameFlags::AllowReplacement.into())
+        .await?;
+    tracing::debug!("Requested MPRIS name {}: {:?}", name, reply);
+    Ok(connection)
+}
+
+/// Update the served player state.
+async fn update(connection: &Connection, info: MediaInfo, seeked: bool) -> zbus::Result<()> {
+    let iface = connection
+        .object_server()
+        .interface::<_, MprisPlayer>(MPRIS_PATH)
+        .await?;
+    let mut player = iface.get_mut().await;
+    player.update(info, seeked, iface.signal_emitter()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kdeconnect_dbus::mock::{MockDaemon, MockDevice};
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use zbus::{connection, proxy, Guid};
+
+    const DEVICE: &str = "abc123";
+
+    #[proxy(interface = "org.mpris.MediaPlayer2.Player")]
+    trait Player {
+        fn play(&self) -> zbus::Result<()>;
+        fn pause(&self) -> zbus::Result<()>;
+        fn stop(&self) -> zbus::Result<()>;
+        fn set_position(&self, track: &ObjectPath<'_>, position: i64) -> zbus::Result<()>;
+        #[zbus(property)]
+        fn playback_status(&self) -> zbus::Result<String>;
+        #[zbus(property)]
+        fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+        #[zbus(property)]
+        fn set_volume(&self, volume: f64) -> zbus::Result<()>;
+        #[zbus(property)]
+        fn set_shuffle(&self, shuffle: bool) -> zbus::Result<()>;
+        #[zbus(property)]
+        fn set_loop_status(&self, loop_status: &str) -> zbus::Result<()>;
+    }
+
+    fn paused_song() -> MediaInfo {
+        MediaInfo {
+            players: vec!["Spotify".to_string()],
+            current_player: "Spotify".to_string(),
+            title: "Song".to_string(),
+            artist: "Band".to_string(),
+            length: 200_000,
+            can_seek: true,
+            can_play: true,
+            can_pause: true,
+            shuffle: Some(false),
+            ..Default::default()
+        }
+    }
+
+    /// Serve a bridged player for a mock device over a private connection.
+    ///
+    /// Also returns where the results of forwarded actions arrive.
+    async fn bridged_player(
+        info: MediaInfo,
+    ) -> (
+        MockDaemon,
+        Connection,
+        PlayerProxy<'static>,
+        UnboundedReceiver<Message>,
+    ) {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        let (results, received) = tokio::sync::mpsc::unbounded_channel();
+        let player = MprisPlayer {
+            client: KdeConnectClient::new(mock.connection().clone()),
+            device_id: DEVICE.to_string(),
+            info,
+            results,
+        };
+
+        let (server_stream, client_stream) = tokio::net::UnixStream::pair().unwrap();
+        let server = connection::Builder::unix_stream(server_stream)
+            .server(Guid::generate())
+            .unwrap()
+            .p2p()
+            .serve_at(MPRIS_PATH, player)
+            .unwrap()
+            .build();
+        let client = connection::Builder::unix_stream(client_stream)
+            .p2p()
+            .build();
+        let (server, client) = tokio::try_join!(server, client).unwrap();
+        let proxy = PlayerProxy::builder(&client)
+            .destination(bus_name(DEVICE))
+            .unwrap()
+            .path(MPRIS_PATH)
+            .unwrap()
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await
+            .unwrap();
+        (mock, server, proxy, received)
+    }
+
+    #[test]
+    fn bus_names_are_valid_for_any_device_id() {
+        assert_eq!(
+            bus_name("a1b2_c3"),
+            "org.mpris.MediaPlayer2.KdeConnect_a1b2_c3"
+        );
+        assert_eq!(
+            bus_name("{1234-abcd}"),
+            "org.mpris.MediaPlayer2.KdeConnect__1234_abcd_"
+        );
+    }
+
+    #[tokio::test]
+    async fn player_mirrors_state_and_forwards_actions() {
+        let (mock, _server, player, mut results) = bridged_player(paused_song()).await;
+
+        assert_eq!(player.playback_status().await.unwrap(), "Paused");
+        let metadata = player.metadata().await.unwrap();
+        assert_eq!(
+            String::try_from(metadata["xesam:title"].try_clone().unwrap()).unwrap(),
+            "Song"
+        );
+        assert_eq!(
+            i64::try_from(&metadata["mpris:length"]).unwrap(),
+            200_000_000
+        );
+
+        // Calls return before the daemon is reached, so wait for each result
+        player.stop().await.unwrap();
+        results.recv().await.unwrap();
+        player.play().await.unwrap();
+        results.recv().await.unwrap();
+        player.set_volume(0.8).await.unwrap();
+        results.recv().await.unwrap();
+        player.set_shuffle(true).await.unwrap();
+        results.recv().await.unwrap();
+        // The daemon reported no loop mode
+        player.set_loop_status("Track").await.unwrap();
+        player
+            .set_position(&track_id(&paused_song()), 30_000_000)
+            .await
+            .unwrap();
+        results.recv().await.unwrap();
+        // A stale track ID is ignored
+        let stale = ObjectPath::try_from("/io/github/nwxnw/connected/track/old").unwrap();
+        player.set_position(&stale, 60_000_000).await.unwrap();
+        assert!(results.try_recv().is_err());
+
+        let media = mock.media(DEVICE).await.unwrap();
+        assert!(media.is_playing);
+        assert_eq!(media.volume, 80);
+        assert_eq!(media.position, 30_000);
+        assert!(media.shuffle);
+        assert_eq!(media.loop_status, "");
+        let actions: Vec<_> = mock
+            .calls_to("mprisremote")
+            .into_iter()
+            .filter(|c| c.method == "sendAction")
+            .flat_map(|c| c.args)
+            .collect();
+        assert_eq!(actions, vec!["Stop", "Play"]);
+    }
+}
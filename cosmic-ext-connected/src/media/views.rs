@@ -1,18 +1,25 @@
 //! Media control view components.
 
 use crate::app::{MediaInfo, Message};
+use crate::constants::media::ALBUM_ART_SIZE;
 use crate::fl;
+use crate::media::fetch::{position_at, LoopStatus};
 use crate::views::helpers::format_duration;
-use cosmic::iced::widget::{column, row, text};
+use cosmic::iced::widget::{column, row, text, tooltip};
 use cosmic::iced::{Alignment, Length};
 use cosmic::widget;
 use cosmic::Element;
+use std::time::Instant;
 
 /// Parameters for the media controls view.
 pub struct MediaControlsParams<'a> {
     pub device_name: Option<&'a str>,
     pub media_info: Option<&'a MediaInfo>,
     pub media_loading: bool,
+    /// Position the seek slider is being dragged to, in milliseconds.
+    pub seek_position: Option<i32>,
+    /// Current time, to interpolate the playback position.
+    pub now: Instant,
 }
 
 /// Render the media controls view.
@@ -58,7 +65,10 @@ pub fn view_media_controls(params: MediaControlsParams<'_>) -> Element<'_, Messa
             .into()
         } else {
             // Show media controls
-            view_media_player(info)
+            let position = params
+                .seek_position
+                .map_or_else(|| position_at(info, params.now), i64::from);
+            view_media_player(info, position)
         }
     } else {
         // Error or no media plugin
@@ -83,8 +93,9 @@ pub fn view_media_controls(params: MediaControlsParams<'_>) -> Element<'_, Messa
         .into()
 }
 
-/// Render the media player with controls.
-pub fn view_media_player(info: &MediaInfo) -> Element<'_, Message> {
+/// Render the media player with controls, showing playback at `position`
+/// (milliseconds).
+pub fn view_media_player(info: &MediaInfo, position: i64) -> Element<'_, Message> {
     // Player selector (if multiple players)
     let player_selector: Element<Message> = if info.players.len() > 1 {
         let players: Vec<String> = info.players.clone();
@@ -144,43 +155,99 @@ pub fn view_media_player(info: &MediaInfo) -> Element<'_, Message> {
     .align_x(Alignment::Center)
     .width(Length::Fill);
 
-    // Position display
-    let position_str = format_duration(info.position);
+    // Album art, or a placeholder while there is none
+    let album_art: Element<Message> = match &info.album_art {
+        Some(path) => widget::image(widget::image::Handle::from_path(path))
+            .width(Length::Fixed(ALBUM_ART_SIZE))
+            .height(Length::Fixed(ALBUM_ART_SIZE))
+            .into(),
+        // Same size as the art, so the controls don't move when it loads
+        None => widget::container(widget::icon::from_name("multimedia-player-symbolic").size(48))
+            .width(Length::Fixed(ALBUM_ART_SIZE))
+            .height(Length::Fixed(ALBUM_ART_SIZE))
+            .align_x(Alignment::Center)
+            .align_y(Alignment::Center)
+            .into(),
+    };
+
+    // Position display; the slider is read-only when the player can't seek
+    let position_str = format_duration(position);
     let length_str = format_duration(info.length);
-    let position_display = row![
+    let mut position_display = column![].spacing(4).padding([0, 12]);
+    if info.length > 0 {
+        let length = i32::try_from(info.length).unwrap_or(i32::MAX);
+        let position = i32::try_from(position).unwrap_or(i32::MAX).min(length);
+        let seek_bar: Element<Message> = if info.can_seek {
+            widget::slider(0..=length, position, Message::MediaSeek)
+                .on_release(Message::MediaSeekRelease)
+                .into()
+        } else {
+            cosmic::iced::widget::progress_bar(0.0..=length as f32, position as f32)
+                .height(Length::Fixed(4.0))
+                .into()
+        };
+        position_display = position_display.push(seek_bar);
+    }
+    let position_display = position_display.push(row![
         text(position_str).size(10),
         widget::horizontal_space(),
         text(length_str).size(10),
-    ]
-    .padding([0, 12]);
+    ]);
 
-    // Playback controls
-    let play_icon = if info.is_playing {
-        "media-playback-pause-symbolic"
+    // Playback controls, enabled by what the player supports
+    let play_button = if info.is_playing {
+        widget::button::icon(widget::icon::from_name("media-playback-pause-symbolic"))
+            .on_press_maybe(info.can_pause.then_some(Message::MediaPause))
     } else {
-        "media-playback-start-symbolic"
+        widget::button::icon(widget::icon::from_name("media-playback-start-symbolic"))
+            .on_press_maybe(info.can_play.then_some(Message::MediaPlay))
     };
 
     let prev_button = widget::button::icon(widget::icon::from_name("media-skip-backward-symbolic"))
-        .on_press_maybe(if info.can_previous {
-            Some(Message::MediaPrevious)
-        } else {
-            None
-        });
-
-    let play_button =
-        widget::button::icon(widget::icon::from_name(play_icon)).on_press(Message::MediaPlayPause);
+        .on_press_maybe(info.can_previous.then_some(Message::MediaPrevious));
 
     let next_button = widget::button::icon(widget::icon::from_name("media-skip-forward-symbolic"))
-        .on_press_maybe(if info.can_next {
-            Some(Message::MediaNext)
-        } else {
-            None
-        });
+        .on_press_maybe(info.can_next.then_some(Message::MediaNext));
 
-    let playback_controls = row![prev_button, play_button, next_button,]
-        .spacing(16)
-        .align_y(Alignment::Center);
+    let stop_button = widget::button::icon(widget::icon::from_name("media-playback-stop-symbolic"))
+        .on_press(Message::MediaStop);
+
+    let mut playback_controls = row![].spacing(16).align_y(Alignment::Center);
+    // Shuffle and loop are only shown when the daemon reports them
+    if let Some(shuffle) = info.shuffle {
+        let shuffle_button =
+            widget::button::icon(widget::icon::from_name("media-playlist-shuffle-symbolic"))
+                .class(toggle_class(shuffle))
+                .on_press(Message::MediaSetShuffle(!shuffle));
+        playback_controls = playback_controls.push(control_tooltip(
+            shuffle_button,
+            if shuffle {
+                fl!("media-shuffle-on")
+            } else {
+                fl!("media-shuffle-off")
+            },
+        ));
+    }
+    playback_controls = playback_controls
+        .push(prev_button)
+        .push(play_button)
+        .push(stop_button)
+        .push(next_button);
+    if let Some(status) = info.loop_status {
+        let loop_icon = match status {
+            LoopStatus::Track => "media-playlist-repeat-song-symbolic",
+            LoopStatus::None | LoopStatus::Playlist => "media-playlist-repeat-symbolic",
+        };
+        let loop_button = widget::button::icon(widget::icon::from_name(loop_icon))
+            .class(toggle_class(status != LoopStatus::None))
+            .on_press(Message::MediaSetLoopStatus(status.next()));
+        let loop_label = match status {
+            LoopStatus::None => fl!("media-loop-none"),
+            LoopStatus::Track => fl!("media-loop-track"),
+            LoopStatus::Playlist => fl!("media-loop-playlist"),
+        };
+        playback_controls = playback_controls.push(control_tooltip(loop_button, loop_label));
+    }
 
     let controls_container = widget::container(playback_controls)
         .width(Length::Fill)
@@ -214,7 +281,7 @@ pub fn view_media_player(info: &MediaInfo) -> Element<'_, Message> {
     column![
         player_selector,
         widget::vertical_space().height(Length::Fixed(16.0)),
-        widget::container(widget::icon::from_name("multimedia-player-symbolic").size(48))
+        widget::container(album_art)
             .width(Length::Fill)
             .align_x(Alignment::Center),
         widget::vertical_space().height(Length::Fixed(12.0)),
@@ -231,3 +298,23 @@ pub fn view_media_player(info: &MediaInfo) -> Element<'_, Message> {
     .width(Length::Fill)
     .into()
 }
+
+/// Button style showing whether a toggle is on.
+fn toggle_class(on: bool) -> cosmic::theme::Button {
+    if on {
+        cosmic::theme::Button::Suggested
+    } else {
+        cosmic::theme::Button::Icon
+    }
+}
+
+/// Wrap a playback control in a tooltip naming its current state.
+fn control_tooltip<'a>(
+    button: impl Into<Element<'a, Message>>,
+    label: String,
+) -> Element<'a, Message> {
+    tooltip(button, text(label).size(11), tooltip::Position::Bottom)
+        .gap(4)
+        .padding(8)
+        .into()
+}
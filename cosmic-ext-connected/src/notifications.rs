@@ -18,6 +18,9 @@ const FILE_DEDUP_PATH: &str = "/tmp/cosmic-connected-file-dedup";
 /// File path for SMS notification deduplication.
 const SMS_DEDUP_PATH: &str = "/tmp/cosmic-connected-sms-dedup";
 
+/// File path for phone notification deduplication.
+const PHONE_NOTIFICATION_DEDUP_PATH: &str = "/tmp/cosmic-connected-notification-dedup";
+
 /// Check if we should show a file notification (cross-process deduplication via file lock).
 /// Returns true if this is the first notification for this file within the dedup window.
 pub fn should_show_file_notification(file_url: &str) -> bool {
@@ -32,6 +35,13 @@ pub fn should_show_sms_notification(thread_id: i64, message_date: i64) -> bool {
     should_show_notification(SMS_DEDUP_PATH, &message_key)
 }
 
+/// Check if we should mirror a phone notification (cross-process deduplication via file lock).
+/// Returns true if this is the first process to see the notification within the dedup window.
+pub fn should_show_phone_notification(device_id: &str, notification_id: &str) -> bool {
+    let notification_key = format!("{}:{}", device_id, notification_id);
+    should_show_notification(PHONE_NOTIFICATION_DEDUP_PATH, &notification_key)
+}
+
 /// Generic notification deduplication using file-based locking.
 ///
 /// This function:
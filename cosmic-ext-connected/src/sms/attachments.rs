@@ -0,0 +1,441 @@
+//! MMS attachments: downloading received ones and preparing outgoing ones.
+//!
+//! Messages only carry a thumbnail of each attachment. The full file is
+//! requested from the phone on demand; the daemon downloads it to its cache
+//! and reports the local path with `attachmentReceived`.
+//!
+//! Outgoing attachments are passed to the daemon as `file://` URLs. It reads
+//! and encodes the whole file into one packet, so files are checked for type
+//! and size before they can be added to a message. Pasted images are saved
+//! to the cache first and deleted once sent.
+
+use crate::app::Message;
+use crate::config::APP_ID;
+use crate::constants::sms::{
+    ATTACHMENT_DOWNLOAD_TIMEOUT_SECS, MAX_OUTGOING_ATTACHMENT_BYTES, PASTED_IMAGE_MAX_AGE_SECS,
+};
+use crate::fl;
+use futures_util::StreamExt;
+use kdeconnect_dbus::KdeConnectClient;
+use std::path::{Path, PathBuf};
+
+/// File types that can be sent by MMS, by file extension.
+const SENDABLE_TYPES: &[(&str, &str)] = &[
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("png", "image/png"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("bmp", "image/bmp"),
+    ("heic", "image/heic"),
+    ("mp4", "video/mp4"),
+    ("3gp", "video/3gpp"),
+    ("webm", "video/webm"),
+    ("mp3", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("aac", "audio/aac"),
+    ("amr", "audio/amr"),
+    ("ogg", "audio/ogg"),
+    ("vcf", "text/vcard"),
+    ("txt", "text/plain"),
+];
+
+/// Extensions of the files that can be sent, for the file picker.
+pub fn sendable_extensions() -> Vec<&'static str> {
+    SENDABLE_TYPES.iter().map(|(ext, _)| *ext).collect()
+}
+
+/// A local file to be sent as an MMS attachment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutgoingAttachment {
+    pub path: PathBuf,
+    pub file_name: String,
+    pub mime_type: &'static str,
+    /// File size in bytes.
+    pub size: u64,
+}
+
+impl OutgoingAttachment {
+    /// Whether the attachment is an image.
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+
+    /// `file://` URL of the attachment, as `sendSms` expects it.
+    pub fn url(&self) -> String {
+        file_url(&self.path)
+    }
+}
+
+/// Request the full file of an attachment and wait until it is downloaded.
+pub async fn download_attachment_async(
+    client: KdeConnectClient,
+    device_id: String,
+    part_id: i64,
+    unique_identifier: String,
+) -> Message {
+    Message::AttachmentDownloaded(
+        download_attachment(&client, &device_id, part_id, &unique_identifier).await,
+    )
+}
+
+/// Request the full file of an attachment and return its local path.
+pub async fn download_attachment(
+    client: &KdeConnectClient,
+    device_id: &str,
+    part_id: i64,
+    unique_identifier: &str,
+) -> Result<PathBuf, String> {
+    let conversations_proxy = client
+        .device(device_id)
+        .conversations_proxy()
+        .await
+        .map_err(|e| format!("Failed to create conversations proxy: {}", e))?;
+
+    // Subscribe before requesting so the signal cannot be missed
+    let mut received = conversations_proxy
+        .receive_attachment_received()
+        .await
+        .map_err(|e| format!("Failed to subscribe to attachments: {}", e))?;
+
+    tracing::debug!(
+        "Requesting attachment {} (part {}) from {}",
+        unique_identifier,
+        part_id,
+        device_id
+    );
+    conversations_proxy
+        .request_attachment_file(part_id, unique_identifier)
+        .await
+        .map_err(|e| format!("Failed to request attachment: {}", e))?;
+
+    let wait = async {
+        while let Some(signal) = received.next().await {
+            match signal.args() {
+                Ok(args) if args.file_name == unique_identifier => {
+                    return Some(local_path(&args.file_path));
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to parse attachmentReceived signal: {}", e),
+            }
+        }
+        None
+    };
+    match tokio::time::timeout(
+        tokio::time::Duration::from_secs(ATTACHMENT_DOWNLOAD_TIMEOUT_SECS),
+        wait,
+    )
+    .await
+    {
+        Ok(Some(path)) => {
+            tracing::info!("Attachment downloaded to {}", path.display());
+            Ok(path)
+        }
+        Ok(None) => Err("Attachment signal stream ended".to_string()),
+        Err(_) => Err(format!("Timed out downloading {}", unique_identifier)),
+    }
+}
+
+/// The daemon reports plain paths, but accept `file://` URLs as well.
+fn local_path(file_path: &str) -> PathBuf {
+    PathBuf::from(file_path.strip_prefix("file://").unwrap_or(file_path))
+}
+
+/// Open a downloaded attachment with the default application.
+pub async fn open_attachment(path: &Path) -> Result<(), String> {
+    let status = tokio::process::Command::new("xdg-open")
+        .arg(path)
+        .status()
+        .await
+        .map_err(|e| format!("Failed to run xdg-open: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("xdg-open failed for {}", path.display()))
+    }
+}
+
+/// MIME type of a sendable file, from its extension.
+fn sendable_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    SENDABLE_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime_type)| *mime_type)
+}
+
+/// Check that a file can be sent by MMS.
+pub async fn prepare_attachment(path: PathBuf) -> Result<OutgoingAttachment, String> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    let Some(mime_type) = sendable_mime_type(&path) else {
+        return Err(fl!("attachment-unsupported", name = file_name));
+    };
+    let metadata = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| format!("{}: {}", file_name, e))?;
+    if !metadata.is_file() {
+        return Err(fl!("attachment-unsupported", name = file_name));
+    }
+    if metadata.len() > MAX_OUTGOING_ATTACHMENT_BYTES {
+        return Err(fl!(
+            "attachment-too-large",
+            name = file_name,
+            limit = format_size(MAX_OUTGOING_ATTACHMENT_BYTES)
+        ));
+    }
+
+    Ok(OutgoingAttachment {
+        path,
+        file_name,
+        mime_type,
+        size: metadata.len(),
+    })
+}
+
+/// Check a set of picked files; see [`prepare_attachment`].
+pub async fn prepare_attachments_async(paths: Vec<PathBuf>) -> Message {
+    let mut prepared = Vec::with_capacity(paths.len());
+    for path in paths {
+        prepared.push(prepare_attachment(path).await);
+    }
+    Message::AttachmentsPrepared(prepared)
+}
+
+/// Add prepared attachments to a message, skipping duplicates.
+///
+/// Attachments that would take the message over the size limit are left out.
+/// Returns the errors to show for files that were not added.
+pub fn add_attachments(
+    pending: &mut Vec<OutgoingAttachment>,
+    prepared: Vec<Result<OutgoingAttachment, String>>,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    for result in prepared {
+        match result {
+            Ok(attachment) if pending.iter().any(|a| a.path == attachment.path) => {}
+            Ok(attachment) => {
+                let total: u64 = pending.iter().map(|a| a.size).sum();
+                if total + attachment.size > MAX_OUTGOING_ATTACHMENT_BYTES {
+                    errors.push(fl!(
+                        "attachment-too-large",
+                        name = attachment.file_name,
+                        limit = format_size(MAX_OUTGOING_ATTACHMENT_BYTES)
+                    ));
+                } else {
+                    pending.push(attachment);
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    errors
+}
+
+/// Directory pasted images are saved to until they are sent.
+fn pasted_images_dir() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join(APP_ID).join("outgoing"))
+}
+
+/// Save an image pasted from the clipboard, so it can be sent by URL.
+pub async fn save_pasted_image(bytes: Vec<u8>, mime_type: String) -> Message {
+    let result = async {
+        let extension = SENDABLE_TYPES
+            .iter()
+            .find(|(_, mime)| *mime == mime_type)
+            .map(|(ext, _)| *ext)
+            .ok_or_else(|| fl!("attachment-unsupported", name = mime_type.clone()))?;
+        let dir = pasted_images_dir().ok_or_else(|| "No cache directory".to_string())?;
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| format!("{}: {}", dir.display(), e))?;
+        remove_stale_pasted_images(&dir).await;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let path = dir.join(format!("pasted-{}.{}", timestamp, extension));
+        tokio::fs::write(&path, bytes)
+            .await
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(path)
+    }
+    .await;
+
+    match result {
+        Ok(path) => prepare_attachments_async(vec![path]).await,
+        Err(e) => Message::AttachmentsPrepared(vec![Err(e)]),
+    }
+}
+
+/// The pasted images among a message's attachments.
+pub fn pasted_images(attachments: &[OutgoingAttachment]) -> Vec<PathBuf> {
+    let Some(dir) = pasted_images_dir() else {
+        return Vec::new();
+    };
+    attachments
+        .iter()
+        .filter(|a| a.path.starts_with(&dir))
+        .map(|a| a.path.clone())
+        .collect()
+}
+
+/// Delete pasted images after sending; the daemon has read them by the time
+/// `sendSms` returns.
+pub async fn remove_pasted_images(paths: Vec<PathBuf>) {
+    for path in paths {
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            tracing::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Delete pasted images that were removed from a message or never sent.
+///
+/// Only old ones, since another applet process may be about to send a new one.
+async fn remove_stale_pasted_images(dir: &Path) {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return;
+    };
+    let max_age = std::time::Duration::from_secs(PASTED_IMAGE_MAX_AGE_SECS);
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let stale = entry
+            .metadata()
+            .await
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if stale {
+            let _ = tokio::fs::remove_file(entry.path()).await;
+        }
+    }
+}
+
+/// Format a `file://` URL, percent-encoding everything but unreserved
+/// characters and path separators.
+pub fn file_url(path: &Path) -> String {
+    let mut url = String::from("file://");
+    for byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                url.push(*byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+/// Format a byte count for display, e.g. `1.5 MB`.
+pub fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+    if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{} KB", bytes / KB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kdeconnect_dbus::mock::{MockDaemon, MockDevice};
+
+    const DEVICE: &str = "abc123";
+
+    #[test]
+    fn file_urls_become_paths() {
+        assert_eq!(
+            local_path("file:///tmp/PART_1.jpg"),
+            PathBuf::from("/tmp/PART_1.jpg")
+        );
+        assert_eq!(
+            local_path("/tmp/PART_1.jpg"),
+            PathBuf::from("/tmp/PART_1.jpg")
+        );
+    }
+
+    #[test]
+    fn file_urls_are_percent_encoded() {
+        assert_eq!(
+            file_url(Path::new("/home/me/My Photos/cat#1.jpg")),
+            "file:///home/me/My%20Photos/cat%231.jpg"
+        );
+    }
+
+    #[test]
+    fn sizes_are_human_readable() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(2048), "2 KB");
+        assert_eq!(format_size(3 * 1024 * 1024 / 2), "1.5 MB");
+    }
+
+    fn attachment(name: &str, size: u64) -> OutgoingAttachment {
+        OutgoingAttachment {
+            path: PathBuf::from(format!("/tmp/{}", name)),
+            file_name: name.to_string(),
+            mime_type: "image/png",
+            size,
+        }
+    }
+
+    #[test]
+    fn messages_stay_under_the_size_limit() {
+        let mut pending = vec![attachment("a.png", MAX_OUTGOING_ATTACHMENT_BYTES - 10)];
+        let errors = add_attachments(
+            &mut pending,
+            vec![
+                Ok(attachment("a.png", MAX_OUTGOING_ATTACHMENT_BYTES - 10)),
+                Ok(attachment("b.png", 10)),
+                Ok(attachment("c.png", 1)),
+                Err("bad".to_string()),
+            ],
+        );
+        let names: Vec<&str> = pending.iter().map(|a| a.file_name.as_str()).collect();
+        assert_eq!(names, vec!["a.png", "b.png"]);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn files_are_checked_for_type_and_size() {
+        let dir = std::env::temp_dir().join(format!("connected-outgoing-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image = dir.join("photo.JPG");
+        std::fs::write(&image, [0xff, 0xd8, 0xff]).unwrap();
+        let document = dir.join("notes.odt");
+        std::fs::write(&document, b"odt").unwrap();
+
+        let prepared = prepare_attachment(image.clone()).await.unwrap();
+        assert_eq!(prepared.mime_type, "image/jpeg");
+        assert_eq!(prepared.size, 3);
+        assert!(prepared.url().starts_with("file:///"));
+        assert!(prepare_attachment(document).await.is_err());
+        assert!(prepare_attachment(dir.join("missing.png")).await.is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn download_waits_for_the_requested_file() {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+
+        let message =
+            download_attachment_async(client, DEVICE.to_string(), 4, "PART_4_cat.jpg".to_string())
+                .await;
+        assert!(matches!(
+            message,
+            Message::AttachmentDownloaded(Ok(ref path)) if path.ends_with("PART_4_cat.jpg")
+        ));
+        let calls = mock.calls_to("conversations");
+        assert_eq!(calls[0].method, "requestAttachmentFile");
+    }
+}
@@ -0,0 +1,31 @@
+//! Pasting images from the clipboard as MMS attachments.
+
+use cosmic::iced::clipboard::mime::AllowedMimeTypes;
+use std::borrow::Cow;
+
+/// Image types accepted from the clipboard, in order of preference.
+const IMAGE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// An image read from the clipboard.
+#[derive(Debug, Clone)]
+pub struct ClipboardImage {
+    pub bytes: Vec<u8>,
+    pub mime_type: String,
+}
+
+impl AllowedMimeTypes for ClipboardImage {
+    fn allowed() -> Cow<'static, [String]> {
+        Cow::Owned(IMAGE_MIME_TYPES.iter().map(|m| m.to_string()).collect())
+    }
+}
+
+impl TryFrom<(Vec<u8>, String)> for ClipboardImage {
+    type Error = ();
+
+    fn try_from((bytes, mime_type): (Vec<u8>, String)) -> Result<Self, Self::Error> {
+        if bytes.is_empty() {
+            return Err(());
+        }
+        Ok(Self { bytes, mime_type })
+    }
+}
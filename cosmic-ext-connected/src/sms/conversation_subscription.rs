@@ -8,8 +8,9 @@ use crate::app::Message;
 use crate::constants::dbus::RETRY_DELAY_SECS;
 use crate::constants::sms::{SIGNAL_ACTIVITY_TIMEOUT_MS, TIMEOUT_CHECK_INTERVAL_MS};
 use futures_util::StreamExt;
-use kdeconnect_dbus::plugins::{parse_sms_message, ConversationSummary, ConversationsProxy};
-use zbus::Connection;
+use kdeconnect_dbus::client::device_path;
+use kdeconnect_dbus::plugins::{parse_sms_message, ConversationSummary};
+use kdeconnect_dbus::KdeConnectClient;
 
 /// Overall timeout for conversation list sync (seconds).
 const CONVERSATION_LIST_TIMEOUT_SECS: u64 = 20;
@@ -18,11 +19,12 @@ const CONVERSATION_LIST_TIMEOUT_SECS: u64 = 20;
 #[allow(clippy::large_enum_variant)]
 enum ConversationListState {
     Init {
+        client: KdeConnectClient,
         device_id: String,
     },
     /// Emitting cached conversations one at a time before listening for signals
     EmittingCached {
-        conn: Connection,
+        client: KdeConnectClient,
         stream: zbus::MessageStream,
         device_id: String,
         pending_conversations: Vec<ConversationSummary>,
@@ -30,7 +32,7 @@ enum ConversationListState {
     },
     Listening {
         #[allow(dead_code)]
-        conn: Connection,
+        client: KdeConnectClient,
         stream: zbus::MessageStream,
         device_id: String,
         start_time: tokio::time::Instant,
@@ -42,118 +44,60 @@ enum ConversationListState {
 /// Create a stream that listens for conversation list updates via D-Bus signals.
 ///
 /// This subscription handles incremental conversation loading by:
-/// 1. Setting up D-Bus match rules for signals
+/// 1. Subscribing to the device's conversation signals
 /// 2. Getting initial cached conversations via activeConversations()
 /// 3. Firing requestAllConversationThreads() to trigger phone sync
 /// 4. Listening for `conversationCreated`/`conversationUpdated` signals
 /// 5. Emitting `Message::ConversationReceived` for each conversation (immediate UI update)
 /// 6. Emitting `Message::ConversationSyncComplete` when activity stops or timeout
 pub fn conversation_list_subscription(
+    client: KdeConnectClient,
     device_id: String,
 ) -> impl futures_util::Stream<Item = Message> {
     futures_util::stream::unfold(
-        ConversationListState::Init { device_id },
+        ConversationListState::Init { client, device_id },
         |state| async move {
             match state {
-                ConversationListState::Init { device_id } => {
-                    // Connect to D-Bus
-                    let conn = match Connection::session().await {
-                        Ok(c) => c,
-                        Err(e) => {
-                            tracing::error!(
-                                "Failed to connect to D-Bus for conversation list: {}",
-                                e
-                            );
-                            tokio::time::sleep(std::time::Duration::from_secs(RETRY_DELAY_SECS))
-                                .await;
-                            return Some((
-                                Message::SmsError(format!("D-Bus connection failed: {}", e)),
-                                ConversationListState::Init { device_id },
-                            ));
+                ConversationListState::Init { client, device_id } => {
+                    // Subscribe to the device's conversation signals BEFORE firing request
+                    // (the match rule is removed again when the stream is dropped)
+                    let stream = match zbus::MatchRule::builder()
+                        .msg_type(zbus::message::Type::Signal)
+                        .interface("org.kde.kdeconnect.device.conversations")
+                        .and_then(|b| b.path(device_path(&device_id)))
+                    {
+                        Ok(b) => {
+                            zbus::MessageStream::for_match_rule(
+                                b.build(),
+                                &client.connection(),
+                                None,
+                            )
+                            .await
                         }
+                        Err(e) => Err(e),
                     };
-
-                    // Add match rules for conversation signals
-                    let dbus_proxy = match zbus::fdo::DBusProxy::new(&conn).await {
-                        Ok(p) => p,
+                    let stream = match stream {
+                        Ok(s) => s,
                         Err(e) => {
-                            tracing::error!("Failed to create DBus proxy: {}", e);
+                            tracing::error!("Failed to subscribe to conversation signals: {}", e);
+                            tokio::time::sleep(std::time::Duration::from_secs(RETRY_DELAY_SECS))
+                                .await;
                             return Some((
-                                Message::SmsError(format!("D-Bus proxy failed: {}", e)),
-                                ConversationListState::Init { device_id },
+                                Message::SmsError(format!("D-Bus subscription failed: {}", e)),
+                                ConversationListState::Init { client, device_id },
                             ));
                         }
                     };
 
-                    // Subscribe to conversationCreated signals
-                    let created_rule = zbus::MatchRule::builder()
-                        .msg_type(zbus::message::Type::Signal)
-                        .interface("org.kde.kdeconnect.device.conversations")
-                        .and_then(|b| b.member("conversationCreated"))
-                        .map(|b| b.build());
-
-                    if let Ok(rule) = created_rule {
-                        if let Err(e) = dbus_proxy.add_match_rule(rule).await {
-                            tracing::warn!("Failed to add conversationCreated match rule: {}", e);
-                        } else {
-                            tracing::debug!("Added match rule for conversationCreated signals");
-                        }
-                    }
-
-                    // Subscribe to conversationUpdated signals
-                    let updated_rule = zbus::MatchRule::builder()
-                        .msg_type(zbus::message::Type::Signal)
-                        .interface("org.kde.kdeconnect.device.conversations")
-                        .and_then(|b| b.member("conversationUpdated"))
-                        .map(|b| b.build());
-
-                    if let Ok(rule) = updated_rule {
-                        if let Err(e) = dbus_proxy.add_match_rule(rule).await {
-                            tracing::warn!("Failed to add conversationUpdated match rule: {}", e);
-                        } else {
-                            tracing::debug!("Added match rule for conversationUpdated signals");
-                        }
-                    }
-
-                    // Subscribe to conversationLoaded signals
-                    let loaded_rule = zbus::MatchRule::builder()
-                        .msg_type(zbus::message::Type::Signal)
-                        .interface("org.kde.kdeconnect.device.conversations")
-                        .and_then(|b| b.member("conversationLoaded"))
-                        .map(|b| b.build());
-
-                    if let Ok(rule) = loaded_rule {
-                        if let Err(e) = dbus_proxy.add_match_rule(rule).await {
-                            tracing::warn!("Failed to add conversationLoaded match rule: {}", e);
-                        } else {
-                            tracing::debug!("Added match rule for conversationLoaded signals");
-                        }
-                    }
-
-                    // Create message stream BEFORE firing request
-                    let stream = zbus::MessageStream::from(&conn);
-
                     // Build conversations proxy for the device
-                    let device_path = format!(
-                        "{}/devices/{}",
-                        kdeconnect_dbus::BASE_PATH,
-                        device_id
-                    );
-
-                    let conversations_proxy = match ConversationsProxy::builder(&conn)
-                        .path(device_path.as_str())
-                        .ok()
-                        .map(|b| b.build())
-                    {
-                        Some(fut) => match fut.await {
+                    let conversations_proxy =
+                        match client.device(&device_id).conversations_proxy().await {
                             Ok(p) => Some(p),
                             Err(e) => {
                                 tracing::warn!("Failed to create conversations proxy: {}", e);
                                 None
                             }
-                        },
-                        None => None,
-                    };
+                        };
 
                     // Get cached conversations first (for immediate display)
                     let mut initial_conversations: Vec<ConversationSummary> = Vec::new();
@@ -205,7 +149,7 @@ pub fn conversation_list_subscription(
                                 conversation: first,
                             },
                             ConversationListState::EmittingCached {
-                                conn,
+                                client,
                                 stream,
                                 device_id,
                                 pending_conversations: initial_conversations,
@@ -218,7 +162,7 @@ pub fn conversation_list_subscription(
                     Some((
                         Message::ConversationSyncStarted { device_id: device_id.clone() },
                         ConversationListState::Listening {
-                            conn,
+                            client,
                             stream,
                             device_id,
                             start_time: now,
@@ -228,7 +172,7 @@ pub fn conversation_list_subscription(
                     ))
                 }
                 ConversationListState::EmittingCached {
-                    conn,
+                    client,
                     stream,
                     device_id,
                     mut pending_conversations,
@@ -248,7 +192,7 @@ pub fn conversation_list_subscription(
                                 conversation,
                             },
                             ConversationListState::EmittingCached {
-                                conn,
+                                client,
                                 stream,
                                 device_id,
                                 pending_conversations,
@@ -266,7 +210,7 @@ pub fn conversation_list_subscription(
                     Some((
                         Message::ConversationSyncStarted { device_id: device_id.clone() },
                         ConversationListState::Listening {
-                            conn,
+                            client,
                             stream,
                             device_id,
                             start_time,
@@ -276,7 +220,7 @@ pub fn conversation_list_subscription(
                     ))
                 }
                 ConversationListState::Listening {
-                    conn,
+                    client,
                     mut stream,
                     device_id,
                     start_time,
@@ -341,7 +285,7 @@ pub fn conversation_list_subscription(
                                                                     conversation,
                                                                 },
                                                                 ConversationListState::Listening {
-                                                                    conn,
+                                                                    client,
                                                                     stream,
                                                                     device_id,
                                                                     start_time,
@@ -380,7 +324,7 @@ pub fn conversation_list_subscription(
                                                                     conversation,
                                                                 },
                                                                 ConversationListState::Listening {
-                                                                    conn,
+                                                                    client,
                                                                     stream,
                                                                     device_id,
                                                                     start_time,
@@ -427,7 +371,7 @@ pub fn conversation_list_subscription(
                                     );
                                     return Some((
                                         Message::ConversationSyncComplete { device_id },
-                                        ConversationListState::Init { device_id: String::new() }, // Done
+                                        ConversationListState::Init { client, device_id: String::new() }, // Done
                                     ));
                                 }
 
@@ -440,7 +384,7 @@ pub fn conversation_list_subscription(
                                     );
                                     return Some((
                                         Message::ConversationSyncComplete { device_id },
-                                        ConversationListState::Init { device_id: String::new() }, // Done
+                                        ConversationListState::Init { client, device_id: String::new() }, // Done
                                     ));
                                 }
                             }
@@ -0,0 +1,561 @@
+//! Exporting SMS conversations to JSON, CSV or a plain-text transcript.
+//!
+//! Before writing, every exported thread is fetched page by page from the
+//! phone, so the export holds the whole conversation rather than just the
+//! loaded page. Messages already in the device's store are included too, so
+//! threads can still be exported while the phone is disconnected; such an
+//! export, or one where the phone stopped answering, is reported as possibly
+//! incomplete.
+
+use super::fetch::fetch_older_page_async;
+use super::store::SmsStore;
+use crate::fl;
+use kdeconnect_dbus::contacts::ContactLookup;
+use kdeconnect_dbus::plugins::{MessageKey, MessageType, SmsMessage};
+use kdeconnect_dbus::KdeConnectClient;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// File format of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Array of message objects.
+    Json,
+    /// One row per message, with a header row.
+    Csv,
+    /// Human-readable transcript grouped by conversation.
+    Text,
+}
+
+impl ExportFormat {
+    /// All formats, in the order they are offered.
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Json, ExportFormat::Csv, ExportFormat::Text];
+
+    /// File name extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Text => "txt",
+        }
+    }
+
+    /// Label for the export menu.
+    pub fn label(self) -> String {
+        match self {
+            ExportFormat::Json => fl!("export-format-json"),
+            ExportFormat::Csv => fl!("export-format-csv"),
+            ExportFormat::Text => fl!("export-format-text"),
+        }
+    }
+}
+
+/// What to export and where.
+#[derive(Debug, Clone)]
+pub struct ExportRequest {
+    pub device_id: String,
+    pub thread_ids: Vec<i64>,
+    pub format: ExportFormat,
+    pub path: PathBuf,
+    /// Page size used while fetching the full threads.
+    pub page_size: u32,
+    /// Whether to fetch the threads from the phone; off while it is out of
+    /// reach, leaving only the stored messages.
+    pub fetch_from_phone: bool,
+}
+
+/// An export that was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub path: PathBuf,
+    /// Number of messages written.
+    pub count: usize,
+    /// Why messages may be missing, if the threads couldn't be fetched in full.
+    pub incomplete: Option<String>,
+}
+
+/// A message as written to JSON and CSV exports.
+#[derive(Serialize)]
+struct ExportedMessage<'a> {
+    thread_id: i64,
+    uid: i32,
+    /// Unix timestamp in milliseconds.
+    date: i64,
+    /// Local time in RFC 3339 format.
+    time: String,
+    direction: &'static str,
+    addresses: &'a [String],
+    /// Contact names for `addresses`, or the number if unknown.
+    contacts: Vec<String>,
+    body: &'a str,
+    sub_id: i64,
+}
+
+impl<'a> ExportedMessage<'a> {
+    fn new(message: &'a SmsMessage, contacts: &ContactLookup) -> Self {
+        Self {
+            thread_id: message.thread_id,
+            uid: message.uid,
+            date: message.date,
+            time: local_time(message.date, "%+"),
+            direction: direction(message.message_type),
+            addresses: &message.addresses,
+            contacts: message
+                .addresses
+                .iter()
+                .map(|address| contacts.get_name_or_number(address))
+                .collect(),
+            body: &message.body,
+            sub_id: message.sub_id,
+        }
+    }
+}
+
+/// Fetch the requested threads in full and write them to the export file.
+///
+/// Once a thread fails to load from the phone, the remaining threads are
+/// exported from the store only, rather than waiting on the phone for each.
+pub async fn export_sms_async(
+    client: KdeConnectClient,
+    request: ExportRequest,
+    contacts: ContactLookup,
+    store: Option<SmsStore>,
+) -> Result<ExportSummary, String> {
+    let mut fetch_from_phone = request.fetch_from_phone;
+    let mut incomplete = (!fetch_from_phone).then(|| fl!("sms-export-unreachable"));
+    let mut messages = Vec::new();
+    for &thread_id in &request.thread_ids {
+        let fetched = if fetch_from_phone {
+            fetch_full_thread(
+                &client,
+                &request.device_id,
+                thread_id,
+                request.page_size,
+                store.as_ref(),
+            )
+            .await
+        } else {
+            Ok(Vec::new())
+        };
+        let fetched = fetched.unwrap_or_else(|e| {
+            tracing::warn!("Failed to fetch thread {} for export: {}", thread_id, e);
+            fetch_from_phone = false;
+            incomplete = Some(e);
+            Vec::new()
+        });
+
+        // Pages fetched before a failure are in the store as well
+        let stored = store
+            .as_ref()
+            .map(|s| s.messages(thread_id))
+            .unwrap_or_default();
+        let thread = merge(stored.into_iter().chain(fetched));
+        tracing::debug!(
+            "Exporting {} messages from thread {}",
+            thread.len(),
+            thread_id
+        );
+        messages.extend(thread);
+    }
+
+    let count = messages.len();
+    let content = render(request.format, &messages, &contacts)?;
+    tokio::fs::write(&request.path, content)
+        .await
+        .map_err(|e| format!("{}: {}", request.path.display(), e))?;
+    tracing::info!(
+        "Exported {} SMS messages to {}",
+        count,
+        request.path.display()
+    );
+    Ok(ExportSummary {
+        path: request.path,
+        count,
+        incomplete,
+    })
+}
+
+/// Fetch every page of a thread from the phone.
+///
+/// Pages the phone doesn't send are an error rather than the end of the
+/// thread, so an export never silently misses them.
+async fn fetch_full_thread(
+    client: &KdeConnectClient,
+    device_id: &str,
+    thread_id: i64,
+    page_size: u32,
+    store: Option<&SmsStore>,
+) -> Result<Vec<SmsMessage>, String> {
+    // Page through the whole thread even where the store already has the
+    // messages, since older pages may still be missing from it
+    let mut messages = Vec::new();
+    let mut start_index = 0;
+    loop {
+        let page = fetch_older_page_async(
+            client.clone(),
+            device_id.to_string(),
+            thread_id,
+            start_index,
+            page_size,
+            store.cloned(),
+        )
+        .await?;
+        let fetched = page.messages.len() as u32;
+        messages.extend(page.messages);
+        start_index += fetched;
+
+        let more = match page.total {
+            Some(total) => u64::from(start_index) < total,
+            None => page.has_more,
+        };
+        if fetched == 0 || !more {
+            return Ok(messages);
+        }
+    }
+}
+
+/// Drop duplicate messages and sort the rest, oldest first.
+fn merge(messages: impl IntoIterator<Item = SmsMessage>) -> Vec<SmsMessage> {
+    let messages: HashMap<MessageKey, SmsMessage> =
+        messages.into_iter().map(|m| (m.key(), m)).collect();
+    let mut messages: Vec<SmsMessage> = messages.into_values().collect();
+    messages.sort_by_key(|m| (m.date, m.uid));
+    messages
+}
+
+/// Render messages in the requested format.
+fn render(
+    format: ExportFormat,
+    messages: &[SmsMessage],
+    contacts: &ContactLookup,
+) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => {
+            let exported: Vec<ExportedMessage> = messages
+                .iter()
+                .map(|m| ExportedMessage::new(m, contacts))
+                .collect();
+            serde_json::to_string_pretty(&exported).map_err(|e| e.to_string())
+        }
+        ExportFormat::Csv => Ok(render_csv(messages, contacts)),
+        ExportFormat::Text => Ok(render_text(messages, contacts)),
+    }
+}
+
+fn render_csv(messages: &[SmsMessage], contacts: &ContactLookup) -> String {
+    let mut out =
+        String::from("thread_id,uid,date,time,direction,addresses,contacts,body,sub_id\n");
+    for message in messages {
+        let exported = ExportedMessage::new(message, contacts);
+        let fields = [
+            exported.thread_id.to_string(),
+            exported.uid.to_string(),
+            exported.date.to_string(),
+            exported.time,
+            exported.direction.to_string(),
+            exported.addresses.join("; "),
+            exported.contacts.join("; "),
+            exported.body.to_string(),
+            exported.sub_id.to_string(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn render_text(messages: &[SmsMessage], contacts: &ContactLookup) -> String {
+    let mut out = String::new();
+    let mut current_thread = None;
+    for message in messages {
+        if current_thread != Some(message.thread_id) {
+            if current_thread.is_some() {
+                out.push('\n');
+            }
+            current_thread = Some(message.thread_id);
+            let participants: Vec<String> = message
+                .addresses
+                .iter()
+                .map(|address| contacts.get_name_or_number(address))
+                .collect();
+            out.push_str(&format!("== {} ==\n", participants.join(", ")));
+        }
+
+        let sender = if message.message_type.is_outgoing() {
+            fl!("export-me")
+        } else {
+            contacts.get_name_or_number(message.primary_address())
+        };
+        out.push_str(&format!(
+            "[{}] {}: {}\n",
+            local_time(message.date, "%Y-%m-%d %H:%M"),
+            sender,
+            message.body
+        ));
+    }
+    out
+}
+
+fn direction(message_type: MessageType) -> &'static str {
+    if message_type.is_outgoing() {
+        "sent"
+    } else {
+        "received"
+    }
+}
+
+fn local_time(timestamp: i64, format: &str) -> String {
+    use chrono::{Local, TimeZone};
+    Local
+        .timestamp_millis_opt(timestamp)
+        .single()
+        .map(|dt| dt.format(format).to_string())
+        .unwrap_or_default()
+}
+
+/// Suggested file name for an export.
+pub fn default_file_name(name: &str, format: ExportFormat) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("sms-{}.{}", name.trim_matches('-'), format.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kdeconnect_dbus::mock::{MockDaemon, MockDevice, MockSms};
+
+    const DEVICE: &str = "abc123";
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        let messages = [SmsMessage::test(1, 10, "Hi, \"you\"\nthere", 1_000)];
+        let csv = render_csv(&messages, &ContactLookup::new());
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("thread_id,uid,date,time,direction,addresses,contacts,body,sub_id")
+        );
+        assert!(csv.contains(",received,+15550001,+15550001,\"Hi, \"\"you\"\"\nthere\",-1\n"));
+    }
+
+    #[test]
+    fn json_export_has_direction_and_sub_id() {
+        let messages = [
+            SmsMessage::test(1, 10, "ping", 1_000),
+            SmsMessage {
+                message_type: MessageType::Sent,
+                sub_id: 2,
+                ..SmsMessage::test(1, 11, "pong", 2_000)
+            },
+            SmsMessage {
+                message_type: MessageType::Failed,
+                ..SmsMessage::test(1, 12, "again?", 3_000)
+            },
+        ];
+        let json = render(ExportFormat::Json, &messages, &ContactLookup::new()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0]["direction"], "received");
+        assert_eq!(value[1]["direction"], "sent");
+        assert_eq!(value[2]["direction"], "sent");
+        assert_eq!(value[1]["sub_id"], 2);
+        assert_eq!(value[1]["contacts"][0], "+15550001");
+    }
+
+    #[test]
+    fn transcript_groups_messages_by_thread() {
+        let messages = [
+            SmsMessage::test(1, 10, "ping", 1_000),
+            SmsMessage {
+                message_type: MessageType::Sent,
+                ..SmsMessage::test(1, 11, "pong", 2_000)
+            },
+        ];
+        let text = render_text(&messages, &ContactLookup::new());
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "== +15550001 ==");
+        assert!(lines[1].ends_with("+15550001: ping"));
+        assert!(lines[2].ends_with(": pong"));
+    }
+
+    #[test]
+    fn file_names_are_safe() {
+        assert_eq!(
+            default_file_name("Alice / Bob", ExportFormat::Csv),
+            "sms-Alice---Bob.csv"
+        );
+    }
+
+    #[tokio::test]
+    async fn export_fetches_every_page() {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        let thread: Vec<MockSms> = (0..5)
+            .map(|i| {
+                MockSms::inbox(
+                    1,
+                    10 + i,
+                    "+15550001",
+                    &format!("m{}", i),
+                    1_000 * i64::from(i),
+                )
+            })
+            .collect();
+        mock.add_sms(DEVICE, thread).await.unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+
+        let path =
+            std::env::temp_dir().join(format!("connected-export-{}.json", std::process::id()));
+        let request = ExportRequest {
+            device_id: DEVICE.to_string(),
+            thread_ids: vec![1],
+            format: ExportFormat::Json,
+            path: path.clone(),
+            page_size: 2,
+            fetch_from_phone: true,
+        };
+        let summary = export_sms_async(client, request, ContactLookup::new(), None)
+            .await
+            .unwrap();
+        assert_eq!(summary.count, 5);
+        assert_eq!(summary.incomplete, None);
+
+        let value: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value[0]["body"], "m0");
+        assert_eq!(value[4]["body"], "m4");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn export_pages_past_messages_already_stored() {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        let thread: Vec<MockSms> = (0..5)
+            .map(|i| {
+                MockSms::inbox(
+                    1,
+                    10 + i,
+                    "+15550001",
+                    &format!("m{}", i),
+                    1_000 * i64::from(i),
+                )
+            })
+            .collect();
+        mock.add_sms(DEVICE, thread).await.unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+
+        // The newest page is cached, as after opening the thread
+        let store = SmsStore::open_at(DEVICE, None);
+        store.insert(
+            &(3..5)
+                .map(|i| SmsMessage::test(1, 10 + i, &format!("m{}", i), 1_000 * i64::from(i)))
+                .collect::<Vec<_>>(),
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "connected-export-stored-{}.json",
+            std::process::id()
+        ));
+        let request = ExportRequest {
+            device_id: DEVICE.to_string(),
+            thread_ids: vec![1],
+            format: ExportFormat::Json,
+            path: path.clone(),
+            page_size: 2,
+            fetch_from_phone: true,
+        };
+        let summary = export_sms_async(client, request, ContactLookup::new(), Some(store))
+            .await
+            .unwrap();
+        assert_eq!(summary.count, 5);
+        assert_eq!(summary.incomplete, None);
+
+        let value: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value[0]["body"], "m0");
+        assert_eq!(value[4]["body"], "m4");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn unreachable_phone_exports_stored_messages_only() {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+        let store = SmsStore::open_at(DEVICE, None);
+        store.insert(&[SmsMessage::test(1, 10, "stored", 1_000)]);
+
+        let path = std::env::temp_dir().join(format!(
+            "connected-export-unreachable-{}.json",
+            std::process::id()
+        ));
+        let request = ExportRequest {
+            device_id: DEVICE.to_string(),
+            thread_ids: vec![1],
+            format: ExportFormat::Json,
+            path: path.clone(),
+            page_size: 2,
+            fetch_from_phone: false,
+        };
+        let summary = export_sms_async(client, request, ContactLookup::new(), Some(store))
+            .await
+            .unwrap();
+        assert_eq!(summary.count, 1);
+        assert!(summary.incomplete.is_some());
+        assert!(mock.calls_to("conversations").is_empty());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn failed_page_marks_export_incomplete() {
+        let mock = MockDaemon::start().await.unwrap();
+        let mut device = MockDevice::new(DEVICE, "Pixel");
+        device.plugins.retain(|p| p != "kdeconnect_sms");
+        mock.add_device(device).await.unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+        let store = SmsStore::open_at(DEVICE, None);
+        store.insert(&[
+            SmsMessage::test(1, 10, "first", 1_000),
+            SmsMessage::test(2, 20, "second", 2_000),
+        ]);
+
+        let path = std::env::temp_dir().join(format!(
+            "connected-export-incomplete-{}.json",
+            std::process::id()
+        ));
+        let request = ExportRequest {
+            device_id: DEVICE.to_string(),
+            thread_ids: vec![1, 2],
+            format: ExportFormat::Json,
+            path: path.clone(),
+            page_size: 2,
+            fetch_from_phone: true,
+        };
+        let summary = export_sms_async(client, request, ContactLookup::new(), Some(store))
+            .await
+            .unwrap();
+        assert_eq!(summary.count, 2);
+        assert!(summary.incomplete.is_some());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -11,6 +11,7 @@
 //! The recommended pattern is to use both: show cached data immediately, then
 //! refresh in the background for a responsive user experience.
 
+use super::store::SmsStore;
 use crate::app::Message;
 use crate::constants::sms::{
     CONVERSATION_TIMEOUT_CACHED_SECS, CONVERSATION_TIMEOUT_INITIAL_SECS,
@@ -20,12 +21,10 @@ use crate::constants::sms::{
 use futures_util::StreamExt;
 use kdeconnect_dbus::plugins::{
     parse_conversations, parse_messages, parse_sms_message, ConversationSummary,
-    ConversationsProxy, SmsMessage, MAX_CONVERSATIONS,
+    ConversationsProxy, MessageKey, SmsMessage, MAX_CONVERSATIONS,
 };
+use kdeconnect_dbus::KdeConnectClient;
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use zbus::Connection;
 
 /// Fetch cached SMS conversations immediately (fast initial display).
 ///
@@ -37,28 +36,13 @@ use zbus::Connection;
 /// but kept as a potential fallback option.
 #[allow(dead_code)]
 pub async fn fetch_cached_conversations_async(
-    conn: Arc<Mutex<Connection>>,
+    client: KdeConnectClient,
     device_id: String,
 ) -> Message {
-    let conn = conn.lock().await;
-
-    // The conversations interface is on the device path
-    let device_path = format!("{}/devices/{}", kdeconnect_dbus::BASE_PATH, device_id);
-
-    // Build conversations proxy on the device path
-    let conversations_proxy = match ConversationsProxy::builder(&conn)
-        .path(device_path.as_str())
-        .ok()
-        .map(|b| b.build())
-    {
-        Some(fut) => match fut.await {
-            Ok(p) => p,
-            Err(e) => {
-                tracing::warn!("Failed to create conversations proxy for cache: {}", e);
-                return Message::ConversationsCached(Vec::new());
-            }
-        },
-        None => {
+    let conversations_proxy = match client.device(&device_id).conversations_proxy().await {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::warn!("Failed to create conversations proxy for cache: {}", e);
             return Message::ConversationsCached(Vec::new());
         }
     };
@@ -81,26 +65,23 @@ pub async fn fetch_cached_conversations_async(
 }
 
 /// Fetch SMS conversations for a device using signal-based loading.
-pub async fn fetch_conversations_async(conn: Arc<Mutex<Connection>>, device_id: String) -> Message {
-    let conn = conn.lock().await;
-
-    // The conversations interface is on the device path
-    let device_path = format!("{}/devices/{}", kdeconnect_dbus::BASE_PATH, device_id);
+///
+/// The conversations are also written to the device's store, if open.
+pub async fn fetch_conversations_async(
+    client: KdeConnectClient,
+    device_id: String,
+    store: Option<SmsStore>,
+) -> Message {
+    let message = load_conversations(client, device_id).await;
+    persist(store.as_ref(), &message);
+    message
+}
 
-    // Build conversations proxy on the device path
-    let conversations_proxy = match ConversationsProxy::builder(&conn)
-        .path(device_path.as_str())
-        .ok()
-        .map(|b| b.build())
-    {
-        Some(fut) => match fut.await {
-            Ok(p) => p,
-            Err(e) => {
-                return Message::SmsError(format!("Failed to create conversations proxy: {}", e));
-            }
-        },
-        None => {
-            return Message::SmsError("Failed to build conversations proxy path".to_string());
+async fn load_conversations(client: KdeConnectClient, device_id: String) -> Message {
+    let conversations_proxy = match client.device(&device_id).conversations_proxy().await {
+        Ok(p) => p,
+        Err(e) => {
+            return Message::SmsError(format!("Failed to create conversations proxy: {}", e));
         }
     };
 
@@ -384,31 +365,30 @@ async fn fetch_conversations_fallback(conversations_proxy: &ConversationsProxy<'
 }
 
 /// Fetch messages for a specific conversation thread using D-Bus signals.
+///
+/// The messages are also written to the device's store, if open.
 pub async fn fetch_messages_async(
-    conn: Arc<Mutex<Connection>>,
+    client: KdeConnectClient,
     device_id: String,
     thread_id: i64,
     messages_per_page: u32,
+    store: Option<SmsStore>,
 ) -> Message {
-    let conn = conn.lock().await;
-
-    // The conversations interface is on the device path
-    let device_path = format!("{}/devices/{}", kdeconnect_dbus::BASE_PATH, device_id);
+    let message = load_messages(client, device_id, thread_id, messages_per_page).await;
+    persist(store.as_ref(), &message);
+    message
+}
 
-    // Build conversations proxy on the device path
-    let conversations_proxy = match ConversationsProxy::builder(&conn)
-        .path(device_path.as_str())
-        .ok()
-        .map(|b| b.build())
-    {
-        Some(fut) => match fut.await {
-            Ok(p) => p,
-            Err(e) => {
-                return Message::SmsError(format!("Failed to create conversations proxy: {}", e));
-            }
-        },
-        None => {
-            return Message::SmsError("Failed to build conversations proxy path".to_string());
+async fn load_messages(
+    client: KdeConnectClient,
+    device_id: String,
+    thread_id: i64,
+    messages_per_page: u32,
+) -> Message {
+    let conversations_proxy = match client.device(&device_id).conversations_proxy().await {
+        Ok(p) => p,
+        Err(e) => {
+            return Message::SmsError(format!("Failed to create conversations proxy: {}", e));
         }
     };
 
@@ -448,8 +428,7 @@ pub async fn fetch_messages_async(
     }
 
     // Collect messages from signals until conversationLoaded, activity timeout, or hard timeout
-    // Use uid (unique message ID) as key for reliable deduplication
-    let mut messages_map: HashMap<i32, SmsMessage> = HashMap::new();
+    let mut messages_map: HashMap<MessageKey, SmsMessage> = HashMap::new();
     let mut total_message_count: Option<u64> = None;
     let timeout = tokio::time::Duration::from_secs(MESSAGE_FETCH_TIMEOUT_SECS);
     let activity_timeout = tokio::time::Duration::from_millis(SIGNAL_ACTIVITY_TIMEOUT_MS);
@@ -467,8 +446,7 @@ pub async fn fetch_messages_async(
                     Ok(args) => {
                         if let Some(msg) = parse_sms_message(&args.msg) {
                             if msg.thread_id == thread_id {
-                                // Use uid as key for reliable deduplication
-                                messages_map.insert(msg.uid, msg);
+                                messages_map.insert(msg.key(), msg);
                                 last_activity = tokio::time::Instant::now();
                                 tracing::debug!(
                                     "Received message for thread {}, total: {}",
@@ -609,60 +587,85 @@ async fn fetch_messages_fallback(
     Message::MessagesLoaded(thread_id, best_messages, None)
 }
 
+/// A page of older messages.
+#[derive(Debug, Clone)]
+pub struct OlderPage {
+    /// Messages of the page, oldest first.
+    pub messages: Vec<SmsMessage>,
+    /// Whether the page was full, for when the daemon doesn't report the total.
+    pub has_more: bool,
+    /// Number of messages in the thread, as reported by the daemon.
+    pub total: Option<u64>,
+}
+
 /// Fetch older messages for pagination (starting from a given offset).
+///
+/// The messages are also written to the device's store, if open. A page that
+/// fails to load comes back empty, ending the pagination.
 pub async fn fetch_older_messages_async(
-    conn: Arc<Mutex<Connection>>,
+    client: KdeConnectClient,
     device_id: String,
     thread_id: i64,
     start_index: u32,
     count: u32,
+    store: Option<SmsStore>,
 ) -> Message {
-    let conn = conn.lock().await;
-
-    // The conversations interface is on the device path
-    let device_path = format!("{}/devices/{}", kdeconnect_dbus::BASE_PATH, device_id);
+    match fetch_older_page_async(client, device_id, thread_id, start_index, count, store).await {
+        Ok(page) => {
+            Message::OlderMessagesLoaded(thread_id, page.messages, page.has_more, page.total)
+        }
+        Err(e) => {
+            tracing::warn!("Failed to load older messages: {}", e);
+            Message::OlderMessagesLoaded(thread_id, Vec::new(), false, None)
+        }
+    }
+}
 
-    // Build conversations proxy on the device path
-    let conversations_proxy = match ConversationsProxy::builder(&conn)
-        .path(device_path.as_str())
-        .ok()
-        .map(|b| b.build())
-    {
-        Some(fut) => match fut.await {
-            Ok(p) => p,
-            Err(e) => {
-                tracing::warn!("Failed to create conversations proxy: {}", e);
-                return Message::OlderMessagesLoaded(thread_id, Vec::new(), false, None);
-            }
-        },
-        None => {
-            return Message::OlderMessagesLoaded(thread_id, Vec::new(), false, None);
+/// Fetch a page of older messages, failing if the phone doesn't send it.
+///
+/// The messages are also written to the device's store, if open.
+pub async fn fetch_older_page_async(
+    client: KdeConnectClient,
+    device_id: String,
+    thread_id: i64,
+    start_index: u32,
+    count: u32,
+    store: Option<SmsStore>,
+) -> Result<OlderPage, String> {
+    let page = load_older_page(client, device_id, thread_id, start_index, count).await?;
+    if let Some(store) = &store {
+        let stored = store.insert(&page.messages);
+        if stored > 0 {
+            tracing::debug!("Stored {} fetched SMS records", stored);
         }
-    };
+    }
+    Ok(page)
+}
+
+async fn load_older_page(
+    client: KdeConnectClient,
+    device_id: String,
+    thread_id: i64,
+    start_index: u32,
+    count: u32,
+) -> Result<OlderPage, String> {
+    let conversations_proxy = client
+        .device(&device_id)
+        .conversations_proxy()
+        .await
+        .map_err(|e| format!("Failed to create conversations proxy: {}", e))?;
 
     // Set up signal stream for conversationUpdated BEFORE requesting
-    let mut updated_stream = match conversations_proxy.receive_conversation_updated().await {
-        Ok(stream) => stream,
-        Err(e) => {
-            tracing::warn!(
-                "Failed to subscribe to conversationUpdated for older messages: {}",
-                e
-            );
-            return Message::OlderMessagesLoaded(thread_id, Vec::new(), false, None);
-        }
-    };
+    let mut updated_stream = conversations_proxy
+        .receive_conversation_updated()
+        .await
+        .map_err(|e| format!("Failed to subscribe to conversationUpdated: {}", e))?;
 
     // Set up signal stream for conversationLoaded
-    let mut loaded_stream = match conversations_proxy.receive_conversation_loaded().await {
-        Ok(stream) => stream,
-        Err(e) => {
-            tracing::warn!(
-                "Failed to subscribe to conversationLoaded for older messages: {}",
-                e
-            );
-            return Message::OlderMessagesLoaded(thread_id, Vec::new(), false, None);
-        }
-    };
+    let mut loaded_stream = conversations_proxy
+        .receive_conversation_loaded()
+        .await
+        .map_err(|e| format!("Failed to subscribe to conversationLoaded: {}", e))?;
 
     // Request the specific conversation with pagination offset
     tracing::debug!(
@@ -671,17 +674,13 @@ pub async fn fetch_older_messages_async(
         start_index,
         start_index + count
     );
-    if let Err(e) = conversations_proxy
-        .request_conversation(thread_id, start_index as i32, count as i32)
+    conversations_proxy
+        .request_conversation(thread_id, start_index as i32, (start_index + count) as i32)
         .await
-    {
-        tracing::warn!("Failed to request older messages: {}", e);
-        return Message::OlderMessagesLoaded(thread_id, Vec::new(), false, None);
-    }
+        .map_err(|e| format!("Failed to request older messages: {}", e))?;
 
     // Collect messages from signals until conversationLoaded or timeout
-    // Use uid (unique message ID) as key for reliable deduplication
-    let mut messages_map: HashMap<i32, SmsMessage> = HashMap::new();
+    let mut messages_map: HashMap<MessageKey, SmsMessage> = HashMap::new();
     let mut total_message_count: Option<u64> = None;
     let timeout = tokio::time::Duration::from_secs(MESSAGE_FETCH_TIMEOUT_SECS);
     let start_time = tokio::time::Instant::now();
@@ -694,8 +693,7 @@ pub async fn fetch_older_messages_async(
                     Ok(args) => {
                         if let Some(msg) = parse_sms_message(&args.msg) {
                             if msg.thread_id == thread_id {
-                                // Use uid as key for reliable deduplication
-                                messages_map.insert(msg.uid, msg);
+                                messages_map.insert(msg.key(), msg);
                                 tracing::debug!(
                                     "Received older message for thread {}, total: {}",
                                     thread_id,
@@ -730,7 +728,7 @@ pub async fn fetch_older_messages_async(
                                         if let Ok(args) = signal.args() {
                                             if let Some(msg) = parse_sms_message(&args.msg) {
                                                 if msg.thread_id == thread_id {
-                                                    messages_map.insert(msg.uid, msg);
+                                                    messages_map.insert(msg.key(), msg);
                                                 }
                                             }
                                         }
@@ -750,6 +748,13 @@ pub async fn fetch_older_messages_async(
             }
             // Timeout
             _ = tokio::time::sleep_until(start_time + timeout) => {
+                // Without a single message the phone most likely never got the request
+                if messages_map.is_empty() {
+                    return Err(format!(
+                        "Timed out after {:?} waiting for older messages",
+                        timeout
+                    ));
+                }
                 tracing::warn!(
                     "Timeout waiting for older messages, got {} messages",
                     messages_map.len()
@@ -774,6 +779,164 @@ pub async fn fetch_older_messages_async(
         has_more_heuristic,
         total_message_count
     );
-    Message::OlderMessagesLoaded(thread_id, messages, has_more_heuristic, total_message_count)
+    Ok(OlderPage {
+        messages,
+        has_more: has_more_heuristic,
+        total: total_message_count,
+    })
+}
+
+/// Write fetched conversations or messages to the device's store.
+fn persist(store: Option<&SmsStore>, message: &Message) {
+    let Some(store) = store else {
+        return;
+    };
+    let stored = match message {
+        Message::ConversationsLoaded(conversations) => store.insert_conversations(conversations),
+        Message::MessagesLoaded(_, messages, _) => store.insert(messages),
+        _ => 0,
+    };
+    if stored > 0 {
+        tracing::debug!("Stored {} fetched SMS records", stored);
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kdeconnect_dbus::mock::{MockDaemon, MockDevice, MockSms};
+
+    const DEVICE: &str = "abc123";
+
+    /// Start a fake daemon with one device whose conversation cache holds `messages`.
+    async fn mock_with_sms(messages: Vec<MockSms>) -> (MockDaemon, KdeConnectClient) {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        mock.add_sms(DEVICE, messages).await.unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+        (mock, client)
+    }
+
+    fn thread_of_three() -> Vec<MockSms> {
+        vec![
+            MockSms::inbox(1, 10, "+15550001", "oldest", 1_000),
+            MockSms::sent(1, 11, "+15550001", "middle", 2_000),
+            MockSms::inbox(1, 12, "+15550001", "newest", 3_000),
+            MockSms::inbox(2, 20, "+15550002", "other thread", 2_500),
+        ]
+    }
+
+    #[tokio::test]
+    async fn conversations_load_newest_first() {
+        let (mock, client) = mock_with_sms(thread_of_three()).await;
+
+        match fetch_conversations_async(client, DEVICE.to_string(), None).await {
+            Message::ConversationsLoaded(conversations) => {
+                assert_eq!(conversations.len(), 2);
+                assert_eq!(conversations[0].thread_id, 1);
+                assert_eq!(conversations[0].last_message, "newest");
+                assert_eq!(conversations[1].thread_id, 2);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert!(mock
+            .calls_to("conversations")
+            .iter()
+            .any(|c| c.method == "requestAllConversationThreads"));
+    }
+
+    #[tokio::test]
+    async fn waiting_for_conversations_does_not_stall_other_calls() {
+        // The phone never answers, so the fetch waits for its full timeout
+        let (_mock, client) = mock_with_sms(Vec::new()).await;
+        let fetch = tokio::spawn(fetch_conversations_async(
+            client.clone(),
+            DEVICE.to_string(),
+            None,
+        ));
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let started = std::time::Instant::now();
+        client.device(DEVICE).ping().await.unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert!(!fetch.is_finished());
+        fetch.abort();
+    }
+
+    #[tokio::test]
+    async fn messages_load_for_requested_thread_only() {
+        let (_mock, client) = mock_with_sms(thread_of_three()).await;
+        let store = SmsStore::open_at(DEVICE, None);
+
+        match fetch_messages_async(client, DEVICE.to_string(), 1, 10, Some(store.clone())).await {
+            Message::MessagesLoaded(thread_id, messages, total) => {
+                assert_eq!(thread_id, 1);
+                let bodies: Vec<&str> = messages.iter().map(|m| m.body.as_str()).collect();
+                assert_eq!(bodies, vec!["oldest", "middle", "newest"]);
+                assert_eq!(total, Some(3));
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert_eq!(store.messages(1).len(), 3);
+        assert!(store.messages(2).is_empty());
+    }
+
+    #[tokio::test]
+    async fn messages_are_limited_to_one_page() {
+        let (_mock, client) = mock_with_sms(thread_of_three()).await;
+
+        match fetch_messages_async(client, DEVICE.to_string(), 1, 2, None).await {
+            Message::MessagesLoaded(_, messages, total) => {
+                let bodies: Vec<&str> = messages.iter().map(|m| m.body.as_str()).collect();
+                assert_eq!(bodies, vec!["middle", "newest"]);
+                assert_eq!(total, Some(3));
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn older_messages_continue_from_offset() {
+        let (mock, client) = mock_with_sms(thread_of_three()).await;
+
+        match fetch_older_messages_async(client, DEVICE.to_string(), 1, 2, 2, None).await {
+            Message::OlderMessagesLoaded(thread_id, messages, has_more, total) => {
+                assert_eq!(thread_id, 1);
+                assert_eq!(messages.len(), 1);
+                assert_eq!(messages[0].body, "oldest");
+                assert!(!has_more);
+                assert_eq!(total, Some(3));
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        let request = mock
+            .calls_to("conversations")
+            .into_iter()
+            .find(|c| c.method == "requestConversation")
+            .unwrap();
+        assert_eq!(request.args, vec!["1", "2", "4"]);
+    }
+
+    #[tokio::test]
+    async fn slow_sync_does_not_block_other_actions() {
+        // No messages: the sync waits for the phone until it times out
+        let (_mock, client) = mock_with_sms(Vec::new()).await;
+        let sync = tokio::spawn(fetch_conversations_async(
+            client.clone(),
+            DEVICE.to_string(),
+            None,
+        ));
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let ping = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            crate::device::actions::send_ping_async(client, DEVICE.to_string()),
+        )
+        .await;
+        assert_eq!(ping, Ok(Ok(())));
+        assert!(!sync.is_finished());
+        sync.abort();
+    }
+}
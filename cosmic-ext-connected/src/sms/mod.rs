@@ -1,11 +1,25 @@
 //! SMS-related functionality for KDE Connect conversations.
 
+pub mod attachments;
+pub mod clipboard;
 pub mod conversation_subscription;
+pub mod export;
 pub mod fetch;
+pub mod notification;
+pub mod search;
 pub mod send;
+pub mod sim;
+pub mod store;
 pub mod views;
 
+pub use attachments::*;
+pub use clipboard::*;
 pub use conversation_subscription::*;
+pub use export::*;
 pub use fetch::*;
+pub use notification::*;
+pub use search::*;
 pub use send::*;
+pub use sim::*;
+pub use store::*;
 pub use views::*;
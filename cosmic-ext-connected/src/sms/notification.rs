@@ -0,0 +1,124 @@
+//! Desktop notifications for incoming SMS messages.
+//!
+//! Each notification offers "Reply" and "Open conversation" actions, and
+//! clicking it opens the conversation too. Notification servers advertising
+//! the `inline-reply` capability (Plasma's) take the reply in the notification
+//! itself and report it with a `NotificationReplied` signal; elsewhere "Reply"
+//! opens the thread with the compose field focused.
+//!
+//! SMS notifications are shown by one applet process (see
+//! `should_show_sms_notification`), which alone knows the desktop ID and
+//! handles the actions.
+
+use crate::app::Message;
+use crate::device::mirror::{DesktopNotificationsProxy, ACTION_REPLY};
+use crate::fl;
+use kdeconnect_dbus::contacts::ContactLookup;
+use kdeconnect_dbus::plugins::SmsMessage;
+
+/// Action key invoked by clicking the notification itself; its label is
+/// the "Open conversation" button on servers that show one.
+pub const ACTION_DEFAULT: &str = "default";
+
+/// Action key of a reply typed in the notification.
+pub const ACTION_INLINE_REPLY: &str = "inline-reply";
+
+/// Hint setting the placeholder of the inline reply field.
+const REPLY_PLACEHOLDER_HINT: &str = "x-kde-reply-placeholder-text";
+
+/// Conversation an SMS notification was shown for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmsNotificationThread {
+    pub device_id: String,
+    pub thread_id: i64,
+    /// Addresses to reply to.
+    pub addresses: Vec<String>,
+    /// SIM the message arrived on.
+    pub sub_id: i64,
+}
+
+impl SmsNotificationThread {
+    fn new(device_id: String, message: &SmsMessage) -> Self {
+        Self {
+            device_id,
+            thread_id: message.thread_id,
+            addresses: message.addresses.clone(),
+            sub_id: message.sub_id,
+        }
+    }
+}
+
+/// Show a desktop notification for an incoming SMS.
+///
+/// Loads the device's contacts to name the sender.
+pub async fn show_sms_notification_async(
+    connection: Option<zbus::Connection>,
+    device_id: String,
+    message: SmsMessage,
+    show_sender: bool,
+    show_content: bool,
+) -> Message {
+    let thread = SmsNotificationThread::new(device_id, &message);
+
+    let summary = if show_sender {
+        let contacts = ContactLookup::load_for_device(&thread.device_id).await;
+        let sender_name = contacts.get_name_or_number(message.primary_address());
+        fl!("sms-notification-title-from", sender = sender_name)
+    } else {
+        fl!("sms-notification-title")
+    };
+    let body = if show_content {
+        message.body
+    } else {
+        fl!("sms-notification-body-hidden")
+    };
+    let inline_reply = match connection {
+        Some(connection) => supports_inline_reply(&connection).await,
+        None => false,
+    };
+
+    // Use spawn_blocking to run notify_rust in a blocking context
+    // to avoid "Cannot start a runtime from within a runtime" panics
+    let result = tokio::task::spawn_blocking(move || {
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .summary(&summary)
+            .body(&body)
+            .icon("phone-symbolic")
+            .appname("Connected")
+            .action(ACTION_DEFAULT, &fl!("open-conversation"));
+        if inline_reply {
+            notification
+                .action(ACTION_INLINE_REPLY, &fl!("reply"))
+                .hint(notify_rust::Hint::Custom(
+                    REPLY_PLACEHOLDER_HINT.to_string(),
+                    fl!("type-message"),
+                ));
+        } else {
+            notification.action(ACTION_REPLY, &fl!("reply"));
+        }
+        notification.show().map(|handle| handle.id())
+    })
+    .await;
+
+    let result = match result {
+        Ok(shown) => shown.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    Message::SmsNotificationShown(thread, result)
+}
+
+/// Whether the notification server takes replies in the notification.
+async fn supports_inline_reply(connection: &zbus::Connection) -> bool {
+    let capabilities = match DesktopNotificationsProxy::new(connection).await {
+        Ok(proxy) => proxy.get_capabilities().await,
+        Err(e) => Err(e),
+    };
+    match capabilities {
+        Ok(capabilities) => capabilities.iter().any(|c| c == ACTION_INLINE_REPLY),
+        Err(e) => {
+            tracing::debug!("Failed to read notification server capabilities: {}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Full-text search over the SMS messages of a device.
+//!
+//! Searches the device's [`SmsStore`], so every message synced so far is
+//! found, not just the threads currently loaded. Conversations whose
+//! participants match by contact name or number come first, followed by
+//! matching messages, newest first.
+
+use super::store::SmsStore;
+use crate::app::Message;
+use crate::constants::sms::{MAX_SEARCH_RESULTS, SEARCH_SNIPPET_CONTEXT_CHARS};
+use kdeconnect_dbus::contacts::ContactLookup;
+use kdeconnect_dbus::plugins::ConversationSummary;
+use std::collections::HashSet;
+
+/// One search result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    /// Thread the hit belongs to.
+    pub thread_id: i64,
+    /// Matching message, or `None` if the conversation matched by participant.
+    pub uid: Option<i32>,
+    /// Participants of the thread.
+    pub addresses: Vec<String>,
+    /// Text around the match.
+    pub snippet: String,
+    /// Timestamp of the message in milliseconds.
+    pub date: i64,
+}
+
+/// Search conversations and message bodies for `query` (case-insensitive).
+///
+/// `conversations` are the ones currently shown; they are searched together
+/// with the conversations kept in the store.
+pub fn search(
+    query: &str,
+    conversations: &[ConversationSummary],
+    store: Option<&SmsStore>,
+    contacts: &ContactLookup,
+) -> Vec<SearchHit> {
+    let query = fold(query.trim());
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let stored = store.map(|s| s.conversations()).unwrap_or_default();
+    let mut seen_threads = HashSet::new();
+    let mut hits: Vec<SearchHit> = conversations
+        .iter()
+        .chain(&stored)
+        .filter(|c| seen_threads.insert(c.thread_id))
+        .filter(|c| {
+            c.addresses.iter().any(|address| {
+                contains(&contacts.get_name_or_number(address), &query) || contains(address, &query)
+            })
+        })
+        .map(|c| SearchHit {
+            thread_id: c.thread_id,
+            uid: None,
+            addresses: c.addresses.clone(),
+            snippet: c.last_message.chars().take(50).collect(),
+            date: c.timestamp,
+        })
+        .collect();
+
+    if let Some(store) = store {
+        let messages = store.find_messages(|m| contains(&m.body, &query));
+        hits.extend(messages.into_iter().map(|m| SearchHit {
+            thread_id: m.thread_id,
+            uid: Some(m.uid),
+            snippet: snippet(&m.body, &query),
+            addresses: m.addresses,
+            date: m.date,
+        }));
+    }
+
+    hits.truncate(MAX_SEARCH_RESULTS);
+    hits
+}
+
+/// Run [`search`] on a blocking thread, so a large store doesn't stall the UI.
+///
+/// `generation` is handed back with the results; the app drops results of
+/// a query that has been edited since.
+pub async fn search_async(
+    query: String,
+    conversations: Vec<ConversationSummary>,
+    store: Option<SmsStore>,
+    contacts: ContactLookup,
+    generation: u64,
+) -> Message {
+    let hits = tokio::task::spawn_blocking(move || {
+        search(&query, &conversations, store.as_ref(), &contacts)
+    })
+    .await
+    .unwrap_or_else(|e| {
+        tracing::error!("SMS search failed: {}", e);
+        Vec::new()
+    });
+    Message::SmsSearchResults { generation, hits }
+}
+
+/// Lowercase one character, so character positions stay the same as in
+/// the original text.
+fn fold_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn fold(text: &str) -> Vec<char> {
+    text.chars().map(fold_char).collect()
+}
+
+fn contains(haystack: &str, needle: &[char]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+/// Character position of the first match of the folded `needle`, folding
+/// `haystack` as it goes instead of copying it.
+fn find(haystack: &str, needle: &[char]) -> Option<usize> {
+    let mut rest = haystack.chars();
+    let mut position = 0;
+    loop {
+        if rest
+            .clone()
+            .map(fold_char)
+            .take(needle.len())
+            .eq(needle.iter().copied())
+        {
+            return Some(position);
+        }
+        rest.next()?;
+        position += 1;
+    }
+}
+
+/// The part of `body` around the first match of the folded `query`.
+fn snippet(body: &str, query: &[char]) -> String {
+    let chars: Vec<char> = body.chars().collect();
+    let Some(start) = find(body, query) else {
+        return chars.iter().take(50).collect();
+    };
+    let from = start.saturating_sub(SEARCH_SNIPPET_CONTEXT_CHARS);
+    let to = (start + query.len() + SEARCH_SNIPPET_CONTEXT_CHARS).min(chars.len());
+
+    let mut snippet = String::new();
+    if from > 0 {
+        snippet.push('…');
+    }
+    snippet.extend(&chars[from..to]);
+    if to < chars.len() {
+        snippet.push('…');
+    }
+    // Keep the snippet on one line in the result list
+    snippet.replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kdeconnect_dbus::plugins::SmsMessage;
+
+    fn store() -> SmsStore {
+        let store = SmsStore::open_at("abc", None);
+        store.insert(&[
+            SmsMessage::test(1, 10, "Dinner at eight?", 1_000),
+            SmsMessage::test(1, 11, "See you at DINNER", 3_000),
+            SmsMessage {
+                addresses: vec!["+15550002".to_string()],
+                ..SmsMessage::test(2, 20, "Parcel delivered", 2_000)
+            },
+        ]);
+        store
+    }
+
+    #[test]
+    fn message_bodies_match_case_insensitively_newest_first() {
+        let hits = search("dinner", &[], Some(&store()), &ContactLookup::new());
+        let uids: Vec<Option<i32>> = hits.iter().map(|h| h.uid).collect();
+        assert_eq!(uids, vec![Some(11), Some(10)]);
+        assert_eq!(hits[0].thread_id, 1);
+    }
+
+    #[test]
+    fn conversations_match_by_participant() {
+        let hits = search("0002", &[], Some(&store()), &ContactLookup::new());
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].thread_id, 2);
+        assert_eq!(hits[0].uid, None);
+    }
+
+    #[test]
+    fn blank_query_finds_nothing() {
+        assert!(search("  ", &[], Some(&store()), &ContactLookup::new()).is_empty());
+    }
+
+    #[test]
+    fn matches_are_found_by_character_position() {
+        assert_eq!(find("Grüße aus KÖLN", &fold("köln")), Some(10));
+        assert_eq!(find("abc", &fold("abcd")), None);
+        assert_eq!(find("", &fold("a")), None);
+    }
+
+    #[test]
+    fn snippets_show_context_around_the_match() {
+        let body = format!("{}needle{}", "a".repeat(40), "b".repeat(40));
+        let text = snippet(&body, &fold("NEEDLE"));
+        assert!(text.starts_with('…'));
+        assert!(text.ends_with('…'));
+        assert!(text.contains("needle"));
+        assert_eq!(
+            text.chars().count(),
+            "needle".len() + 2 * SEARCH_SNIPPET_CONTEXT_CHARS + 2
+        );
+
+        assert_eq!(snippet("short needle", &fold("needle")), "short needle");
+    }
+}
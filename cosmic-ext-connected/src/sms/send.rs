@@ -1,11 +1,10 @@
 //! SMS sending functionality.
 
+use super::attachments::{download_attachment, file_url};
 use crate::app::Message;
-use kdeconnect_dbus::plugins::ConversationsProxy;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use zbus::zvariant::{Structure, Value};
-use zbus::Connection;
+use crate::errors::describe;
+use kdeconnect_dbus::plugins::SmsMessage;
+use kdeconnect_dbus::KdeConnectClient;
 
 /// Send an SMS message using the SMS plugin's sendSms method directly.
 ///
@@ -14,43 +13,30 @@ use zbus::Connection;
 /// MMS group messages to work correctly.
 ///
 /// Sends to ALL addresses in the recipients list, supporting group conversations.
+/// With attachments (`file://` URLs) the phone sends an MMS.
 pub async fn send_sms_async(
-    conn: Arc<Mutex<Connection>>,
+    client: KdeConnectClient,
     device_id: String,
     _thread_id: i64,
     recipients: Vec<String>,
     message: String,
+    attachment_urls: Vec<String>,
     sub_id: i64,
 ) -> Message {
-    let conn = conn.lock().await;
-    // SMS plugin is on /sms subpath
-    let sms_path = format!("{}/devices/{}/sms", kdeconnect_dbus::BASE_PATH, device_id);
-
-    // Format addresses as D-Bus structs: array of variants containing structs with single string
-    // This matches what KDE Connect's ConversationAddress serializes to
-    // NOTE: Do NOT deduplicate - MMS groups need exact address list to match the thread
-    let addresses: Vec<Value<'_>> = recipients
-        .iter()
-        .map(|addr| Value::Structure(Structure::from((addr.clone(),))))
-        .collect();
-    let empty_attachments: Vec<Value<'_>> = vec![];
+    let device = client.device(&device_id);
 
     tracing::info!(
-        "Sending SMS via sendSms to {} recipient(s), sub_id={}",
-        addresses.len(),
+        "Sending SMS via sendSms to {} recipient(s) with {} attachment(s), sub_id={}",
+        recipients.len(),
+        attachment_urls.len(),
         sub_id
     );
 
     // Use the SMS plugin's sendSms method directly with sub_id
     // This is what replyToConversation does internally after looking up addresses from cache
-    let result = conn
-        .call_method(
-            Some("org.kde.kdeconnect.daemon"),
-            sms_path.as_str(),
-            Some("org.kde.kdeconnect.device.sms"),
-            "sendSms",
-            &(addresses, message.as_str(), empty_attachments, sub_id),
-        )
+    // NOTE: Do NOT deduplicate - MMS groups need exact address list to match the thread
+    let result = device
+        .send_sms(&recipients, &message, &attachment_urls, sub_id)
         .await;
 
     match result {
@@ -60,50 +46,145 @@ pub async fn send_sms_async(
         }
         Err(e) => {
             tracing::error!("SMS send failed: {}", e);
-            Message::SmsSendResult(Err(format!("Send failed: {}", e)))
+            Message::SmsSendResult(Err(describe(&e)))
         }
     }
 }
 
+/// Resend a failed message, attachments included.
+///
+/// The attachments of a failed MMS only exist on the phone, so their files
+/// are downloaded first and sent again from the daemon's cache.
+pub async fn retry_sms_async(
+    client: KdeConnectClient,
+    device_id: String,
+    failed: SmsMessage,
+) -> Message {
+    let mut attachment_urls = Vec::with_capacity(failed.attachments.len());
+    for attachment in &failed.attachments {
+        match download_attachment(
+            &client,
+            &device_id,
+            attachment.part_id,
+            &attachment.unique_identifier,
+        )
+        .await
+        {
+            Ok(path) => attachment_urls.push(file_url(&path)),
+            Err(e) => return Message::SmsSendResult(Err(e)),
+        }
+    }
+    send_sms_async(
+        client,
+        device_id,
+        failed.thread_id,
+        failed.addresses,
+        failed.body,
+        attachment_urls,
+        failed.sub_id,
+    )
+    .await
+}
+
 /// Send an SMS to a new recipient (creates or adds to existing conversation).
+///
+/// Without a `sub_id` the phone's default SIM is used.
 pub async fn send_new_sms_async(
-    conn: Arc<Mutex<Connection>>,
+    client: KdeConnectClient,
     device_id: String,
     recipient: String,
     message: String,
+    attachment_urls: Vec<String>,
+    sub_id: Option<i64>,
 ) -> Message {
-    let conn = conn.lock().await;
-    let device_path = format!("{}/devices/{}", kdeconnect_dbus::BASE_PATH, device_id);
-
-    let conversations_proxy = match ConversationsProxy::builder(&conn)
-        .path(device_path.as_str())
-        .ok()
-        .map(|b| b.build())
-    {
-        Some(fut) => match fut.await {
-            Ok(p) => p,
-            Err(e) => {
-                return Message::NewMessageSendResult(Err(format!(
-                    "Failed to create proxy: {}",
-                    e
-                )));
-            }
-        },
+    let device = client.device(&device_id);
+    let recipients = [recipient];
+
+    let result = match sub_id {
+        Some(sub_id) => {
+            device
+                .send_sms(&recipients, &message, &attachment_urls, sub_id)
+                .await
+        }
         None => {
-            return Message::NewMessageSendResult(Err("Failed to build proxy path".to_string()));
+            device
+                .send_without_conversation(&recipients, &message, &attachment_urls)
+                .await
         }
     };
+    match result {
+        Ok(()) => Message::NewMessageSendResult(Ok("Message sent".to_string())),
+        Err(e) => Message::NewMessageSendResult(Err(describe(&e))),
+    }
+}
 
-    // Format address as D-Bus struct for KDE Connect
-    // KDE Connect's ConversationAddress is a struct containing a single string: (s)
-    let addresses: Vec<Value<'_>> = vec![Value::Structure(Structure::from((recipient.clone(),)))];
-    let empty_attachments: Vec<Value<'_>> = vec![];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kdeconnect_dbus::mock::{MockDaemon, MockDevice};
+    use kdeconnect_dbus::plugins::{Attachment, MessageType};
 
-    match conversations_proxy
-        .send_without_conversation(addresses, &message, empty_attachments)
-        .await
-    {
-        Ok(()) => Message::NewMessageSendResult(Ok("Message sent".to_string())),
-        Err(e) => Message::NewMessageSendResult(Err(format!("Send failed: {}", e))),
+    const DEVICE: &str = "abc123";
+
+    #[tokio::test]
+    async fn new_messages_use_the_chosen_sim() {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+
+        for sub_id in [Some(2), None] {
+            let message = send_new_sms_async(
+                client.clone(),
+                DEVICE.to_string(),
+                "+15550001".to_string(),
+                "hi".to_string(),
+                Vec::new(),
+                sub_id,
+            )
+            .await;
+            assert!(matches!(message, Message::NewMessageSendResult(Ok(_))));
+        }
+
+        let sms_calls = mock.calls_to("sms");
+        assert_eq!(sms_calls.len(), 1);
+        assert_eq!(sms_calls[0].method, "sendSms");
+        assert_eq!(sms_calls[0].args[3], "2");
+        let conversation_calls = mock.calls_to("conversations");
+        assert_eq!(conversation_calls[0].method, "sendWithoutConversation");
+    }
+
+    #[tokio::test]
+    async fn failed_mms_is_retried_with_its_attachments() {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+
+        let failed = SmsMessage {
+            message_type: MessageType::Failed,
+            sub_id: 2,
+            attachments: vec![Attachment {
+                part_id: 4,
+                mime_type: "image/jpeg".to_string(),
+                file_name: "PART_4_cat.jpg".to_string(),
+                unique_identifier: "PART_4_cat.jpg".to_string(),
+                thumbnail: Vec::new(),
+            }],
+            ..SmsMessage::test(1, 10, "look", 1_000)
+        };
+        let message = retry_sms_async(client, DEVICE.to_string(), failed).await;
+        assert!(matches!(message, Message::SmsSendResult(Ok(_))));
+
+        assert_eq!(
+            mock.calls_to("conversations")[0].method,
+            "requestAttachmentFile"
+        );
+        let sms_calls = mock.calls_to("sms");
+        assert_eq!(sms_calls[0].method, "sendSms");
+        assert_eq!(sms_calls[0].args[1], "look");
+        assert_eq!(sms_calls[0].args[2], "1");
     }
 }
@@ -0,0 +1,134 @@
+//! SIM selection for dual-SIM phones.
+//!
+//! KDE Connect does not report the phone's SIM cards over D-Bus, so the
+//! subscription IDs are collected from the messages seen so far: every
+//! message records the subscription it was sent or received on. IDs are
+//! labelled "SIM 1", "SIM 2", ... in ascending order, which matches the
+//! slot order on most phones.
+
+use crate::fl;
+use kdeconnect_dbus::plugins::SmsMessage;
+use kdeconnect_dbus::{normalize_phone_number, phone_suffix};
+
+/// SIM subscriptions known for the SMS device.
+#[derive(Debug, Clone, Default)]
+pub struct SimCards {
+    /// Subscription IDs, ascending.
+    sub_ids: Vec<i64>,
+    /// Display label of each subscription, in the same order.
+    labels: Vec<String>,
+}
+
+impl SimCards {
+    /// Add subscription IDs; `-1` (the phone's default SIM) is ignored.
+    pub fn extend(&mut self, sub_ids: impl IntoIterator<Item = i64>) {
+        let known = self.sub_ids.len();
+        self.sub_ids
+            .extend(sub_ids.into_iter().filter(|&sub_id| sub_id >= 0));
+        self.sub_ids.sort_unstable();
+        self.sub_ids.dedup();
+        if self.sub_ids.len() != known {
+            self.labels = (1..=self.sub_ids.len())
+                .map(|number| fl!("sim-label", number = number))
+                .collect();
+        }
+    }
+
+    /// Add the subscriptions the messages were sent or received on.
+    pub fn add_messages<'a>(&mut self, messages: impl IntoIterator<Item = &'a SmsMessage>) {
+        self.extend(messages.into_iter().map(|m| m.sub_id));
+    }
+
+    /// Forget all subscriptions, e.g. when switching devices.
+    pub fn clear(&mut self) {
+        self.sub_ids.clear();
+        self.labels.clear();
+    }
+
+    /// Whether there is more than one SIM to choose from.
+    pub fn has_choice(&self) -> bool {
+        self.sub_ids.len() > 1
+    }
+
+    /// Labels for the SIM picker.
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Index of a subscription in [`labels`](Self::labels).
+    pub fn position(&self, sub_id: i64) -> Option<usize> {
+        self.sub_ids.iter().position(|&id| id == sub_id)
+    }
+
+    /// Subscription ID at a picker index.
+    pub fn sub_id(&self, index: usize) -> Option<i64> {
+        self.sub_ids.get(index).copied()
+    }
+}
+
+/// Subscription a thread was last used with, from its messages (oldest first).
+pub fn thread_sub_id(messages: &[SmsMessage]) -> Option<i64> {
+    messages
+        .iter()
+        .rev()
+        .map(|m| m.sub_id)
+        .find(|&sub_id| sub_id >= 0)
+}
+
+/// Config key of the default SIM for a set of recipients on a device.
+///
+/// Numbers are reduced to their suffix so `+1 555-0001` and `5550001` share
+/// a default.
+pub fn sim_default_key(device_id: &str, addresses: &[String]) -> String {
+    let mut numbers: Vec<String> = addresses
+        .iter()
+        .map(|address| phone_suffix(&normalize_phone_number(address)).to_string())
+        .collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+    format!("{}:{}", device_id, numbers.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A message received on subscription `sub_id`.
+    fn on_sim(sub_id: i64) -> SmsMessage {
+        SmsMessage {
+            sub_id,
+            ..SmsMessage::test(1, 1, "hi", 1_000)
+        }
+    }
+
+    #[test]
+    fn subscriptions_are_discovered_from_messages() {
+        let mut sims = SimCards::default();
+        sims.add_messages(&[on_sim(-1), on_sim(3)]);
+        assert!(!sims.has_choice());
+        sims.add_messages(&[on_sim(1), on_sim(3)]);
+        assert!(sims.has_choice());
+        assert_eq!(sims.labels().len(), 2);
+        assert_eq!(sims.position(3), Some(1));
+        assert_eq!(sims.sub_id(0), Some(1));
+        assert_eq!(sims.position(-1), None);
+    }
+
+    #[test]
+    fn thread_uses_its_newest_known_subscription() {
+        assert_eq!(thread_sub_id(&[on_sim(1), on_sim(2), on_sim(-1)]), Some(2));
+        assert_eq!(thread_sub_id(&[on_sim(-1)]), None);
+    }
+
+    #[test]
+    fn default_keys_ignore_number_formatting() {
+        assert_eq!(
+            sim_default_key("abc", &["+1 (555) 123-4567".to_string()]),
+            sim_default_key("abc", &["5551234567".to_string()])
+        );
+        assert_ne!(
+            sim_default_key("abc", &["5551234567".to_string()]),
+            sim_default_key("def", &["5551234567".to_string()])
+        );
+    }
+}
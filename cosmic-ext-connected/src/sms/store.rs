@@ -0,0 +1,642 @@
+//! Persistent SMS store, one file per device.
+//!
+//! Messages synced from the phone are appended to a JSON Lines log under the
+//! XDG data directory so conversations can be shown straight after a restart
+//! and while the phone is offline. The first line of the log records the
+//! schema version; every other line is a message or a conversation summary.
+//! Messages are keyed by their [`MessageKey`] and summaries by thread ID, so
+//! anything synced again (e.g. after being marked read) replaces the older
+//! copy instead of being shown twice.
+//!
+//! Writes happen on a thread of their own, so the UI never waits for the
+//! disk. COSMIC may run several applet processes, so every write takes an
+//! exclusive `flock` on a sibling lock file.
+
+use crate::app::Message;
+use crate::log_store::{log_path, rewrite, write_line, LogLock};
+use kdeconnect_dbus::plugins::{ConversationSummary, MessageKey, SmsMessage, MAX_CONVERSATIONS};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+
+/// Version of the on-disk format. Logs written with another version are set aside.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Rewrite the log once it holds this many superseded records.
+const COMPACT_THRESHOLD: usize = 500;
+
+/// First line of every log.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    version: u32,
+}
+
+/// One line of the log after the header.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Record {
+    Message(SmsMessage),
+    Conversation(ConversationSummary),
+}
+
+/// Store contents as read from the log.
+#[derive(Default)]
+struct Contents {
+    messages: HashMap<MessageKey, SmsMessage>,
+    conversations: HashMap<i64, ConversationSummary>,
+    /// Records that a later record for the same message or thread replaced.
+    stale_records: usize,
+}
+
+/// Messages stored for one device.
+///
+/// Cloning is cheap; clones share the loaded messages.
+#[derive(Clone)]
+pub struct SmsStore {
+    inner: Arc<Mutex<StoreInner>>,
+}
+
+struct StoreInner {
+    device_id: String,
+    /// Log file, or `None` when there is no data directory to keep it in.
+    path: Option<PathBuf>,
+    contents: Contents,
+    /// Queue of the thread writing the log, started by the first write.
+    writer: Option<mpsc::Sender<WriteJob>>,
+}
+
+/// Work for a store's writer thread.
+enum WriteJob {
+    Append(Vec<Record>),
+    /// Answered once every earlier job is done.
+    Flush(mpsc::Sender<()>),
+}
+
+impl SmsStore {
+    /// Open the store for a device in the user's data directory.
+    ///
+    /// Blocks while the log is read; use [`open_store_async`] from the UI.
+    pub fn open(device_id: &str) -> Self {
+        let path = log_path("sms", device_id);
+        if path.is_none() {
+            tracing::warn!("No data directory, SMS messages won't be kept on disk");
+        }
+        Self::open_at(device_id, path)
+    }
+
+    /// Open a store backed by the log at `path` (in memory only if `None`).
+    pub fn open_at(device_id: &str, path: Option<PathBuf>) -> Self {
+        let mut inner = StoreInner {
+            device_id: device_id.to_string(),
+            path,
+            contents: Contents::default(),
+            writer: None,
+        };
+        if let Some(path) = inner.path.clone() {
+            match read_log(&path) {
+                Ok(contents) => inner.contents = contents,
+                Err(e) => tracing::warn!("Failed to read SMS store {}: {}", path.display(), e),
+            }
+            if inner.contents.stale_records >= COMPACT_THRESHOLD {
+                match compact(&path) {
+                    Ok(contents) => inner.contents = contents,
+                    Err(e) => {
+                        tracing::warn!("Failed to compact SMS store {}: {}", path.display(), e)
+                    }
+                }
+            }
+        }
+        tracing::debug!(
+            "Opened SMS store for {} with {} messages in {} conversations",
+            device_id,
+            inner.contents.messages.len(),
+            inner.contents.conversations.len()
+        );
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    /// Device the messages belong to.
+    pub fn device_id(&self) -> String {
+        self.lock().device_id.clone()
+    }
+
+    /// Add or update messages, queueing the ones that changed for the log.
+    ///
+    /// Returns how many messages were new or changed. Write failures are
+    /// logged; the messages are still kept in memory.
+    pub fn insert<'a>(&self, messages: impl IntoIterator<Item = &'a SmsMessage>) -> usize {
+        let mut inner = self.lock();
+        let mut changed = Vec::new();
+        for message in messages {
+            let key = message.key();
+            match inner.contents.messages.get(&key) {
+                Some(existing) if existing == message => continue,
+                Some(_) => inner.contents.stale_records += 1,
+                None => {}
+            }
+            inner.contents.messages.insert(key, message.clone());
+            changed.push(Record::Message(message.clone()));
+        }
+        inner.append(changed)
+    }
+
+    /// Add or update conversation summaries, queueing the ones that changed.
+    ///
+    /// Returns how many summaries were new or changed.
+    pub fn insert_conversations<'a>(
+        &self,
+        conversations: impl IntoIterator<Item = &'a ConversationSummary>,
+    ) -> usize {
+        let mut inner = self.lock();
+        let mut changed = Vec::new();
+        for conversation in conversations {
+            match inner.contents.conversations.get(&conversation.thread_id) {
+                Some(existing) if existing == conversation => continue,
+                Some(_) => inner.contents.stale_records += 1,
+                None => {}
+            }
+            inner
+                .contents
+                .conversations
+                .insert(conversation.thread_id, conversation.clone());
+            changed.push(Record::Conversation(conversation.clone()));
+        }
+        inner.append(changed)
+    }
+
+    /// Conversations with their latest message, newest first.
+    ///
+    /// Stored summaries are updated with any newer message kept for the thread.
+    pub fn conversations(&self) -> Vec<ConversationSummary> {
+        let inner = self.lock();
+        let mut latest = inner.contents.conversations.clone();
+        for message in inner.contents.messages.values() {
+            let newer = latest
+                .get(&message.thread_id)
+                .map_or(true, |c| message.date > c.timestamp);
+            if newer {
+                latest.insert(
+                    message.thread_id,
+                    ConversationSummary {
+                        thread_id: message.thread_id,
+                        addresses: message.addresses.clone(),
+                        last_message: message.body.clone(),
+                        timestamp: message.date,
+                        unread: !message.read,
+                    },
+                );
+            }
+        }
+        let mut conversations: Vec<ConversationSummary> = latest.into_values().collect();
+        conversations.sort_by_key(|c| std::cmp::Reverse(c.timestamp));
+        conversations.truncate(MAX_CONVERSATIONS);
+        conversations
+    }
+
+    /// Messages of a thread, oldest first.
+    pub fn messages(&self, thread_id: i64) -> Vec<SmsMessage> {
+        let inner = self.lock();
+        let mut messages: Vec<SmsMessage> = inner
+            .contents
+            .messages
+            .values()
+            .filter(|m| m.thread_id == thread_id)
+            .cloned()
+            .collect();
+        messages.sort_by_key(|m| (m.date, m.uid));
+        messages
+    }
+
+    /// IDs of every thread with a stored summary or message.
+    pub fn thread_ids(&self) -> Vec<i64> {
+        let inner = self.lock();
+        let mut ids: Vec<i64> = inner
+            .contents
+            .conversations
+            .keys()
+            .copied()
+            .chain(inner.contents.messages.values().map(|m| m.thread_id))
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// SIM subscription IDs of the stored messages, ascending.
+    pub fn sub_ids(&self) -> Vec<i64> {
+        let inner = self.lock();
+        let mut ids: Vec<i64> = inner.contents.messages.values().map(|m| m.sub_id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Messages of all threads matching `predicate`, newest first.
+    pub fn find_messages(&self, predicate: impl Fn(&SmsMessage) -> bool) -> Vec<SmsMessage> {
+        let inner = self.lock();
+        let mut messages: Vec<SmsMessage> = inner
+            .contents
+            .messages
+            .values()
+            .filter(|m| predicate(m))
+            .cloned()
+            .collect();
+        messages.sort_by_key(|m| std::cmp::Reverse((m.date, m.uid)));
+        messages
+    }
+
+    /// Wait until every change made so far is written to the log.
+    pub fn flush(&self) {
+        let Some(writer) = self.lock().writer.clone() else {
+            return;
+        };
+        let (done, wait) = mpsc::channel();
+        if writer.send(WriteJob::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StoreInner> {
+        self.inner.lock().expect("SMS store poisoned")
+    }
+}
+
+impl std::fmt::Debug for SmsStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.lock();
+        f.debug_struct("SmsStore")
+            .field("device_id", &inner.device_id)
+            .field("messages", &inner.contents.messages.len())
+            .field("conversations", &inner.contents.conversations.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl StoreInner {
+    /// Queue changed records for the log, returning how many there were.
+    fn append(&mut self, records: Vec<Record>) -> usize {
+        let count = records.len();
+        if count == 0 {
+            return 0;
+        }
+        let Some(path) = &self.path else {
+            return count;
+        };
+        let writer = self
+            .writer
+            .get_or_insert_with(|| spawn_writer(path.clone()));
+        if writer.send(WriteJob::Append(records)).is_err() {
+            tracing::warn!("SMS store writer for {} is gone", path.display());
+        }
+        count
+    }
+}
+
+/// Start the thread that appends to the log at `path`.
+///
+/// It ends once every sender for the store is dropped.
+fn spawn_writer(path: PathBuf) -> mpsc::Sender<WriteJob> {
+    let (tx, rx) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("sms-store-writer".to_string())
+        .spawn(move || {
+            for job in rx {
+                match job {
+                    WriteJob::Append(records) => {
+                        if let Err(e) = append(&path, &records) {
+                            tracing::warn!("Failed to write SMS store {}: {}", path.display(), e);
+                        }
+                    }
+                    WriteJob::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        tracing::warn!("Failed to start SMS store writer: {}", e);
+    }
+    tx
+}
+
+/// Replace a log with one record per message and conversation.
+///
+/// The log is read again under the lock, so records other applet processes
+/// appended since it was opened are kept. Returns what the log now holds.
+fn compact(path: &Path) -> io::Result<Contents> {
+    let _lock = LogLock::acquire(path)?;
+    let mut contents = read_log(path)?;
+    rewrite(path, |out| {
+        write_header(out)?;
+        let messages = contents.messages.values().cloned().map(Record::Message);
+        let conversations = contents
+            .conversations
+            .values()
+            .cloned()
+            .map(Record::Conversation);
+        for record in messages.chain(conversations) {
+            write_line(out, &record)?;
+        }
+        Ok(())
+    })?;
+    tracing::info!(
+        "Compacted SMS store {} ({} stale records dropped)",
+        path.display(),
+        contents.stale_records
+    );
+    contents.stale_records = 0;
+    Ok(contents)
+}
+
+/// Open the SMS store for a device without blocking the UI.
+pub async fn open_store_async(device_id: String) -> Message {
+    let id = device_id.clone();
+    let store = match tokio::task::spawn_blocking(move || SmsStore::open(&id)).await {
+        Ok(store) => store,
+        Err(e) => {
+            tracing::error!("Failed to load the SMS store for {}: {}", device_id, e);
+            SmsStore::open_at(&device_id, None)
+        }
+    };
+    Message::SmsStoreLoaded(store)
+}
+
+/// Read a log.
+///
+/// A missing log reads as empty. So does one written with another schema
+/// version, which is renamed to `<device>.v<version>.bak` to keep it around.
+fn read_log(path: &Path) -> io::Result<Contents> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Contents::default()),
+        Err(e) => return Err(e),
+    };
+    let mut lines = BufReader::new(file).lines();
+
+    let version = match lines.next() {
+        Some(line) => serde_json::from_str::<Header>(&line?)
+            .map(|h| h.version)
+            .ok(),
+        None => return Ok(Contents::default()),
+    };
+    if version != Some(SCHEMA_VERSION) {
+        let backup = path.with_extension(match version {
+            Some(version) => format!("v{}.bak", version),
+            None => "bak".to_string(),
+        });
+        tracing::warn!(
+            "Moving SMS store {} with schema version {:?} (expected {}) to {}",
+            path.display(),
+            version,
+            SCHEMA_VERSION,
+            backup.display()
+        );
+        std::fs::rename(path, &backup)?;
+        return Ok(Contents::default());
+    }
+
+    let mut contents = Contents::default();
+    for line in lines {
+        let replaced = match serde_json::from_str::<Record>(&line?) {
+            Ok(Record::Message(message)) => {
+                contents.messages.insert(message.key(), message).is_some()
+            }
+            Ok(Record::Conversation(conversation)) => contents
+                .conversations
+                .insert(conversation.thread_id, conversation)
+                .is_some(),
+            // A write cut short by a crash leaves a partial last line
+            Err(e) => {
+                tracing::debug!("Skipping unreadable SMS store record: {}", e);
+                false
+            }
+        };
+        if replaced {
+            contents.stale_records += 1;
+        }
+    }
+    Ok(contents)
+}
+
+/// Append records to a log, creating it with a header if needed.
+fn append(path: &Path, records: &[Record]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let _lock = LogLock::acquire(path)?;
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let is_new = file.metadata()?.len() == 0;
+
+    let mut out = BufWriter::new(file);
+    if is_new {
+        write_header(&mut out)?;
+    }
+    for record in records {
+        write_line(&mut out, record)?;
+    }
+    out.flush()
+}
+
+fn write_header(out: &mut impl Write) -> io::Result<()> {
+    write_line(
+        out,
+        &Header {
+            version: SCHEMA_VERSION,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kdeconnect_dbus::plugins::Attachment;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn temp_log() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "connected-sms-store-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("abc.jsonl")
+    }
+
+    #[test]
+    fn messages_survive_reopening() {
+        let path = temp_log();
+        let store = SmsStore::open_at("abc", Some(path.clone()));
+        let messages = [
+            SmsMessage::test(1, 10, "first", 1_000),
+            SmsMessage::test(1, 11, "second", 2_000),
+        ];
+        assert_eq!(store.insert(&messages), 2);
+        store.flush();
+
+        let store = SmsStore::open_at("abc", Some(path));
+        let bodies: Vec<String> = store.messages(1).into_iter().map(|m| m.body).collect();
+        assert_eq!(bodies, vec!["first", "second"]);
+        let conversations = store.conversations();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].last_message, "second");
+    }
+
+    #[test]
+    fn messages_are_deduplicated_by_thread_and_uid() {
+        let path = temp_log();
+        let store = SmsStore::open_at("abc", Some(path.clone()));
+        let message = SmsMessage::test(1, 10, "hello", 1_000);
+        assert_eq!(store.insert([&message]), 1);
+        assert_eq!(store.insert([&message]), 0);
+
+        let mut read = message.clone();
+        read.read = true;
+        assert_eq!(store.insert([&read]), 1);
+        store.flush();
+
+        let store = SmsStore::open_at("abc", Some(path));
+        let messages = store.messages(1);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].read);
+    }
+
+    #[test]
+    fn sms_and_mms_with_the_same_uid_are_both_kept() {
+        let path = temp_log();
+        let store = SmsStore::open_at("abc", Some(path.clone()));
+        let sms = SmsMessage::test(1, 10, "text", 1_000);
+        let mut mms = sms.clone();
+        mms.body = "group".to_string();
+        mms.addresses.push("+15550002".to_string());
+        mms.date = 2_000;
+        assert_eq!(store.insert([&sms, &mms]), 2);
+        store.flush();
+
+        let store = SmsStore::open_at("abc", Some(path));
+        let bodies: Vec<String> = store.messages(1).into_iter().map(|m| m.body).collect();
+        assert_eq!(bodies, vec!["text", "group"]);
+    }
+
+    #[test]
+    fn newer_messages_update_stored_conversations() {
+        let store = SmsStore::open_at("abc", None);
+        store.insert_conversations([
+            &ConversationSummary {
+                thread_id: 1,
+                addresses: vec!["+15550001".to_string()],
+                last_message: "old".to_string(),
+                timestamp: 1_000,
+                unread: false,
+            },
+            &ConversationSummary {
+                thread_id: 2,
+                addresses: vec!["+15550002".to_string()],
+                last_message: "other".to_string(),
+                timestamp: 2_000,
+                unread: false,
+            },
+        ]);
+        store.insert([&SmsMessage::test(1, 10, "new", 3_000)]);
+
+        let conversations = store.conversations();
+        let threads: Vec<i64> = conversations.iter().map(|c| c.thread_id).collect();
+        assert_eq!(threads, vec![1, 2]);
+        assert_eq!(conversations[0].last_message, "new");
+        assert!(conversations[0].unread);
+    }
+
+    #[test]
+    fn attachments_survive_reopening() {
+        let path = temp_log();
+        let store = SmsStore::open_at("abc", Some(path.clone()));
+        let mut message = SmsMessage::test(1, 10, "", 1_000);
+        message.attachments.push(Attachment {
+            part_id: 4,
+            mime_type: "image/jpeg".to_string(),
+            file_name: "PART_4.jpg".to_string(),
+            unique_identifier: "PART_4.jpg".to_string(),
+            thumbnail: vec![0xff, 0xd8, 0x00],
+        });
+        store.insert([&message]);
+        store.flush();
+
+        let store = SmsStore::open_at("abc", Some(path.clone()));
+        assert_eq!(store.messages(1), vec![message]);
+
+        // Messages written before attachments were stored still load
+        let line = std::fs::read_to_string(&path).unwrap();
+        let old = line.replace(
+            &format!(
+                ",\"attachments\":{}",
+                serde_json::to_string(&store.messages(1)[0].attachments).unwrap()
+            ),
+            "",
+        );
+        assert_ne!(old, line);
+        std::fs::write(&path, old).unwrap();
+        let store = SmsStore::open_at("abc", Some(path));
+        assert!(store.messages(1)[0].attachments.is_empty());
+    }
+
+    #[test]
+    fn other_schema_versions_are_set_aside() {
+        let path = temp_log();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let old = "{\"version\":0}\n{\"not\":\"a message\"}\n";
+        std::fs::write(&path, old).unwrap();
+
+        let store = SmsStore::open_at("abc", Some(path.clone()));
+        assert!(store.conversations().is_empty());
+        let backup = path.with_extension("v0.bak");
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), old);
+        store.insert([&SmsMessage::test(2, 20, "fresh", 1_000)]);
+        store.flush();
+
+        let store = SmsStore::open_at("abc", Some(path));
+        assert_eq!(store.messages(2).len(), 1);
+    }
+
+    #[test]
+    fn stale_records_are_compacted() {
+        let path = temp_log();
+        let store = SmsStore::open_at("abc", Some(path.clone()));
+        let mut message = SmsMessage::test(1, 10, "hello", 1_000);
+        for i in 0..=COMPACT_THRESHOLD {
+            message.body = format!("edit {}", i);
+            store.insert([&message]);
+        }
+        store.flush();
+
+        SmsStore::open_at("abc", Some(path.clone()));
+        let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+        assert_eq!(lines, 2);
+    }
+
+    #[test]
+    fn compaction_keeps_records_written_after_opening() {
+        let path = temp_log();
+        let store = SmsStore::open_at("abc", Some(path.clone()));
+        // Another applet process with the log open
+        let other = SmsStore::open_at("abc", Some(path.clone()));
+        let mut message = SmsMessage::test(1, 10, "hello", 1_000);
+        for i in 0..=COMPACT_THRESHOLD {
+            message.body = format!("edit {}", i);
+            store.insert([&message]);
+        }
+        store.flush();
+
+        other.insert([&SmsMessage::test(2, 20, "late", 2_000)]);
+        other.flush();
+
+        let contents = compact(&path).unwrap();
+        assert_eq!(contents.messages.len(), 2);
+        let store = SmsStore::open_at("abc", Some(path));
+        assert_eq!(store.messages(2)[0].body, "late");
+    }
+}
@@ -1,14 +1,93 @@
 //! SMS view components for conversation list and message threads.
 
+use super::attachments::{format_size, OutgoingAttachment};
+use super::export::ExportFormat;
+use super::search::SearchHit;
+use super::sim::SimCards;
 use crate::app::{LoadingPhase, Message, SmsLoadingState};
+use crate::constants::sms::ATTACHMENT_THUMBNAIL_SIZE;
 use crate::fl;
 use crate::views::helpers::{format_timestamp, WIDE_POPUP_WIDTH};
 use cosmic::iced::widget::{column, row, text};
-use cosmic::iced::{Alignment, Length};
+use cosmic::iced::{Alignment, Length, Rectangle, Task, Vector};
+use cosmic::iced_core::widget::operation::scrollable::{scroll_to, AbsoluteOffset, Scrollable};
+use cosmic::iced_core::widget::operation::{Operation, Outcome};
 use cosmic::widget;
 use cosmic::Element;
 use kdeconnect_dbus::contacts::ContactLookup;
-use kdeconnect_dbus::plugins::{is_address_valid, ConversationSummary, MessageType, SmsMessage};
+use kdeconnect_dbus::plugins::{
+    is_address_valid, Attachment, ConversationSummary, MessageType, SmsMessage,
+};
+use std::collections::HashMap;
+
+// --- Scrolling to a message ---
+
+/// Widget id of a message in the thread.
+fn message_widget_id(uid: i32) -> widget::Id {
+    widget::Id::new(format!("sms-message-{uid}"))
+}
+
+/// Scroll the message thread so the message with `uid` is centred.
+///
+/// Bubbles differ in height, so the offset comes from the laid-out widget
+/// rather than the message's index.
+pub fn scroll_to_message<T: Send + 'static>(uid: i32) -> Task<T> {
+    cosmic::iced_runtime::task::widget(FindMessage {
+        target: message_widget_id(uid),
+        thread: None,
+        message: None,
+    })
+}
+
+/// Finds the bounds of the thread and of one message in it, then scrolls.
+struct FindMessage {
+    target: widget::Id,
+    /// Viewport and content bounds of the thread
+    thread: Option<(Rectangle, Rectangle)>,
+    message: Option<Rectangle>,
+}
+
+impl<T: Send + 'static> Operation<T> for FindMessage {
+    fn container(
+        &mut self,
+        id: Option<&widget::Id>,
+        bounds: Rectangle,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation<T>),
+    ) {
+        if id == Some(&self.target) {
+            self.message = Some(bounds);
+        } else {
+            operate_on_children(self);
+        }
+    }
+
+    fn scrollable(
+        &mut self,
+        _state: &mut dyn Scrollable,
+        id: Option<&widget::Id>,
+        bounds: Rectangle,
+        content_bounds: Rectangle,
+        _translation: Vector,
+    ) {
+        if id == Some(&widget::Id::new("message-thread")) {
+            self.thread = Some((bounds, content_bounds));
+        }
+    }
+
+    fn finish(&self) -> Outcome<T> {
+        let (Some((viewport, content)), Some(message)) = (self.thread, self.message) else {
+            return Outcome::None;
+        };
+        let y = message.y - content.y - (viewport.height - message.height) / 2.0;
+        Outcome::Chain(Box::new(scroll_to(
+            widget::Id::new("message-thread"),
+            AbsoluteOffset {
+                x: 0.0,
+                y: y.max(0.0),
+            },
+        )))
+    }
+}
 
 // --- Helper functions for loading state ---
 
@@ -60,6 +139,13 @@ pub struct ConversationListParams<'a> {
     pub loading_state: &'a SmsLoadingState,
     /// Whether background sync is active (syncing conversations from phone)
     pub sync_active: bool,
+    /// Current search text (results replace the list when non-empty)
+    pub search_query: &'a str,
+    pub search_results: &'a [SearchHit],
+    /// Whether the export format choices are shown
+    pub export_menu_open: bool,
+    /// Progress or outcome of the last export
+    pub export_status: Option<&'a str>,
 }
 
 /// Render the SMS conversation list view.
@@ -90,15 +176,27 @@ pub fn view_conversation_list(params: ConversationListParams<'_>) -> Element<'_,
 
     let header = header_row
         .push(widget::horizontal_space())
+        .push(
+            widget::button::icon(widget::icon::from_name("document-save-symbolic"))
+                .on_press(Message::ToggleSmsExportMenu),
+        )
         .push(
             widget::button::icon(widget::icon::from_name("list-add-symbolic"))
                 .on_press(Message::OpenNewMessage),
         )
         .padding([8, 12]);
 
-    let content: Element<Message> = if is_loading_conversations(params.loading_state)
-        && params.conversations.is_empty()
-    {
+    let search_box = widget::container(
+        widget::search_input(fl!("search-messages"), params.search_query)
+            .on_input(Message::SmsSearchInput)
+            .on_clear(Message::SmsSearchInput(String::new()))
+            .width(Length::Fill),
+    )
+    .padding([0, 12]);
+
+    let content: Element<Message> = if !params.search_query.trim().is_empty() {
+        view_search_results(params.search_results, params.contacts)
+    } else if is_loading_conversations(params.loading_state) && params.conversations.is_empty() {
         widget::container(
             column![text(conversation_loading_text(params.loading_state)).size(14),]
                 .align_x(Alignment::Center),
@@ -197,8 +295,97 @@ pub fn view_conversation_list(params: ConversationListParams<'_>) -> Element<'_,
             .into()
     };
 
-    column![header, widget::divider::horizontal::default(), content,]
-        .spacing(8)
+    let mut layout = column![header].spacing(8).width(Length::Fill);
+    if let Some(bar) = export_bar(
+        fl!("export-all"),
+        params.export_menu_open,
+        params.export_status,
+    ) {
+        layout = layout.push(bar);
+    }
+    layout
+        .push(search_box)
+        .push(widget::divider::horizontal::default())
+        .push(content)
+        .into()
+}
+
+/// Export format choices and the progress of the last export, if either is shown.
+fn export_bar<'a>(
+    label: String,
+    menu_open: bool,
+    status: Option<&'a str>,
+) -> Option<Element<'a, Message>> {
+    if !menu_open && status.is_none() {
+        return None;
+    }
+
+    let mut bar = column![].spacing(4).padding([0, 12]);
+    if menu_open {
+        let mut choices = row![text(label).size(12), widget::horizontal_space()]
+            .spacing(8)
+            .align_y(Alignment::Center);
+        for format in ExportFormat::ALL {
+            choices = choices
+                .push(widget::button::text(format.label()).on_press(Message::ExportSms(format)));
+        }
+        bar = bar.push(choices);
+    }
+    if let Some(status) = status {
+        bar = bar.push(text(status).size(11).wrapping(text::Wrapping::Word));
+    }
+    Some(bar.into())
+}
+
+/// Render SMS search results; pressing one opens its thread at the match.
+fn view_search_results<'a>(
+    results: &'a [SearchHit],
+    contacts: &'a ContactLookup,
+) -> Element<'a, Message> {
+    if results.is_empty() {
+        return widget::container(text(fl!("no-search-results")).size(14))
+            .center(Length::Fill)
+            .into();
+    }
+
+    let mut results_column = column![].spacing(4);
+    for hit in results {
+        let display_name = hit
+            .addresses
+            .iter()
+            .map(|address| contacts.get_name_or_number(address))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let hit_row = widget::button::custom(
+            widget::container(
+                row![
+                    column![
+                        text(display_name).size(14),
+                        text(hit.snippet.as_str()).size(11),
+                    ]
+                    .spacing(2),
+                    widget::horizontal_space(),
+                    text(format_timestamp(hit.date)).size(10),
+                    widget::icon::from_name("go-next-symbolic").size(16),
+                ]
+                .spacing(8)
+                .align_y(Alignment::Center),
+            )
+            .padding(8)
+            .width(Length::Fill),
+        )
+        .class(cosmic::theme::Button::Text)
+        .on_press(Message::OpenSearchResult {
+            thread_id: hit.thread_id,
+            uid: hit.uid,
+        })
+        .width(Length::Fill);
+
+        results_column = results_column.push(hit_row);
+    }
+
+    widget::scrollable(results_column.padding([0, 8]))
         .width(Length::Fill)
         .into()
 }
@@ -217,6 +404,54 @@ pub struct MessageThreadParams<'a> {
     pub pressed_bubble_uid: Option<i32>,
     /// Whether to show the "Hold to copy" hint (500ms elapsed)
     pub show_copy_hint: bool,
+    /// UID of the message a search result jumped to
+    pub highlighted_uid: Option<i32>,
+    /// Whether the export format choices are shown
+    pub export_menu_open: bool,
+    /// Progress or outcome of the last export
+    pub export_status: Option<&'a str>,
+    /// Image handles of MMS thumbnails by attachment identifier
+    pub thumbnails: &'a HashMap<String, widget::image::Handle>,
+    /// Progress or error of the last attachment download or attach
+    pub attachment_status: Option<&'a str>,
+    /// Files attached to the reply being composed
+    pub outgoing_attachments: &'a [OutgoingAttachment],
+    /// SIMs of the phone, for the SIM picker
+    pub sims: &'a SimCards,
+    /// SIM replies are sent with
+    pub sub_id: Option<i64>,
+}
+
+/// SIM picker, shown when the phone has more than one SIM.
+fn sim_picker(
+    sims: &SimCards,
+    sub_id: Option<i64>,
+    on_select: fn(usize) -> Message,
+) -> Option<Element<'_, Message>> {
+    sims.has_choice().then(|| {
+        let selected = sub_id.and_then(|sub_id| sims.position(sub_id));
+        widget::dropdown(sims.labels(), selected, on_select).into()
+    })
+}
+
+/// Timestamp of a message bubble, with the state of outgoing messages that
+/// have not been sent.
+fn view_delivery_status(msg: &SmsMessage, time_str: String) -> Element<'_, Message> {
+    let (icon_name, label) = match msg.message_type {
+        MessageType::Inbox | MessageType::Sent => return text(time_str).size(9).into(),
+        MessageType::Outbox | MessageType::Queued => {
+            ("content-loading-symbolic", fl!("message-pending"))
+        }
+        MessageType::Failed => ("dialog-error-symbolic", fl!("message-failed")),
+        MessageType::Draft => ("document-edit-symbolic", fl!("message-draft")),
+    };
+    row![
+        widget::icon::from_name(icon_name).size(10),
+        text(format!("{} · {}", time_str, label)).size(9),
+    ]
+    .spacing(4)
+    .align_y(Alignment::Center)
+    .into()
 }
 
 /// Render the SMS message thread view.
@@ -250,8 +485,15 @@ pub fn view_message_thread(params: MessageThreadParams<'_>) -> Element<'_, Messa
         );
     }
 
+    let mut header_row = header_row.push(widget::horizontal_space());
+    if let Some(picker) = sim_picker(params.sims, params.sub_id, Message::SelectThreadSim) {
+        header_row = header_row.push(picker);
+    }
     let header = header_row
-        .push(widget::horizontal_space())
+        .push(
+            widget::button::icon(widget::icon::from_name("document-save-symbolic"))
+                .on_press(Message::ToggleSmsExportMenu),
+        )
         .padding([8, 12]);
 
     // Show loading indicator only when loading AND no messages yet
@@ -297,19 +539,28 @@ pub fn view_message_thread(params: MessageThreadParams<'_>) -> Element<'_, Messa
         }
 
         for msg in params.messages {
-            // MessageType::Inbox (1) = incoming/received, MessageType::Sent (2) = outgoing/sent
-            let is_received = msg.message_type == MessageType::Inbox;
+            // Only MessageType::Inbox is incoming; every other type was written by the user
+            let is_received = !msg.message_type.is_outgoing();
             let time_str = format_timestamp(msg.date);
             let is_pressed = params.pressed_bubble_uid == Some(msg.uid);
+            let is_highlighted = params.highlighted_uid == Some(msg.uid);
             let show_hint = is_pressed && params.show_copy_hint;
 
             // Message bubble content (long-press to copy)
-            let bubble_content =
-                column![text(&msg.body).size(13).wrapping(text::Wrapping::Word), text(time_str).size(9),]
-                    .spacing(4);
-
-            // Use highlighted style when pressed for high contrast visual feedback
-            let bubble: Element<Message> = if is_pressed {
+            let mut bubble_content = column![].spacing(4);
+            for attachment in &msg.attachments {
+                bubble_content =
+                    bubble_content.push(view_attachment(attachment, params.thumbnails));
+            }
+            if !msg.body.is_empty() || msg.attachments.is_empty() {
+                bubble_content =
+                    bubble_content.push(text(&msg.body).size(13).wrapping(text::Wrapping::Word));
+            }
+            let bubble_content = bubble_content.push(view_delivery_status(msg, time_str));
+
+            // Use highlighted style when pressed for high contrast visual feedback,
+            // and for the message a search result jumped to
+            let bubble: Element<Message> = if is_pressed || is_highlighted {
                 // Wrap in two containers for a "selected" border effect
                 let inner = widget::container(bubble_content)
                     .padding([8, 12])
@@ -340,7 +591,7 @@ pub fn view_message_thread(params: MessageThreadParams<'_>) -> Element<'_, Messa
                 .on_release(Message::BubblePressReleased);
 
             // Bubble with optional "Hold to copy" hint (only after 500ms)
-            let bubble_element: Element<Message> = if show_hint {
+            let mut bubble_element: Element<Message> = if show_hint {
                 column![
                     bubble_with_press,
                     text(fl!("hold-to-copy")).size(10),
@@ -351,6 +602,16 @@ pub fn view_message_thread(params: MessageThreadParams<'_>) -> Element<'_, Messa
                 bubble_with_press.into()
             };
 
+            // Failed messages can be resent, attachments included
+            if msg.message_type == MessageType::Failed {
+                let retry_button = widget::button::text(fl!("retry"))
+                    .on_press_maybe((!params.sms_sending).then_some(Message::RetrySms(msg.uid)));
+                bubble_element = column![bubble_element, retry_button]
+                    .spacing(2)
+                    .align_x(Alignment::End)
+                    .into();
+            }
+
             // Received messages: show sender name above and align left
             // Sent messages: align right
             let msg_row: Element<Message> = if is_received {
@@ -368,7 +629,7 @@ pub fn view_message_thread(params: MessageThreadParams<'_>) -> Element<'_, Messa
                     .into()
             };
 
-            msg_column = msg_column.push(msg_row);
+            msg_column = msg_column.push(widget::container(msg_row).id(message_widget_id(msg.uid)));
         }
 
         widget::scrollable(msg_column)
@@ -382,7 +643,8 @@ pub fn view_message_thread(params: MessageThreadParams<'_>) -> Element<'_, Messa
     // Compose row
     let compose_input = widget::text_input(fl!("type-message"), params.sms_compose_text)
         .on_input(Message::SmsComposeInput)
-        .width(Length::Fill);
+        .width(Length::Fill)
+        .id(widget::Id::new("sms-compose"));
 
     // Check if this is a group conversation (can't send to groups)
     let unique_addresses: std::collections::HashSet<&str> = params
@@ -406,7 +668,9 @@ pub fn view_message_thread(params: MessageThreadParams<'_>) -> Element<'_, Messa
             .leading_icon(widget::icon::from_name("process-working-symbolic").size(16))
             .into()
     } else {
-        let can_send = !params.sms_compose_text.is_empty() && !params.sms_sending;
+        let can_send = (!params.sms_compose_text.is_empty()
+            || !params.outgoing_attachments.is_empty())
+            && !params.sms_sending;
         widget::button::suggested(fl!("send"))
             .leading_icon(widget::icon::from_name("mail-send-symbolic").size(16))
             .on_press_maybe(if can_send {
@@ -421,26 +685,132 @@ pub fn view_message_thread(params: MessageThreadParams<'_>) -> Element<'_, Messa
         widget::container(column![row![compose_input].width(Length::Fill), send_btn,].spacing(8))
             .padding([8, 12])
     } else {
+        let mut compose = column![].spacing(8);
+        if let Some(chips) = attachment_chips(params.outgoing_attachments) {
+            compose = compose.push(chips);
+        }
         widget::container(
-            row![compose_input, send_btn,]
-                .spacing(8)
-                .align_y(Alignment::Center),
+            compose.push(
+                row![attach_buttons(), compose_input, send_btn,]
+                    .spacing(8)
+                    .align_y(Alignment::Center),
+            ),
         )
         .padding([8, 12])
     };
 
-    column![
-        header,
-        widget::divider::horizontal::default(),
-        content,
-        widget::divider::horizontal::default(),
-        compose_row,
+    let mut layout = column![header].spacing(4).width(Length::Fill);
+    if let Some(bar) = export_bar(
+        fl!("export-conversation"),
+        params.export_menu_open,
+        params.export_status,
+    ) {
+        layout = layout.push(bar);
+    }
+    if let Some(status) = params.attachment_status {
+        layout = layout.push(
+            widget::container(text(status).size(12).wrapping(text::Wrapping::Word))
+                .padding([0, 12]),
+        );
+    }
+    layout
+        .push(widget::divider::horizontal::default())
+        .push(content)
+        .push(widget::divider::horizontal::default())
+        .push(compose_row)
+        .into()
+}
+
+/// An MMS attachment in a message bubble; clicking it opens the full file.
+///
+/// Images are shown as their thumbnail, other files as a button with the file name.
+fn view_attachment<'a>(
+    attachment: &'a Attachment,
+    thumbnails: &'a HashMap<String, widget::image::Handle>,
+) -> Element<'a, Message> {
+    let on_press = Message::OpenAttachment {
+        part_id: attachment.part_id,
+        unique_identifier: attachment.unique_identifier.clone(),
+    };
+    match thumbnails.get(&attachment.unique_identifier) {
+        Some(handle) => widget::button::custom(
+            widget::image(handle.clone()).width(Length::Fixed(ATTACHMENT_THUMBNAIL_SIZE)),
+        )
+        .padding(0)
+        .class(cosmic::theme::Button::Image)
+        .on_press(on_press)
+        .into(),
+        None => widget::button::standard(attachment.file_name.as_str())
+            .leading_icon(widget::icon::from_name("mail-attachment-symbolic").size(16))
+            .on_press(on_press)
+            .into(),
+    }
+}
+
+/// Buttons to attach files or paste an image from the clipboard.
+fn attach_buttons<'a>() -> Element<'a, Message> {
+    row![
+        widget::tooltip(
+            widget::button::icon(widget::icon::from_name("mail-attachment-symbolic"))
+                .on_press(Message::PickAttachments),
+            text(fl!("attach-file")).size(12),
+            widget::tooltip::Position::Top,
+        ),
+        widget::tooltip(
+            widget::button::icon(widget::icon::from_name("edit-paste-symbolic"))
+                .on_press(Message::PasteAttachment),
+            text(fl!("paste-image")).size(12),
+            widget::tooltip::Position::Top,
+        ),
     ]
     .spacing(4)
-    .width(Length::Fill)
+    .align_y(Alignment::Center)
     .into()
 }
 
+/// One removable chip per file attached to the message being composed,
+/// with a small preview for images.
+fn attachment_chips(attachments: &[OutgoingAttachment]) -> Option<Element<'_, Message>> {
+    if attachments.is_empty() {
+        return None;
+    }
+    let mut chips = column![].spacing(4);
+    for (index, attachment) in attachments.iter().enumerate() {
+        let preview: Element<Message> = if attachment.is_image() {
+            // Path handles are identified by the path, so the preview is decoded once
+            widget::image(widget::image::Handle::from_path(&attachment.path))
+                .width(Length::Fixed(32.0))
+                .height(Length::Fixed(32.0))
+                .into()
+        } else {
+            widget::icon::from_name("text-x-generic-symbolic")
+                .size(16)
+                .into()
+        };
+        chips = chips.push(
+            widget::container(
+                row![
+                    preview,
+                    text(format!(
+                        "{} ({})",
+                        attachment.file_name,
+                        format_size(attachment.size)
+                    ))
+                    .size(12)
+                    .width(Length::Fill),
+                    widget::button::icon(widget::icon::from_name("window-close-symbolic"))
+                        .on_press(Message::RemoveAttachment(index)),
+                ]
+                .spacing(8)
+                .align_y(Alignment::Center),
+            )
+            .padding([2, 8])
+            .class(cosmic::theme::Container::Card),
+        );
+    }
+    Some(chips.into())
+}
+
 /// Parameters for the new message view.
 pub struct NewMessageParams<'a> {
     pub recipient: &'a str,
@@ -449,6 +819,14 @@ pub struct NewMessageParams<'a> {
     pub sending: bool,
     /// Contact suggestions as (contact_name, phone_number) tuples
     pub contact_suggestions: &'a [(String, String)],
+    /// Files attached to the message
+    pub attachments: &'a [OutgoingAttachment],
+    /// Error from the last attach
+    pub attachment_status: Option<&'a str>,
+    /// SIMs of the phone, for the SIM picker
+    pub sims: &'a SimCards,
+    /// SIM the message will be sent with (None for the phone's default)
+    pub sub_id: Option<i64>,
 }
 
 /// Render the new message compose view.
@@ -528,7 +906,9 @@ pub fn view_new_message(params: NewMessageParams<'_>) -> Element<'_, Message> {
         .width(Length::Fill);
 
     // Send button
-    let send_enabled = params.recipient_valid && !params.body.is_empty() && !params.sending;
+    let send_enabled = params.recipient_valid
+        && (!params.body.is_empty() || !params.attachments.is_empty())
+        && !params.sending;
 
     let send_btn = if params.sending {
         widget::button::standard(fl!("sending"))
@@ -543,22 +923,39 @@ pub fn view_new_message(params: NewMessageParams<'_>) -> Element<'_, Message> {
     };
 
     let send_row = widget::container(
-        row![widget::horizontal_space(), send_btn,]
+        row![attach_buttons(), widget::horizontal_space(), send_btn,]
             .spacing(8)
             .align_y(Alignment::Center),
     )
     .padding([8, 12]);
 
-    column![
+    let mut layout = column![
         header,
         widget::divider::horizontal::default(),
         recipient_row,
         suggestions_section,
-        widget::container(message_input).padding([8, 12]),
-        send_row,
-        widget::vertical_space(),
     ]
     .spacing(4)
-    .width(Length::Fill)
-    .into()
+    .width(Length::Fill);
+    if let Some(picker) = sim_picker(params.sims, params.sub_id, Message::SelectNewMessageSim) {
+        layout = layout.push(
+            widget::container(
+                row![text(fl!("sim")).size(14), picker]
+                    .spacing(8)
+                    .align_y(Alignment::Center),
+            )
+            .padding([0, 12]),
+        );
+    }
+    layout = layout.push(widget::container(message_input).padding([8, 12]));
+    if let Some(chips) = attachment_chips(params.attachments) {
+        layout = layout.push(widget::container(chips).padding([0, 12]));
+    }
+    if let Some(status) = params.attachment_status {
+        layout = layout.push(
+            widget::container(text(status).size(12).wrapping(text::Wrapping::Word))
+                .padding([0, 12]),
+        );
+    }
+    layout.push(send_row).push(widget::vertical_space()).into()
 }
@@ -2,6 +2,7 @@
 
 use crate::app::{DeviceInfo, Message};
 use crate::config::Config;
+use crate::device::filter::NotificationFilters;
 use crate::fl;
 use cosmic::iced::advanced::widget::text::Style as TextStyle;
 use cosmic::iced::widget::{column, row, text};
@@ -13,6 +14,7 @@ use cosmic::{theme, Element};
 pub fn view<'a>(
     devices: &'a [DeviceInfo],
     config: &'a Config,
+    filters: &'a NotificationFilters,
     status_message: Option<&'a str>,
 ) -> Element<'a, Message> {
     // Header with refresh and settings buttons
@@ -46,7 +48,7 @@ pub fn view<'a>(
 
     let device_rows: Vec<Element<Message>> = filtered_devices
         .iter()
-        .map(|device| device_row(device, config))
+        .map(|device| device_row(device, config, filters))
         .collect();
 
     let mut content = column![header, widget::divider::horizontal::default(),].spacing(4);
@@ -75,7 +77,11 @@ pub fn view<'a>(
 }
 
 /// Render a single device row.
-fn device_row<'a>(device: &'a DeviceInfo, config: &'a Config) -> Element<'a, Message> {
+fn device_row<'a>(
+    device: &'a DeviceInfo,
+    config: &'a Config,
+    filters: &NotificationFilters,
+) -> Element<'a, Message> {
     let icon_name = match device.device_type.as_str() {
         "phone" | "smartphone" => "phone-symbolic",
         "tablet" => "tablet-symbolic",
@@ -136,9 +142,14 @@ fn device_row<'a>(device: &'a DeviceInfo, config: &'a Config) -> Element<'a, Mes
     }
 
     // Add notification count badge if there are notifications and notifications are enabled
-    if config.forward_notifications && !device.notifications.is_empty() {
+    let notification_count = device
+        .notifications
+        .iter()
+        .filter(|n| filters.allows(&device.id, n))
+        .count();
+    if config.forward_notifications && notification_count > 0 {
         row_content = row_content.push(
-            widget::container(text(format!("{}", device.notifications.len())).size(11))
+            widget::container(text(format!("{}", notification_count)).size(11))
                 .padding([2, 6])
                 .class(cosmic::theme::Container::Card),
         );
@@ -2,7 +2,9 @@
 //!
 //! Shows detailed information and actions for a specific device.
 
-use crate::app::{DeviceInfo, Message};
+use crate::app::{DeviceInfo, Message, NotificationReply};
+use crate::device::filter::NotificationFilters;
+use crate::device::icons::fallback_icon;
 use crate::fl;
 use cosmic::iced::widget::{column, row, text, tooltip};
 use cosmic::iced::{Alignment, Length};
@@ -11,7 +13,12 @@ use cosmic::Element;
 use kdeconnect_dbus::plugins::NotificationInfo;
 
 /// Render the device detail page.
-pub fn view<'a>(device: &'a DeviceInfo, status_message: Option<&'a str>) -> Element<'a, Message> {
+pub fn view<'a>(
+    device: &'a DeviceInfo,
+    status_message: Option<&'a str>,
+    notification_reply: Option<&'a NotificationReply>,
+    filters: &NotificationFilters,
+) -> Element<'a, Message> {
     // Back button
     let back_btn = widget::button::text(fl!("back"))
         .leading_icon(icon::from_name("go-previous-symbolic").size(16))
@@ -63,30 +70,48 @@ pub fn view<'a>(device: &'a DeviceInfo, status_message: Option<&'a str>) -> Elem
     // Build the combined status row with connected, paired, and battery
     let status_row = build_status_row(device);
 
-    // Actions section - only available for connected and paired devices
+    // SMS Messages action item - stored messages stay readable while disconnected
+    let sms_row = row![
+        icon::from_name("mail-message-new-symbolic").size(24),
+        text(fl!("sms-messages")).size(14),
+        widget::horizontal_space(),
+        icon::from_name("go-next-symbolic").size(16),
+    ]
+    .spacing(12)
+    .align_y(Alignment::Center);
+
+    let sms_item =
+        widget::button::custom(widget::container(sms_row).padding(8).width(Length::Fill))
+            .class(cosmic::theme::Button::Text)
+            .on_press(Message::OpenSmsView(device.id.clone()))
+            .width(Length::Fill);
+
+    // Notification history action item - recorded notifications stay readable too
+    let history_row = row![
+        icon::from_name("document-open-recent-symbolic").size(24),
+        text(fl!("notification-history")).size(14),
+        widget::horizontal_space(),
+        icon::from_name("go-next-symbolic").size(16),
+    ]
+    .spacing(12)
+    .align_y(Alignment::Center);
+
+    let history_item = widget::button::custom(
+        widget::container(history_row)
+            .padding(8)
+            .width(Length::Fill),
+    )
+    .class(cosmic::theme::Button::Text)
+    .on_press(Message::OpenNotificationHistory(device.id.clone()))
+    .width(Length::Fill);
+
+    // Other actions are only available for connected and paired devices
     let actions: Element<Message> = if device.is_reachable && device.is_paired {
-        let device_id_for_sms = device.id.clone();
         let device_id_for_sendto = device.id.clone();
         let device_type_for_sendto = device.device_type.clone();
         let device_id_for_media = device.id.clone();
         let device_id_for_find = device.id.clone();
 
-        // SMS Messages action item
-        let sms_row = row![
-            icon::from_name("mail-message-new-symbolic").size(24),
-            text(fl!("sms-messages")).size(14),
-            widget::horizontal_space(),
-            icon::from_name("go-next-symbolic").size(16),
-        ]
-        .spacing(12)
-        .align_y(Alignment::Center);
-
-        let sms_item =
-            widget::button::custom(widget::container(sms_row).padding(8).width(Length::Fill))
-                .class(cosmic::theme::Button::Text)
-                .on_press(Message::OpenSmsView(device_id_for_sms))
-                .width(Length::Fill);
-
         // Send to device action item
         let sendto_row = row![
             icon::from_name("document-send-symbolic").size(24),
@@ -137,21 +162,28 @@ pub fn view<'a>(device: &'a DeviceInfo, status_message: Option<&'a str>) -> Elem
                 .on_press(Message::FindMyPhone(device_id_for_find))
                 .width(Length::Fill);
 
-        column![sms_item, sendto_item, media_item, find_item,]
+        column![sms_item, history_item, sendto_item, media_item, find_item,]
             .spacing(4)
             .into()
     } else if !device.is_paired {
         // Not paired - show nothing (pairing section will be shown below)
         widget::Space::new(Length::Shrink, Length::Shrink).into()
     } else {
-        text(fl!("device-must-be-connected")).size(12).into()
+        column![
+            sms_item,
+            history_item,
+            text(fl!("device-must-be-connected")).size(12)
+        ]
+        .spacing(4)
+        .into()
     };
 
     // Pairing section
     let pairing_section: Element<Message> = build_pairing_section(device);
 
     // Notifications section
-    let notifications_section: Element<Message> = build_notifications_section(device);
+    let notifications_section: Element<Message> =
+        build_notifications_section(device, notification_reply, filters);
 
     // Build status message element if present
     let status_bar: Element<Message> = if let Some(msg) = status_message {
@@ -345,22 +377,38 @@ fn build_pairing_section<'a>(device: &'a DeviceInfo) -> Element<'a, Message> {
 }
 
 /// Build the notifications section.
-fn build_notifications_section<'a>(device: &'a DeviceInfo) -> Element<'a, Message> {
-    if device.notifications.is_empty() {
+fn build_notifications_section<'a>(
+    device: &'a DeviceInfo,
+    notification_reply: Option<&'a NotificationReply>,
+    filters: &NotificationFilters,
+) -> Element<'a, Message> {
+    // Notifications hidden by a rule are left out
+    let notifications: Vec<&NotificationInfo> = device
+        .notifications
+        .iter()
+        .filter(|n| filters.allows(&device.id, n))
+        .collect();
+    if notifications.is_empty() {
         return widget::Space::new(Length::Shrink, Length::Shrink).into();
     }
 
     let mut notif_column = column![text(format!(
         "{} ({})",
         fl!("notifications"),
-        device.notifications.len()
+        notifications.len()
     ))
     .size(14),]
     .spacing(8);
 
-    for notif in &device.notifications {
+    for notif in notifications {
         let notif_widget = build_notification_row(device, notif);
         notif_column = notif_column.push(notif_widget);
+        // Reply field below the notification it answers
+        if let Some(reply) =
+            notification_reply.filter(|r| r.device_id == device.id && r.notification_id == notif.id)
+        {
+            notif_column = notif_column.push(build_reply_row(reply));
+        }
     }
 
     notif_column
@@ -381,13 +429,52 @@ fn build_notification_row<'a>(
 
     let notif_content = column![text(notif_title).size(13), text(&notif.text).size(11),].spacing(2);
 
-    let mut notif_row = row![
-        icon::from_name("notification-symbolic").size(20),
-        notif_content,
-        widget::horizontal_space(),
-    ]
-    .spacing(8)
-    .align_y(Alignment::Center);
+    let notif_icon: Element<Message> = match &notif.icon_path {
+        // Path handles are identified by the path, so the icon is decoded once
+        Some(path) => widget::image(widget::image::Handle::from_path(path))
+            .width(Length::Fixed(20.0))
+            .height(Length::Fixed(20.0))
+            .into(),
+        None => icon::from_name(fallback_icon(&notif.app_name))
+            .size(20)
+            .into(),
+    };
+
+    let mut notif_row = row![notif_icon, notif_content, widget::horizontal_space(),]
+        .spacing(8)
+        .align_y(Alignment::Center);
+
+    // Add reply button if the app on the phone accepts replies
+    if notif.repliable {
+        let reply_btn =
+            widget::button::icon(icon::from_name("mail-reply-sender-symbolic")).on_press(
+                Message::ToggleNotificationReply(device.id.clone(), notif.id.clone()),
+            );
+        notif_row = notif_row.push(
+            tooltip(
+                reply_btn,
+                text(fl!("reply")).size(11),
+                tooltip::Position::Bottom,
+            )
+            .gap(4)
+            .padding(8),
+        );
+    }
+
+    // Hide this app's notifications from now on
+    let mute_btn =
+        widget::button::icon(icon::from_name("notifications-disabled-symbolic")).on_press(
+            Message::MuteNotificationApp(device.id.clone(), notif.app_name.clone()),
+        );
+    notif_row = notif_row.push(
+        tooltip(
+            mute_btn,
+            text(fl!("mute-app")).size(11),
+            tooltip::Position::Bottom,
+        )
+        .gap(4)
+        .padding(8),
+    );
 
     // Add dismiss button if notification is dismissable
     if notif.dismissable {
@@ -404,3 +491,30 @@ fn build_notification_row<'a>(
         .width(Length::Fill)
         .into()
 }
+
+/// Build the inline reply field for a notification.
+fn build_reply_row(reply: &NotificationReply) -> Element<'_, Message> {
+    let reply_input = widget::text_input(fl!("reply-placeholder"), &reply.text)
+        .on_input(Message::NotificationReplyInput)
+        .width(Length::Fill)
+        .id(widget::Id::new("notification-reply"));
+
+    let send_btn = if reply.sending {
+        widget::button::standard(fl!("sending"))
+    } else {
+        widget::button::suggested(fl!("send"))
+            .leading_icon(icon::from_name("mail-send-symbolic").size(16))
+            .on_press_maybe(
+                (!reply.text.trim().is_empty()).then_some(Message::SendNotificationReply),
+            )
+    };
+
+    widget::container(
+        row![reply_input, send_btn]
+            .spacing(8)
+            .align_y(Alignment::Center),
+    )
+    .padding([4, 8])
+    .width(Length::Fill)
+    .into()
+}
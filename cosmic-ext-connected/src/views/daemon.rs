@@ -0,0 +1,42 @@
+//! Daemon status view shown while KDE Connect isn't running.
+
+use crate::app::{DaemonState, Message};
+use crate::fl;
+use cosmic::iced::widget::{column, text};
+use cosmic::iced::Alignment;
+use cosmic::widget;
+use cosmic::Element;
+
+/// View for a daemon that is missing, stopped or crashed.
+///
+/// Offers to start the daemon unless it isn't installed.
+pub fn view_daemon_status<'a>(
+    state: DaemonState,
+    starting: bool,
+    status_message: Option<&'a str>,
+) -> Element<'a, Message> {
+    let title = match state {
+        DaemonState::NotInstalled => fl!("daemon-not-installed"),
+        DaemonState::Crashed => fl!("daemon-crashed"),
+        DaemonState::NotRunning | DaemonState::Running => fl!("daemon-not-running"),
+    };
+
+    let mut content = column![text(title).size(16)]
+        .spacing(8)
+        .align_x(Alignment::Center);
+
+    if state == DaemonState::NotInstalled {
+        content = content.push(text(fl!("daemon-not-installed-hint")).size(12));
+    } else if starting {
+        content = content.push(text(fl!("daemon-starting")).size(12));
+    } else {
+        content = content
+            .push(widget::button::suggested(fl!("daemon-start")).on_press(Message::StartDaemon));
+    }
+
+    if let Some(msg) = status_message {
+        content = content.push(text(msg).size(12));
+    }
+
+    widget::container(content).padding(16).into()
+}
@@ -1,5 +1,7 @@
 //! View components and helper functions for the Connected applet.
 
+pub mod daemon;
 pub mod helpers;
+pub mod notification_history;
 pub mod send_to;
 pub mod settings;
@@ -0,0 +1,126 @@
+//! Notification history view of a device.
+
+use crate::app::Message;
+use crate::device::history::{search, HistoryEntry};
+use crate::device::icons::fallback_icon;
+use crate::fl;
+use crate::views::helpers::format_timestamp;
+use cosmic::iced::widget::{column, row, text, tooltip};
+use cosmic::iced::{Alignment, Length};
+use cosmic::widget::{self, icon};
+use cosmic::Element;
+
+/// View parameters for the notification history.
+pub struct NotificationHistoryParams<'a> {
+    /// Name of the device, if known.
+    pub device_name: Option<&'a str>,
+    /// Recorded notifications, oldest first.
+    pub entries: &'a [HistoryEntry],
+    /// Current search text.
+    pub search_query: &'a str,
+    /// Days notifications are kept.
+    pub retention_days: u32,
+    /// Status message to display, if any.
+    pub status_message: Option<&'a str>,
+}
+
+/// View listing the notifications recorded for a device, newest first.
+pub fn view_notification_history(params: NotificationHistoryParams<'_>) -> Element<'_, Message> {
+    let default_device = fl!("device");
+    let device_name = params.device_name.unwrap_or(&default_device);
+
+    let clear_btn = widget::button::icon(icon::from_name("edit-clear-all-symbolic"))
+        .on_press_maybe((!params.entries.is_empty()).then_some(Message::ClearNotificationHistory));
+    let header = row![
+        widget::button::icon(icon::from_name("go-previous-symbolic"))
+            .on_press(Message::CloseNotificationHistory),
+        text(format!("{} - {}", fl!("notification-history"), device_name)).size(16),
+        widget::horizontal_space(),
+        tooltip(
+            clear_btn,
+            text(fl!("clear-history")).size(11),
+            tooltip::Position::Bottom,
+        )
+        .gap(4)
+        .padding(8),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center)
+    .padding([8, 12]);
+
+    let search_box = widget::container(
+        widget::search_input(fl!("search-notifications"), params.search_query)
+            .on_input(Message::NotificationHistorySearch)
+            .on_clear(Message::NotificationHistorySearch(String::new()))
+            .width(Length::Fill),
+    )
+    .padding([0, 12]);
+
+    let entries = search(params.entries, params.search_query);
+    let content: Element<Message> = if entries.is_empty() {
+        let message = if params.entries.is_empty() {
+            fl!("no-notification-history", days = params.retention_days)
+        } else {
+            fl!("no-matching-notifications")
+        };
+        widget::container(
+            column![
+                icon::from_name("notification-symbolic").size(48),
+                text(message).size(14),
+            ]
+            .spacing(12)
+            .align_x(Alignment::Center),
+        )
+        .width(Length::Fill)
+        .align_x(Alignment::Center)
+        .padding(24)
+        .into()
+    } else {
+        let mut entries_column = column![].spacing(4);
+        for entry in entries {
+            entries_column = entries_column.push(view_entry(entry));
+        }
+        widget::scrollable(entries_column.padding([0, 8]))
+            .width(Length::Fill)
+            .into()
+    };
+
+    let mut layout = column![header].spacing(8).width(Length::Fill);
+    if let Some(msg) = params.status_message {
+        layout = layout.push(
+            widget::container(text(msg).size(11))
+                .padding([4, 8])
+                .width(Length::Fill)
+                .class(cosmic::theme::Container::Card),
+        );
+    }
+    layout
+        .push(search_box)
+        .push(widget::divider::horizontal::default())
+        .push(content)
+        .into()
+}
+
+/// Build a single history row.
+fn view_entry(entry: &HistoryEntry) -> Element<'_, Message> {
+    let title = if entry.title.is_empty() {
+        entry.app_name.clone()
+    } else {
+        format!("{}: {}", entry.app_name, entry.title)
+    };
+
+    widget::container(
+        row![
+            icon::from_name(fallback_icon(&entry.app_name)).size(20),
+            column![text(title).size(13), text(&entry.text).size(11)]
+                .spacing(2)
+                .width(Length::Fill),
+            text(format_timestamp(entry.time)).size(10),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
+    )
+    .padding([4, 8])
+    .width(Length::Fill)
+    .into()
+}
@@ -1,15 +1,26 @@
 //! Settings view components.
 
-use crate::app::{Message, SettingKey};
+use crate::app::{Message, RuleDraft, SettingKey};
 use crate::config::Config;
+use crate::constants::notifications::HISTORY_RETENTION_DAYS;
+use crate::device::filter::{NotificationRule, RuleAction};
 use crate::fl;
 use cosmic::iced::widget::{column, row, text};
 use cosmic::iced::{Alignment, Length};
 use cosmic::widget;
 use cosmic::Element;
+use std::sync::LazyLock;
+
+/// Labels of the notification history retention choices.
+static RETENTION_LABELS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    HISTORY_RETENTION_DAYS
+        .iter()
+        .map(|&days| fl!("history-retention-days", days = days))
+        .collect()
+});
 
 /// Render the settings view.
-pub fn view_settings(config: &Config) -> Element<'_, Message> {
+pub fn view_settings<'a>(config: &'a Config, rule_draft: &'a RuleDraft) -> Element<'a, Message> {
     let back_btn = widget::button::text(fl!("back"))
         .leading_icon(widget::icon::from_name("go-previous-symbolic").size(16))
         .on_press(Message::ToggleSettings);
@@ -36,6 +47,7 @@ pub fn view_settings(config: &Config) -> Element<'_, Message> {
             config.forward_notifications,
             SettingKey::ForwardNotifications,
         ),
+        view_history_retention(config.notification_history_days),
         widget::divider::horizontal::default(),
         view_setting_toggle(
             fl!("settings-sms-notifications"),
@@ -101,9 +113,167 @@ pub fn view_settings(config: &Config) -> Element<'_, Message> {
             SettingKey::FileNotifications,
         ));
 
+    // Media player bridge section
+    settings_col = settings_col
+        .push(widget::divider::horizontal::default())
+        .push(view_setting_toggle(
+            fl!("settings-media-bridge"),
+            fl!("settings-media-bridge-desc"),
+            config.media_player_bridge,
+            SettingKey::MediaPlayerBridge,
+        ));
+
+    // Notification rules section
+    settings_col = settings_col
+        .push(widget::divider::horizontal::default())
+        .push(view_notification_rules(config, rule_draft));
+
     widget::container(settings_col).width(Length::Fill).into()
 }
 
+/// Render the notification rules of each device and the form adding one.
+fn view_notification_rules<'a>(config: &'a Config, draft: &'a RuleDraft) -> Element<'a, Message> {
+    let mut rules_col = column![
+        text(fl!("settings-notification-rules")).size(14),
+        text(fl!("settings-notification-rules-desc"))
+            .size(11)
+            .wrapping(text::Wrapping::Word),
+    ]
+    .spacing(8);
+
+    for (device_id, rules) in &config.notification_rules {
+        let device_name = draft
+            .device_ids
+            .iter()
+            .position(|id| id == device_id)
+            .and_then(|index| draft.device_names.get(index))
+            .unwrap_or(device_id);
+        rules_col = rules_col.push(text(device_name).size(12));
+        for (index, rule) in rules.iter().enumerate() {
+            rules_col = rules_col.push(
+                row![
+                    text(rule_summary(rule)).size(11).width(Length::Fill),
+                    widget::button::icon(widget::icon::from_name("list-remove-symbolic"))
+                        .on_press(Message::RemoveNotificationRule(device_id.clone(), index)),
+                ]
+                .spacing(8)
+                .align_y(Alignment::Center),
+            );
+        }
+    }
+
+    // Device picker, only needed with more than one device
+    if draft.device_ids.len() > 1 {
+        rules_col = rules_col.push(
+            row![
+                text(fl!("notification-rule-device")).size(12),
+                widget::dropdown(&draft.device_names, draft.device, Message::RuleDraftDevice),
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center),
+        );
+    }
+    rules_col = rules_col
+        .push(
+            widget::text_input(fl!("notification-rule-app"), &draft.app_name)
+                .on_input(Message::RuleDraftAppName)
+                .width(Length::Fill),
+        )
+        .push(
+            widget::text_input(fl!("notification-rule-title"), &draft.title_pattern)
+                .on_input(Message::RuleDraftTitle)
+                .width(Length::Fill),
+        )
+        .push(view_rule_toggle(
+            fl!("notification-rule-silent"),
+            draft.silent_only,
+            Message::RuleDraftSilentOnly,
+        ))
+        .push(view_rule_toggle(
+            fl!("notification-rule-allow"),
+            draft.allow,
+            Message::RuleDraftAllow,
+        ));
+    if let Some(error) = &draft.error {
+        rules_col = rules_col.push(text(error).size(11));
+    }
+    rules_col = rules_col.push(
+        widget::button::text(fl!("notification-rule-add")).on_press(Message::AddNotificationRule),
+    );
+
+    widget::container(rules_col)
+        .padding(12)
+        .width(Length::Fill)
+        .into()
+}
+
+/// Render the notification history retention picker.
+fn view_history_retention(days: u32) -> Element<'static, Message> {
+    let selected = HISTORY_RETENTION_DAYS.iter().position(|&d| d == days);
+    let text_col = column![
+        text(fl!("settings-history-retention")).size(14),
+        text(fl!("settings-history-retention-desc"))
+            .size(11)
+            .wrapping(text::Wrapping::Word),
+    ]
+    .spacing(2)
+    .width(Length::Fill);
+
+    let setting_row = row![
+        text_col,
+        widget::dropdown(
+            RETENTION_LABELS.as_slice(),
+            selected,
+            Message::SetHistoryRetention
+        ),
+    ]
+    .spacing(12)
+    .align_y(Alignment::Center);
+
+    widget::container(setting_row)
+        .padding(12)
+        .width(Length::Fill)
+        .into()
+}
+
+/// Render a toggle of the rule being written.
+fn view_rule_toggle(
+    label: String,
+    enabled: bool,
+    on_toggle: fn(bool) -> Message,
+) -> Element<'static, Message> {
+    row![
+        text(label).size(12).width(Length::Fill),
+        widget::toggler(enabled).on_toggle(on_toggle),
+    ]
+    .spacing(12)
+    .align_y(Alignment::Center)
+    .into()
+}
+
+/// One-line description of a rule, e.g. "Hide · WhatsApp · silent only".
+fn rule_summary(rule: &NotificationRule) -> String {
+    let mut parts = vec![match rule.action {
+        RuleAction::Block => fl!("notification-rule-hide"),
+        RuleAction::Allow => fl!("notification-rule-show"),
+    }];
+    parts.push(if rule.app_name.is_empty() {
+        fl!("notification-rule-any-app")
+    } else {
+        rule.app_name.clone()
+    });
+    if !rule.title_pattern.is_empty() {
+        parts.push(fl!(
+            "notification-rule-title-matches",
+            pattern = rule.title_pattern.clone()
+        ));
+    }
+    if rule.silent_only {
+        parts.push(fl!("notification-rule-silent-only"));
+    }
+    parts.join(" · ")
+}
+
 /// Render a single setting toggle row.
 pub fn view_setting_toggle(
     title: String,
@@ -0,0 +1,964 @@
+//! High-level client for the KDE Connect daemon.
+//!
+//! The proxies in [`crate::plugins`] map one-to-one onto D-Bus objects, which
+//! leaves every caller to build object paths and to find out the hard way
+//! that a plugin isn't loaded. [`KdeConnectClient`] hands out a
+//! [`DeviceHandle`] per device instead. Handles build each plugin proxy once,
+//! check `hasPlugin` before using it and return domain types such as
+//! [`BatteryStatus`], [`NotificationInfo`] and [`SmsMessage`].
+//!
+//! ```no_run
+//! use kdeconnect_dbus::KdeConnectClient;
+//!
+//! # async fn example() -> kdeconnect_dbus::Result<()> {
+//! let client = KdeConnectClient::session().await?;
+//! for device in client.devices().await? {
+//!     let battery = device.battery_status().await?;
+//!     println!("{}: {}%", device.id(), battery.charge);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::events::{events_with, KdeConnectEvent};
+use crate::plugins::{
+    parse_conversations, parse_messages, BatteryProxy, BatteryStatus, ClipboardProxy,
+    ConversationSummary, ConversationsProxy, FindMyPhoneProxy, MprisRemoteProxy, NotificationInfo,
+    NotificationProxy, NotificationsProxy, PingProxy, ShareProxy, SmsMessage, SmsProxy,
+    TelephonyProxy,
+};
+use crate::{DaemonProxy, DeviceProxy, Error, Result, BASE_PATH};
+use futures_util::Stream;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+use zbus::proxy::{Builder, CacheProperties, Defaults};
+use zbus::zvariant::{Structure, Value};
+use zbus::Connection;
+
+/// A KDE Connect plugin that a device may or may not have loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Plugin {
+    Battery,
+    Clipboard,
+    FindMyPhone,
+    MprisRemote,
+    Notifications,
+    Ping,
+    Share,
+    Sms,
+    Telephony,
+}
+
+impl Plugin {
+    /// Plugin ID as reported by `supportedPlugins` (e.g. `kdeconnect_battery`).
+    pub fn id(self) -> &'static str {
+        match self {
+            Plugin::Battery => "kdeconnect_battery",
+            Plugin::Clipboard => "kdeconnect_clipboard",
+            Plugin::FindMyPhone => "kdeconnect_findmyphone",
+            Plugin::MprisRemote => "kdeconnect_mprisremote",
+            Plugin::Notifications => "kdeconnect_notifications",
+            Plugin::Ping => "kdeconnect_ping",
+            Plugin::Share => "kdeconnect_share",
+            Plugin::Sms => "kdeconnect_sms",
+            Plugin::Telephony => "kdeconnect_telephony",
+        }
+    }
+
+    /// Name of the plugin's object below the device path.
+    fn object_name(self) -> &'static str {
+        &self.id()["kdeconnect_".len()..]
+    }
+}
+
+/// D-Bus object path of a device.
+pub fn device_path(device_id: &str) -> String {
+    format!("{}/devices/{}", BASE_PATH, device_id)
+}
+
+/// D-Bus object path of one of a device's plugins.
+pub fn plugin_path(device_id: &str, plugin: Plugin) -> String {
+    format!("{}/{}", device_path(device_id), plugin.object_name())
+}
+
+/// Build a proxy at `path` without property caching.
+///
+/// The daemon doesn't emit `PropertiesChanged` for most of its properties,
+/// so a long-lived proxy with the default cache would return stale values.
+async fn build_proxy<P>(conn: &Connection, path: String) -> Result<P>
+where
+    P: Defaults + From<zbus::Proxy<'static>>,
+{
+    Ok(Builder::<P>::new(conn)
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?)
+}
+
+/// Format phone numbers as the `(s)` structs KDE Connect expects for addresses.
+fn address_values(addresses: &[String]) -> Vec<Value<'static>> {
+    addresses
+        .iter()
+        .map(|addr| Value::Structure(Structure::from((addr.clone(),))))
+        .collect()
+}
+
+/// Format attachment URLs as the string variants KDE Connect expects.
+fn url_values(urls: &[String]) -> Vec<Value<'static>> {
+    urls.iter().map(|url| Value::from(url.clone())).collect()
+}
+
+/// Entry point for talking to the KDE Connect daemon.
+///
+/// Cloning is cheap; clones share the connection and the per-device handles.
+#[derive(Clone)]
+pub struct KdeConnectClient {
+    state: Arc<Mutex<ClientState>>,
+}
+
+/// The connection and the proxies built on it, replaced together on reconnect.
+struct ClientState {
+    conn: Connection,
+    daemon: Arc<OnceCell<DaemonProxy<'static>>>,
+    devices: HashMap<String, DeviceHandle>,
+}
+
+impl ClientState {
+    fn new(conn: Connection) -> Self {
+        Self {
+            conn,
+            daemon: Arc::new(OnceCell::new()),
+            devices: HashMap::new(),
+        }
+    }
+}
+
+impl KdeConnectClient {
+    /// Create a client on an existing connection.
+    pub fn new(connection: Connection) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ClientState::new(connection))),
+        }
+    }
+
+    /// Connect to the session bus and create a client on it.
+    pub async fn session() -> Result<Self> {
+        Ok(Self::new(Connection::session().await?))
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, ClientState> {
+        self.state.lock().expect("client state poisoned")
+    }
+
+    /// The current D-Bus connection.
+    ///
+    /// This changes when the event stream reconnects, so don't hold on to it
+    /// longer than needed.
+    pub fn connection(&self) -> Connection {
+        self.state().conn.clone()
+    }
+
+    /// Move the client onto a new connection.
+    ///
+    /// Cached proxies and device handles belong to the old connection and are
+    /// dropped; handles that callers still hold keep using it.
+    pub fn set_connection(&self, connection: Connection) {
+        *self.state() = ClientState::new(connection);
+    }
+
+    /// Proxy for the daemon object.
+    pub async fn daemon(&self) -> Result<DaemonProxy<'static>> {
+        let (conn, cell) = {
+            let state = self.state();
+            (state.conn.clone(), state.daemon.clone())
+        };
+        let daemon = cell
+            .get_or_try_init(|| build_proxy(&conn, BASE_PATH.to_string()))
+            .await?;
+        Ok(daemon.clone())
+    }
+
+    /// IDs of all devices known to the daemon.
+    pub async fn device_ids(&self) -> Result<Vec<String>> {
+        Ok(self.daemon().await?.devices().await?)
+    }
+
+    /// Handles for all devices known to the daemon.
+    pub async fn devices(&self) -> Result<Vec<DeviceHandle>> {
+        let ids = self.device_ids().await?;
+        Ok(ids.iter().map(|id| self.device(id)).collect())
+    }
+
+    /// Stream of events from the daemon, subscribed on this client's connection.
+    ///
+    /// If the connection is lost the stream carries on over a new session bus
+    /// connection, which the client's calls then use as well; see
+    /// [`KdeConnectClient::events_reconnecting`].
+    pub fn events(&self) -> impl Stream<Item = KdeConnectEvent> {
+        self.events_reconnecting(Connection::session)
+    }
+
+    /// Stream of events from the daemon, using `connect` to replace a lost connection.
+    ///
+    /// The stream starts on the client's current connection. Each new
+    /// connection is also handed to the client with
+    /// [`KdeConnectClient::set_connection`], so calls don't stay on a dead
+    /// one after a daemon or bus restart. See [`events_with`].
+    pub fn events_reconnecting<F, Fut>(&self, mut connect: F) -> impl Stream<Item = KdeConnectEvent>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = zbus::Result<Connection>>,
+    {
+        let client = self.clone();
+        let mut reconnect = false;
+        events_with(move || {
+            let client = client.clone();
+            let next = reconnect.then(&mut connect);
+            reconnect = true;
+            async move {
+                let Some(next) = next else {
+                    return Ok(client.connection());
+                };
+                let conn = next.await?;
+                client.set_connection(conn.clone());
+                Ok(conn)
+            }
+        })
+    }
+
+    /// Handle for a device, reusing the cached one if there is one.
+    ///
+    /// This doesn't check that the device exists; calls on the handle fail
+    /// if it doesn't.
+    pub fn device(&self, device_id: &str) -> DeviceHandle {
+        let mut state = self.state();
+        let ClientState { conn, devices, .. } = &mut *state;
+        devices
+            .entry(device_id.to_string())
+            .or_insert_with(|| DeviceHandle::new(conn, device_id))
+            .clone()
+    }
+}
+
+impl std::fmt::Debug for KdeConnectClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KdeConnectClient")
+            .field("connection", &self.connection())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A device known to the daemon, with lazily built plugin proxies.
+///
+/// Cloning is cheap; clones share the cached proxies.
+#[derive(Clone)]
+pub struct DeviceHandle {
+    inner: Arc<DeviceInner>,
+}
+
+struct DeviceInner {
+    id: String,
+    conn: Connection,
+    device: OnceCell<DeviceProxy<'static>>,
+    battery: OnceCell<BatteryProxy<'static>>,
+    clipboard: OnceCell<ClipboardProxy<'static>>,
+    conversations: OnceCell<ConversationsProxy<'static>>,
+    findmyphone: OnceCell<FindMyPhoneProxy<'static>>,
+    mprisremote: OnceCell<MprisRemoteProxy<'static>>,
+    notifications: OnceCell<NotificationsProxy<'static>>,
+    ping: OnceCell<PingProxy<'static>>,
+    share: OnceCell<ShareProxy<'static>>,
+    sms: OnceCell<SmsProxy<'static>>,
+    telephony: OnceCell<TelephonyProxy<'static>>,
+}
+
+impl DeviceHandle {
+    /// Create a standalone handle for a device.
+    ///
+    /// Prefer [`KdeConnectClient::device`], which shares handles between callers.
+    pub fn new(connection: &Connection, device_id: &str) -> Self {
+        Self {
+            inner: Arc::new(DeviceInner {
+                id: device_id.to_string(),
+                conn: connection.clone(),
+                device: OnceCell::new(),
+                battery: OnceCell::new(),
+                clipboard: OnceCell::new(),
+                conversations: OnceCell::new(),
+                findmyphone: OnceCell::new(),
+                mprisremote: OnceCell::new(),
+                notifications: OnceCell::new(),
+                ping: OnceCell::new(),
+                share: OnceCell::new(),
+                sms: OnceCell::new(),
+                telephony: OnceCell::new(),
+            }),
+        }
+    }
+
+    /// The device ID.
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    /// Proxy for the device object itself (name, pairing, reachability).
+    pub async fn proxy(&self) -> Result<DeviceProxy<'static>> {
+        let device = self
+            .inner
+            .device
+            .get_or_try_init(|| build_proxy(&self.inner.conn, device_path(&self.inner.id)))
+            .await?;
+        Ok(device.clone())
+    }
+
+    /// Whether the device currently has `plugin` loaded.
+    ///
+    /// Plugins are unloaded while a device is unreachable or unpaired, so this
+    /// is asked fresh on every call rather than cached.
+    pub async fn has_plugin(&self, plugin: Plugin) -> Result<bool> {
+        let device = self.proxy().await?;
+        device
+            .has_plugin(plugin.id())
+            .await
+            .map_err(|e| self.device_error(e))
+    }
+
+    /// Classify a failed call on the device object.
+    fn device_error(&self, err: zbus::Error) -> Error {
+        Error::from_device_call(err, &self.inner.id, None)
+    }
+
+    /// Classify a failed call on one of the device's plugins.
+    fn plugin_error(&self, plugin: Plugin) -> impl Fn(zbus::Error) -> Error + '_ {
+        move |err| Error::from_device_call(err, &self.inner.id, Some(plugin))
+    }
+
+    /// Fail unless `plugin` is loaded, saying why when it isn't.
+    ///
+    /// The daemon unloads all plugins of a device that is out of reach or not
+    /// paired, so those are checked before blaming the plugin itself.
+    async fn require(&self, plugin: Plugin) -> Result<()> {
+        if self.has_plugin(plugin).await? {
+            return Ok(());
+        }
+
+        let device = self.proxy().await?;
+        let id = self.inner.id.clone();
+        if !device
+            .is_reachable()
+            .await
+            .map_err(|e| self.device_error(e))?
+        {
+            return Err(Error::DeviceNotReachable(id));
+        }
+        if !device
+            .is_trusted()
+            .await
+            .map_err(|e| self.device_error(e))?
+        {
+            return Err(Error::DeviceNotPaired(id));
+        }
+        Err(Error::PluginNotAvailable {
+            device: id,
+            plugin: plugin.id().to_string(),
+        })
+    }
+
+    /// Ask the device to pair with us.
+    pub async fn request_pairing(&self) -> Result<()> {
+        let device = self.proxy().await?;
+        device
+            .request_pair()
+            .await
+            .map_err(|e| self.device_error(e))
+    }
+
+    /// Accept a pairing request from the device.
+    pub async fn accept_pairing(&self) -> Result<()> {
+        let device = self.proxy().await?;
+        device
+            .accept_pairing()
+            .await
+            .map_err(|e| self.device_error(e))
+    }
+
+    /// Reject a pairing request from the device, or cancel our own.
+    pub async fn reject_pairing(&self) -> Result<()> {
+        let device = self.proxy().await?;
+        device
+            .reject_pairing()
+            .await
+            .map_err(|e| self.device_error(e))
+    }
+
+    /// Unpair from the device.
+    pub async fn unpair(&self) -> Result<()> {
+        let device = self.proxy().await?;
+        device.unpair().await.map_err(|e| self.device_error(e))
+    }
+
+    /// Check that `plugin` is loaded, then return the cached proxy at `path`.
+    async fn plugin_proxy<P>(&self, cell: &OnceCell<P>, plugin: Plugin, path: String) -> Result<P>
+    where
+        P: Defaults + From<zbus::Proxy<'static>> + Clone,
+    {
+        self.require(plugin).await?;
+        let proxy = cell
+            .get_or_try_init(|| build_proxy(&self.inner.conn, path))
+            .await?;
+        Ok(proxy.clone())
+    }
+
+    /// Proxy for the battery plugin.
+    pub async fn battery_proxy(&self) -> Result<BatteryProxy<'static>> {
+        let path = plugin_path(&self.inner.id, Plugin::Battery);
+        self.plugin_proxy(&self.inner.battery, Plugin::Battery, path)
+            .await
+    }
+
+    /// Proxy for the clipboard plugin.
+    pub async fn clipboard_proxy(&self) -> Result<ClipboardProxy<'static>> {
+        let path = plugin_path(&self.inner.id, Plugin::Clipboard);
+        self.plugin_proxy(&self.inner.clipboard, Plugin::Clipboard, path)
+            .await
+    }
+
+    /// Proxy for the conversations interface of the SMS plugin.
+    ///
+    /// Unlike the other plugins this lives on the device path itself.
+    pub async fn conversations_proxy(&self) -> Result<ConversationsProxy<'static>> {
+        let path = device_path(&self.inner.id);
+        self.plugin_proxy(&self.inner.conversations, Plugin::Sms, path)
+            .await
+    }
+
+    /// Proxy for the find-my-phone plugin.
+    pub async fn find_my_phone_proxy(&self) -> Result<FindMyPhoneProxy<'static>> {
+        let path = plugin_path(&self.inner.id, Plugin::FindMyPhone);
+        self.plugin_proxy(&self.inner.findmyphone, Plugin::FindMyPhone, path)
+            .await
+    }
+
+    /// Proxy for the media remote control plugin.
+    pub async fn mpris_remote_proxy(&self) -> Result<MprisRemoteProxy<'static>> {
+        let path = plugin_path(&self.inner.id, Plugin::MprisRemote);
+        self.plugin_proxy(&self.inner.mprisremote, Plugin::MprisRemote, path)
+            .await
+    }
+
+    /// Proxy for the notifications plugin.
+    pub async fn notifications_proxy(&self) -> Result<NotificationsProxy<'static>> {
+        let path = plugin_path(&self.inner.id, Plugin::Notifications);
+        self.plugin_proxy(&self.inner.notifications, Plugin::Notifications, path)
+            .await
+    }
+
+    /// Proxy for a single notification.
+    ///
+    /// Notifications come and go, so these proxies are not cached.
+    pub async fn notification_proxy(
+        &self,
+        notification_id: &str,
+    ) -> Result<NotificationProxy<'static>> {
+        self.require(Plugin::Notifications).await?;
+        self.build_notification_proxy(notification_id).await
+    }
+
+    /// Build the proxy of a notification without checking the plugin first.
+    async fn build_notification_proxy(
+        &self,
+        notification_id: &str,
+    ) -> Result<NotificationProxy<'static>> {
+        let path = format!(
+            "{}/{}",
+            plugin_path(&self.inner.id, Plugin::Notifications),
+            notification_id
+        );
+        build_proxy(&self.inner.conn, path).await
+    }
+
+    /// Proxy for the ping plugin.
+    pub async fn ping_proxy(&self) -> Result<PingProxy<'static>> {
+        let path = plugin_path(&self.inner.id, Plugin::Ping);
+        self.plugin_proxy(&self.inner.ping, Plugin::Ping, path)
+            .await
+    }
+
+    /// Proxy for the share plugin.
+    pub async fn share_proxy(&self) -> Result<ShareProxy<'static>> {
+        let path = plugin_path(&self.inner.id, Plugin::Share);
+        self.plugin_proxy(&self.inner.share, Plugin::Share, path)
+            .await
+    }
+
+    /// Proxy for the SMS plugin object.
+    pub async fn sms_proxy(&self) -> Result<SmsProxy<'static>> {
+        let path = plugin_path(&self.inner.id, Plugin::Sms);
+        self.plugin_proxy(&self.inner.sms, Plugin::Sms, path).await
+    }
+
+    /// Proxy for the telephony plugin.
+    pub async fn telephony_proxy(&self) -> Result<TelephonyProxy<'static>> {
+        let path = plugin_path(&self.inner.id, Plugin::Telephony);
+        self.plugin_proxy(&self.inner.telephony, Plugin::Telephony, path)
+            .await
+    }
+
+    /// Current battery charge and charging state.
+    pub async fn battery_status(&self) -> Result<BatteryStatus> {
+        let battery = self.battery_proxy().await?;
+        let err = self.plugin_error(Plugin::Battery);
+        Ok(BatteryStatus {
+            charge: battery.charge().await.map_err(&err)?,
+            is_charging: battery.is_charging().await.map_err(&err)?,
+        })
+    }
+
+    /// All notifications currently shown on the device.
+    ///
+    /// Notifications that disappear while the list is being read are skipped.
+    pub async fn notifications(&self) -> Result<Vec<NotificationInfo>> {
+        let ids = self
+            .notifications_proxy()
+            .await?
+            .active_notifications()
+            .await
+            .map_err(self.plugin_error(Plugin::Notifications))?;
+
+        // notifications_proxy() checked the plugin, so each notification is
+        // read without asking the daemon again
+        let mut notifications = Vec::with_capacity(ids.len());
+        for id in ids {
+            let info = match self.build_notification_proxy(&id).await {
+                Ok(notification) => self.read_notification(&id, &notification).await,
+                Err(e) => Err(e),
+            };
+            match info {
+                Ok(info) => notifications.push(info),
+                Err(e) => tracing::warn!("Failed to read notification {}: {}", id, e),
+            }
+        }
+        Ok(notifications)
+    }
+
+    /// A single notification.
+    pub async fn notification(&self, notification_id: &str) -> Result<NotificationInfo> {
+        let notification = self.notification_proxy(notification_id).await?;
+        self.read_notification(notification_id, &notification).await
+    }
+
+    /// Read the properties of a notification.
+    async fn read_notification(
+        &self,
+        notification_id: &str,
+        notification: &NotificationProxy<'static>,
+    ) -> Result<NotificationInfo> {
+        let err = self.plugin_error(Plugin::Notifications);
+        let reply_id = notification.reply_id().await.map_err(&err)?;
+        // The daemon downloads the icon to its cache; the path is empty until then
+        let icon_path = if notification.has_icon().await.unwrap_or(false) {
+            notification
+                .icon_path()
+                .await
+                .ok()
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+        } else {
+            None
+        };
+
+        Ok(NotificationInfo {
+            id: notification_id.to_string(),
+            app_name: notification.app_name().await.map_err(&err)?,
+            title: notification.title().await.map_err(&err)?,
+            text: notification.text().await.map_err(&err)?,
+            icon_path,
+            dismissable: notification.dismissable().await.map_err(&err)?,
+            repliable: !reply_id.is_empty(),
+            silent: notification.silent().await.map_err(&err)?,
+        })
+    }
+
+    /// Dismiss a notification on the device.
+    pub async fn dismiss_notification(&self, notification_id: &str) -> Result<()> {
+        let notification = self.notification_proxy(notification_id).await?;
+        notification
+            .dismiss()
+            .await
+            .map_err(self.plugin_error(Plugin::Notifications))
+    }
+
+    /// Reply to a notification that offers a reply action (see
+    /// [`NotificationInfo::repliable`]).
+    pub async fn reply_to_notification(&self, notification_id: &str, message: &str) -> Result<()> {
+        let notification = self.notification_proxy(notification_id).await?;
+        notification
+            .send_reply(message)
+            .await
+            .map_err(self.plugin_error(Plugin::Notifications))
+    }
+
+    /// Send a ping.
+    pub async fn ping(&self) -> Result<()> {
+        let ping = self.ping_proxy().await?;
+        ping.send_ping()
+            .await
+            .map_err(self.plugin_error(Plugin::Ping))
+    }
+
+    /// Make the device ring so it can be found.
+    pub async fn ring(&self) -> Result<()> {
+        let findmyphone = self.find_my_phone_proxy().await?;
+        findmyphone
+            .ring()
+            .await
+            .map_err(self.plugin_error(Plugin::FindMyPhone))
+    }
+
+    /// Share a URL with the device.
+    pub async fn share_url(&self, url: &str) -> Result<()> {
+        let share = self.share_proxy().await?;
+        share
+            .share_url(url)
+            .await
+            .map_err(self.plugin_error(Plugin::Share))
+    }
+
+    /// Send a local file to the device.
+    pub async fn share_file(&self, path: &Path) -> Result<()> {
+        self.share_url(&format!("file://{}", path.display())).await
+    }
+
+    /// Share text with the device.
+    pub async fn share_text(&self, text: &str) -> Result<()> {
+        let share = self.share_proxy().await?;
+        share
+            .share_text(text)
+            .await
+            .map_err(self.plugin_error(Plugin::Share))
+    }
+
+    /// Send the desktop clipboard contents to the device.
+    pub async fn send_clipboard(&self) -> Result<()> {
+        let clipboard = self.clipboard_proxy().await?;
+        clipboard
+            .send_clipboard()
+            .await
+            .map_err(self.plugin_error(Plugin::Clipboard))
+    }
+
+    /// Conversations the daemon has cached, newest first.
+    ///
+    /// This doesn't ask the phone for anything; see
+    /// [`ConversationsProxy::request_all_conversation_threads`] for that.
+    pub async fn conversations(&self) -> Result<Vec<ConversationSummary>> {
+        let values = self
+            .conversations_proxy()
+            .await?
+            .active_conversations()
+            .await
+            .map_err(self.plugin_error(Plugin::Sms))?;
+        Ok(parse_conversations(values))
+    }
+
+    /// Cached messages belonging to one conversation thread.
+    pub async fn cached_messages(&self, thread_id: i64) -> Result<Vec<SmsMessage>> {
+        let values = self
+            .conversations_proxy()
+            .await?
+            .active_conversations()
+            .await
+            .map_err(self.plugin_error(Plugin::Sms))?;
+        Ok(parse_messages(values, thread_id))
+    }
+
+    /// Send an SMS straight through the SMS plugin.
+    ///
+    /// `addresses` must be the full participant list of the thread for group
+    /// messages to land in the right conversation. `attachment_urls` are
+    /// `file://` URLs of local files; any attachment makes the phone send an
+    /// MMS. `sub_id` selects the SIM (-1 for the default).
+    pub async fn send_sms(
+        &self,
+        addresses: &[String],
+        text: &str,
+        attachment_urls: &[String],
+        sub_id: i64,
+    ) -> Result<()> {
+        let sms = self.sms_proxy().await?;
+        sms.send_sms(
+            address_values(addresses),
+            text,
+            url_values(attachment_urls),
+            sub_id,
+        )
+        .await
+        .map_err(self.plugin_error(Plugin::Sms))
+    }
+
+    /// Send a message without naming a thread; the phone picks or creates one.
+    pub async fn send_without_conversation(
+        &self,
+        addresses: &[String],
+        text: &str,
+        attachment_urls: &[String],
+    ) -> Result<()> {
+        let conversations = self.conversations_proxy().await?;
+        conversations
+            .send_without_conversation(address_values(addresses), text, url_values(attachment_urls))
+            .await
+            .map_err(self.plugin_error(Plugin::Sms))
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::mock::{MockDaemon, MockDevice, MockNotification, MockSms};
+    use futures_util::StreamExt;
+    use std::time::Duration;
+
+    const DEVICE: &str = "abc123";
+
+    async fn client_with_device(device: MockDevice) -> (MockDaemon, KdeConnectClient) {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(device).await.unwrap();
+        let client = KdeConnectClient::new(mock.connection().clone());
+        (mock, client)
+    }
+
+    #[test]
+    fn plugin_paths() {
+        assert_eq!(Plugin::FindMyPhone.id(), "kdeconnect_findmyphone");
+        assert_eq!(
+            plugin_path("abc", Plugin::MprisRemote),
+            "/modules/kdeconnect/devices/abc/mprisremote"
+        );
+    }
+
+    #[tokio::test]
+    async fn devices_are_listed_and_cached() {
+        let (_mock, client) = client_with_device(MockDevice::new(DEVICE, "Pixel")).await;
+
+        let devices = client.devices().await.unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].id(), DEVICE);
+        assert!(Arc::ptr_eq(&devices[0].inner, &client.device(DEVICE).inner));
+    }
+
+    #[tokio::test]
+    async fn calls_follow_the_event_stream_onto_a_new_connection() {
+        let (first, client) = client_with_device(MockDevice::new(DEVICE, "Pixel")).await;
+        let second = MockDaemon::start().await.unwrap();
+        second
+            .add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        let replacement = Mutex::new(Some(second.connection().clone()));
+        let mut events = Box::pin(client.events_reconnecting(move || {
+            std::future::ready(Ok(replacement.lock().unwrap().take().unwrap()))
+        }));
+
+        assert!(matches!(
+            events.next().await,
+            Some(KdeConnectEvent::Connected)
+        ));
+        client.device(DEVICE).ping().await.unwrap();
+        assert_eq!(first.calls_to("ping").len(), 1);
+
+        drop(first);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !matches!(events.next().await, Some(KdeConnectEvent::Connected)) {}
+        })
+        .await
+        .expect("event stream didn't reconnect");
+
+        client.device(DEVICE).ping().await.unwrap();
+        assert_eq!(second.calls_to("ping").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn battery_status() {
+        let (mock, client) = client_with_device(MockDevice::new(DEVICE, "Pixel")).await;
+        mock.set_battery(DEVICE, 42, true).await.unwrap();
+
+        let status = client.device(DEVICE).battery_status().await.unwrap();
+        assert_eq!(
+            status,
+            BatteryStatus {
+                charge: 42,
+                is_charging: true
+            }
+        );
+
+        // The cached proxy must not hand back stale property values.
+        mock.set_battery(DEVICE, 41, false).await.unwrap();
+        let status = client.device(DEVICE).battery_status().await.unwrap();
+        assert_eq!(status.charge, 41);
+        assert!(!status.is_charging);
+    }
+
+    #[tokio::test]
+    async fn missing_plugin_is_reported() {
+        let mut device = MockDevice::new(DEVICE, "Pixel");
+        device.plugins.retain(|p| p != "kdeconnect_ping");
+        let (mock, client) = client_with_device(device).await;
+
+        let err = client.device(DEVICE).ping().await.unwrap_err();
+        assert!(
+            matches!(err, Error::PluginNotAvailable { ref plugin, .. } if plugin == "kdeconnect_ping"),
+            "unexpected error: {err}"
+        );
+        assert!(mock.calls_to("ping").is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_device_is_reported() {
+        let (_mock, client) = client_with_device(MockDevice::new(DEVICE, "Pixel")).await;
+
+        let err = client.device("nope").ping().await.unwrap_err();
+        assert!(matches!(err, Error::DeviceNotFound(ref id) if id == "nope"));
+    }
+
+    #[tokio::test]
+    async fn unreachable_and_unpaired_devices_are_reported() {
+        let (mock, client) = client_with_device(MockDevice::new(DEVICE, "Pixel")).await;
+        let device = client.device(DEVICE);
+
+        mock.set_reachable(DEVICE, false).await.unwrap();
+        let err = device.ring().await.unwrap_err();
+        assert!(matches!(err, Error::DeviceNotReachable(_)), "{err}");
+
+        mock.set_reachable(DEVICE, true).await.unwrap();
+        device.unpair().await.unwrap();
+        let err = device.ring().await.unwrap_err();
+        assert!(matches!(err, Error::DeviceNotPaired(_)), "{err}");
+        assert!(mock.calls_to("findmyphone").is_empty());
+    }
+
+    #[tokio::test]
+    async fn actions_reach_the_device() {
+        let (mock, client) = client_with_device(MockDevice::new(DEVICE, "Pixel")).await;
+        let device = client.device(DEVICE);
+
+        device.ping().await.unwrap();
+        device.ring().await.unwrap();
+        device.share_text("hello").await.unwrap();
+        device.send_clipboard().await.unwrap();
+
+        assert_eq!(mock.calls_to("ping").len(), 1);
+        assert_eq!(mock.calls_to("findmyphone").len(), 1);
+        assert_eq!(mock.calls_to("share")[0].args, vec!["hello".to_string()]);
+        assert_eq!(mock.calls_to("clipboard").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn notifications_are_read_replied_to_and_dismissed() {
+        let (mock, client) = client_with_device(MockDevice::new(DEVICE, "Pixel")).await;
+        let mut notification = MockNotification::new("n1", "Signal", "Alice", "Hi");
+        notification.reply_id = "r1".to_string();
+        mock.post_notification(DEVICE, notification).await.unwrap();
+
+        let device = client.device(DEVICE);
+        let notifications = device.notifications().await.unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].app_name, "Signal");
+        assert!(notifications[0].repliable);
+
+        device
+            .reply_to_notification("n1", "On my way")
+            .await
+            .unwrap();
+        device.dismiss_notification("n1").await.unwrap();
+        let calls = mock.calls_to("notification");
+        assert_eq!(calls[0].method, "sendReply");
+        assert_eq!(
+            calls[0].args,
+            vec!["n1".to_string(), "On my way".to_string()]
+        );
+        assert_eq!(calls[1].method, "dismiss");
+    }
+
+    #[tokio::test]
+    async fn notification_icons_are_read_when_present() {
+        let (mock, client) = client_with_device(MockDevice::new(DEVICE, "Pixel")).await;
+        let mut with_icon = MockNotification::new("n1", "Signal", "Alice", "Hi");
+        with_icon.has_icon = true;
+        with_icon.icon_path = "/tmp/kdeconnect/1a2b.png".to_string();
+        let mut pending_icon = MockNotification::new("n2", "Signal", "Bob", "Hey");
+        pending_icon.has_icon = true;
+        let mut stale_path = MockNotification::new("n3", "Gmail", "News", "...");
+        stale_path.icon_path = "/tmp/kdeconnect/3c4d.png".to_string();
+        for notification in [with_icon, pending_icon, stale_path] {
+            mock.post_notification(DEVICE, notification).await.unwrap();
+        }
+
+        let device = client.device(DEVICE);
+        assert_eq!(
+            device.notification("n1").await.unwrap().icon_path,
+            Some(PathBuf::from("/tmp/kdeconnect/1a2b.png"))
+        );
+        assert_eq!(device.notification("n2").await.unwrap().icon_path, None);
+        assert_eq!(device.notification("n3").await.unwrap().icon_path, None);
+    }
+
+    #[tokio::test]
+    async fn silent_notifications_are_flagged() {
+        let (mock, client) = client_with_device(MockDevice::new(DEVICE, "Pixel")).await;
+        let mut silent = MockNotification::new("n1", "Backup", "Done", "1 GB");
+        silent.silent = true;
+        mock.post_notification(DEVICE, silent).await.unwrap();
+        mock.post_notification(DEVICE, MockNotification::new("n2", "Signal", "Alice", "Hi"))
+            .await
+            .unwrap();
+
+        let device = client.device(DEVICE);
+        assert!(device.notification("n1").await.unwrap().silent);
+        assert!(!device.notification("n2").await.unwrap().silent);
+    }
+
+    #[tokio::test]
+    async fn conversations_and_sms() {
+        let (mock, client) = client_with_device(MockDevice::new(DEVICE, "Pixel")).await;
+        mock.add_sms(
+            DEVICE,
+            [
+                MockSms::inbox(7, 1, "+15550001", "first", 1_000),
+                MockSms::inbox(7, 2, "+15550001", "second", 2_000),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let device = client.device(DEVICE);
+        let conversations = device.conversations().await.unwrap();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].last_message, "second");
+
+        let messages = device.cached_messages(7).await.unwrap();
+        assert_eq!(messages[0].body, "second");
+
+        let recipients = vec!["+15550001".to_string(), "+15550002".to_string()];
+        device
+            .send_sms(&recipients, "hi all", &[], 3)
+            .await
+            .unwrap();
+        let call = &mock.calls_to("sms")[0];
+        assert_eq!(call.method, "sendSms");
+        assert_eq!(call.args[0], "+15550001,+15550002");
+        assert_eq!(call.args[3], "3");
+
+        let attachments = vec!["file:///tmp/cat.jpg".to_string()];
+        device
+            .send_without_conversation(&recipients[..1], "", &attachments)
+            .await
+            .unwrap();
+        let call = &mock.calls_to("conversations")[0];
+        assert_eq!(call.method, "sendWithoutConversation");
+        assert_eq!(call.args, vec!["+15550001", "", "1"]);
+    }
+}
@@ -3,8 +3,36 @@
 //! The daemon is the central service that manages device discovery,
 //! pairing, and plugin management.
 
+use crate::SERVICE_NAME;
+use zbus::fdo::DBusProxy;
+use zbus::names::BusName;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::OwnedObjectPath;
 use zbus::{proxy, Connection};
 
+/// systemd user unit generated from the daemon's XDG autostart entry.
+const SYSTEMD_UNIT: &str = "app-org.kde.kdeconnect.daemon@autostart.service";
+
+/// Whether the daemon is installed and running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaemonStatus {
+    /// Neither the bus nor systemd can start `kdeconnectd`.
+    NotInstalled,
+    /// The daemon is installed but doesn't own its bus name.
+    NotRunning,
+    /// The daemon owns its bus name.
+    Running,
+}
+
+/// How the daemon went away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaemonExit {
+    /// It quit normally, or wasn't run by systemd so nothing recorded how.
+    Clean,
+    /// Its systemd unit failed.
+    Crashed,
+}
+
 /// Proxy for the KDE Connect daemon D-Bus interface.
 ///
 /// This provides access to daemon-level operations like discovering devices,
@@ -48,4 +76,202 @@ impl DaemonProxy<'_> {
             Err(_) => false,
         }
     }
+
+    /// Find out whether the daemon is running, or could be started.
+    ///
+    /// The daemon counts as installed when the bus can activate it, which is
+    /// what the D-Bus service file shipped with KDE Connect provides, or when
+    /// systemd has a unit for it, which [`DaemonProxy::start`] falls back to.
+    pub async fn status(connection: &Connection) -> zbus::Result<DaemonStatus> {
+        let dbus = DBusProxy::new(connection).await?;
+        if dbus
+            .name_has_owner(BusName::try_from(SERVICE_NAME)?)
+            .await?
+        {
+            return Ok(DaemonStatus::Running);
+        }
+        let activatable = dbus.list_activatable_names().await?;
+        if activatable.iter().any(|name| name.as_str() == SERVICE_NAME) {
+            return Ok(DaemonStatus::NotRunning);
+        }
+        match systemd_unit(connection).await {
+            Ok(Some(_)) => Ok(DaemonStatus::NotRunning),
+            Ok(None) => Ok(DaemonStatus::NotInstalled),
+            Err(e) => {
+                // No systemd user manager on the bus
+                tracing::debug!("Failed to look up {}: {}", SYSTEMD_UNIT, e);
+                Ok(DaemonStatus::NotInstalled)
+            }
+        }
+    }
+
+    /// Find out whether the daemon crashed or quit normally.
+    ///
+    /// Call this after it went away; systemd records how its unit ended.
+    pub async fn last_exit(connection: &Connection) -> DaemonExit {
+        match systemd_unit_state(connection).await {
+            Ok(Some(state)) if state == "failed" => DaemonExit::Crashed,
+            Ok(_) => DaemonExit::Clean,
+            Err(e) => {
+                tracing::debug!("Failed to read the state of {}: {}", SYSTEMD_UNIT, e);
+                DaemonExit::Clean
+            }
+        }
+    }
+
+    /// Start the daemon.
+    ///
+    /// Tries D-Bus activation first and falls back to the systemd user unit
+    /// for setups without the D-Bus service file. Returns once the start was
+    /// requested; the daemon claims its bus name shortly after.
+    pub async fn start(connection: &Connection) -> zbus::Result<()> {
+        let dbus = DBusProxy::new(connection).await?;
+        let activation = dbus
+            .start_service_by_name(SERVICE_NAME.try_into()?, 0)
+            .await;
+        match activation {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::debug!("D-Bus activation of {} failed: {}", SERVICE_NAME, e);
+                let systemd = SystemdManagerProxy::new(connection).await?;
+                systemd.start_unit(SYSTEMD_UNIT, "replace").await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// `ActiveState` of the daemon's systemd unit, if systemd has one.
+async fn systemd_unit_state(connection: &Connection) -> zbus::Result<Option<String>> {
+    let Some(path) = systemd_unit(connection).await? else {
+        return Ok(None);
+    };
+    let unit = SystemdUnitProxy::builder(connection)
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    Ok(Some(unit.active_state().await?))
+}
+
+/// Object path of the daemon's systemd unit, if systemd has one.
+async fn systemd_unit(connection: &Connection) -> zbus::Result<Option<OwnedObjectPath>> {
+    let systemd = SystemdManagerProxy::new(connection).await?;
+    let path = systemd.load_unit(SYSTEMD_UNIT).await?;
+    let unit = SystemdUnitProxy::builder(connection)
+        .path(path.clone())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    // Units without a unit file load as "not-found"
+    Ok((unit.load_state().await? == "loaded").then_some(path))
+}
+
+/// The part of systemd's user manager used to find and start the daemon's unit.
+#[proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait SystemdManager {
+    /// Queue a start job for a unit.
+    #[zbus(name = "StartUnit")]
+    fn start_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+
+    /// Load a unit, whether or not it has a unit file, and return its object.
+    #[zbus(name = "LoadUnit")]
+    fn load_unit(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+/// The state of a systemd unit.
+#[proxy(
+    interface = "org.freedesktop.systemd1.Unit",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait SystemdUnit {
+    /// Whether the unit's configuration was found: `loaded`, `not-found`, ...
+    #[zbus(property, name = "LoadState")]
+    fn load_state(&self) -> zbus::Result<String>;
+
+    /// Whether the unit runs: `active`, `inactive`, `failed`, ...
+    #[zbus(property, name = "ActiveState")]
+    fn active_state(&self) -> zbus::Result<String>;
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockDaemon;
+
+    #[tokio::test]
+    async fn status_follows_name_ownership() {
+        let mock = MockDaemon::start().await.unwrap();
+        let conn = mock.connection();
+        assert_eq!(
+            DaemonProxy::status(conn).await.unwrap(),
+            DaemonStatus::Running
+        );
+
+        mock.set_running(false).await.unwrap();
+        assert_eq!(
+            DaemonProxy::status(conn).await.unwrap(),
+            DaemonStatus::NotRunning
+        );
+
+        mock.set_installed(false).await.unwrap();
+        assert_eq!(
+            DaemonProxy::status(conn).await.unwrap(),
+            DaemonStatus::NotInstalled
+        );
+    }
+
+    #[tokio::test]
+    async fn start_activates_daemon() {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.set_running(false).await.unwrap();
+
+        DaemonProxy::start(mock.connection()).await.unwrap();
+        assert_eq!(
+            DaemonProxy::status(mock.connection()).await.unwrap(),
+            DaemonStatus::Running
+        );
+        let calls = mock.calls_to("bus");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].method, "StartServiceByName");
+    }
+
+    #[tokio::test]
+    async fn systemd_unit_counts_as_installed() {
+        let mock = MockDaemon::start().await.unwrap();
+        let conn = mock.connection();
+        mock.set_running(false).await.unwrap();
+        mock.set_installed(false).await.unwrap();
+        mock.set_unit(Some("inactive")).await.unwrap();
+        assert_eq!(
+            DaemonProxy::status(conn).await.unwrap(),
+            DaemonStatus::NotRunning
+        );
+
+        DaemonProxy::start(conn).await.unwrap();
+        assert_eq!(
+            DaemonProxy::status(conn).await.unwrap(),
+            DaemonStatus::Running
+        );
+        let calls = mock.calls_to("systemd");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].method, "StartUnit");
+    }
+
+    #[tokio::test]
+    async fn only_a_failed_unit_counts_as_crashed() {
+        let mock = MockDaemon::start().await.unwrap();
+        let conn = mock.connection();
+        assert_eq!(DaemonProxy::last_exit(conn).await, DaemonExit::Clean);
+
+        mock.set_unit(Some("inactive")).await.unwrap();
+        assert_eq!(DaemonProxy::last_exit(conn).await, DaemonExit::Clean);
+
+        mock.set_unit(Some("failed")).await.unwrap();
+        assert_eq!(DaemonProxy::last_exit(conn).await, DaemonExit::Crashed);
+    }
 }
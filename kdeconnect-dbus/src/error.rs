@@ -1,6 +1,8 @@
 //! Error types for the kdeconnect-dbus crate.
 
+use crate::client::Plugin;
 use thiserror::Error;
+use zbus::DBusError;
 
 /// Result type alias using our Error type.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -10,7 +12,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     /// D-Bus communication error
     #[error("D-Bus error: {0}")]
-    DBus(#[from] zbus::Error),
+    DBus(#[source] zbus::Error),
 
     /// The KDE Connect daemon is not running
     #[error("KDE Connect daemon is not running")]
@@ -32,3 +34,171 @@ pub enum Error {
     #[error("Device '{0}' is not paired")]
     DeviceNotPaired(String),
 }
+
+const SERVICE_UNKNOWN: &str = "org.freedesktop.DBus.Error.ServiceUnknown";
+const NAME_HAS_NO_OWNER: &str = "org.freedesktop.DBus.Error.NameHasNoOwner";
+const UNKNOWN_OBJECT: &str = "org.freedesktop.DBus.Error.UnknownObject";
+const UNKNOWN_INTERFACE: &str = "org.freedesktop.DBus.Error.UnknownInterface";
+const UNKNOWN_METHOD: &str = "org.freedesktop.DBus.Error.UnknownMethod";
+const KDECONNECT_ERROR_PREFIX: &str = "org.kde.kdeconnect";
+
+/// D-Bus error name and description of a failed call, if the peer replied with one.
+fn error_reply(err: &zbus::Error) -> Option<(String, String)> {
+    match err {
+        zbus::Error::MethodError(name, description, _) => {
+            Some((name.to_string(), description.clone().unwrap_or_default()))
+        }
+        zbus::Error::FDO(fdo) => Some((
+            fdo.name().to_string(),
+            fdo.description().unwrap_or_default().to_string(),
+        )),
+        _ => None,
+    }
+}
+
+impl From<zbus::Error> for Error {
+    /// Wrap a D-Bus error, recognizing a daemon that isn't on the bus.
+    fn from(err: zbus::Error) -> Self {
+        match error_reply(&err) {
+            Some((name, _)) if name == SERVICE_UNKNOWN || name == NAME_HAS_NO_OWNER => {
+                Error::DaemonNotRunning
+            }
+            _ => Error::DBus(err),
+        }
+    }
+}
+
+impl Error {
+    /// Classify a failed call on a device object (`plugin` is `None`) or on one
+    /// of its plugins.
+    ///
+    /// The daemon removes a device's object when it forgets the device and a
+    /// plugin's object when the plugin is unloaded, so a missing object or
+    /// method says which of the two is gone. The daemon doesn't define a fixed
+    /// set of its own error names; those mentioning reachability or pairing
+    /// are mapped onto the matching variant.
+    pub fn from_device_call(err: zbus::Error, device_id: &str, plugin: Option<Plugin>) -> Self {
+        let Some((name, description)) = error_reply(&err) else {
+            return Error::DBus(err);
+        };
+        let plugin_missing = |plugin: Plugin| Error::PluginNotAvailable {
+            device: device_id.to_string(),
+            plugin: plugin.id().to_string(),
+        };
+
+        match (name.as_str(), plugin) {
+            (UNKNOWN_OBJECT, None) => Error::DeviceNotFound(device_id.to_string()),
+            (UNKNOWN_OBJECT | UNKNOWN_INTERFACE | UNKNOWN_METHOD, Some(plugin)) => {
+                plugin_missing(plugin)
+            }
+            (name, _) if name.starts_with(KDECONNECT_ERROR_PREFIX) => {
+                let text = format!("{} {}", name, description).to_lowercase();
+                if text.contains("reachable") {
+                    Error::DeviceNotReachable(device_id.to_string())
+                } else if text.contains("pair") || text.contains("trust") {
+                    Error::DeviceNotPaired(device_id.to_string())
+                } else {
+                    Error::DBus(err)
+                }
+            }
+            _ => Error::from(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::fdo;
+
+    fn fdo_error(err: fdo::Error) -> zbus::Error {
+        zbus::Error::FDO(Box::new(err))
+    }
+
+    #[test]
+    fn missing_daemon_is_recognized() {
+        let err = Error::from(fdo_error(fdo::Error::ServiceUnknown(
+            "The name org.kde.kdeconnect.daemon was not provided".to_string(),
+        )));
+        assert!(matches!(err, Error::DaemonNotRunning));
+
+        let err = Error::from(fdo_error(fdo::Error::NameHasNoOwner(String::new())));
+        assert!(matches!(err, Error::DaemonNotRunning));
+    }
+
+    #[test]
+    fn other_errors_stay_dbus_errors() {
+        let err = Error::from(zbus::Error::InvalidReply);
+        assert!(matches!(err, Error::DBus(zbus::Error::InvalidReply)));
+
+        let err = Error::from(fdo_error(fdo::Error::AccessDenied(String::new())));
+        assert!(matches!(err, Error::DBus(_)));
+    }
+
+    #[test]
+    fn unknown_object_depends_on_target() {
+        let unknown = || fdo_error(fdo::Error::UnknownObject(String::new()));
+
+        let err = Error::from_device_call(unknown(), "abc", None);
+        assert!(matches!(err, Error::DeviceNotFound(ref id) if id == "abc"));
+
+        let err = Error::from_device_call(unknown(), "abc", Some(Plugin::Share));
+        assert!(matches!(
+            err,
+            Error::PluginNotAvailable { ref device, ref plugin }
+                if device == "abc" && plugin == "kdeconnect_share"
+        ));
+    }
+
+    #[test]
+    fn unknown_method_on_plugin_means_plugin_missing() {
+        let err = Error::from_device_call(
+            fdo_error(fdo::Error::UnknownMethod(String::new())),
+            "abc",
+            Some(Plugin::Ping),
+        );
+        assert!(matches!(err, Error::PluginNotAvailable { .. }));
+    }
+
+    #[test]
+    fn kdeconnect_errors_are_classified() {
+        let kdeconnect_error = |name: &str, description: &str| {
+            let msg = zbus::Message::method_call("/", "test")
+                .unwrap()
+                .build(&())
+                .unwrap();
+            zbus::Error::MethodError(name.try_into().unwrap(), Some(description.to_string()), msg)
+        };
+
+        let err = Error::from_device_call(
+            kdeconnect_error("org.kde.kdeconnect.Error", "Device is not reachable"),
+            "abc",
+            Some(Plugin::Sms),
+        );
+        assert!(matches!(err, Error::DeviceNotReachable(ref id) if id == "abc"));
+
+        let err = Error::from_device_call(
+            kdeconnect_error("org.kde.kdeconnect.NotPaired", ""),
+            "abc",
+            None,
+        );
+        assert!(matches!(err, Error::DeviceNotPaired(_)));
+
+        let err = Error::from_device_call(
+            kdeconnect_error("org.kde.kdeconnect.Error", "Something else"),
+            "abc",
+            None,
+        );
+        assert!(matches!(err, Error::DBus(_)));
+    }
+
+    #[test]
+    fn missing_daemon_wins_over_device_context() {
+        let err = Error::from_device_call(
+            fdo_error(fdo::Error::ServiceUnknown(String::new())),
+            "abc",
+            Some(Plugin::Ping),
+        );
+        assert!(matches!(err, Error::DaemonNotRunning));
+    }
+}
@@ -0,0 +1,775 @@
+//! Typed stream of KDE Connect daemon signals.
+//!
+//! [`events`] subscribes to every signal the daemon emits below
+//! [`BASE_PATH`] and turns the ones clients care about into
+//! [`KdeConnectEvent`]s, so callers don't have to match interface and member
+//! names or dig device IDs out of object paths. The stream reconnects on its
+//! own when the bus connection is lost.
+
+use crate::plugins::{parse_sms_message, MediaChanges, SmsMessage};
+use crate::{BASE_PATH, SERVICE_NAME};
+use futures_util::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use zbus::zvariant::OwnedValue;
+use zbus::{Connection, MatchRule, MessageStream};
+
+/// Delay before trying again after connecting or subscribing failed.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+const DAEMON_INTERFACE: &str = "org.kde.kdeconnect.daemon";
+const DEVICE_INTERFACE: &str = "org.kde.kdeconnect.device";
+const BATTERY_INTERFACE: &str = "org.kde.kdeconnect.device.battery";
+const CONVERSATIONS_INTERFACE: &str = "org.kde.kdeconnect.device.conversations";
+const MPRIS_REMOTE_INTERFACE: &str = "org.kde.kdeconnect.device.mprisremote";
+const NOTIFICATIONS_INTERFACE: &str = "org.kde.kdeconnect.device.notifications";
+const SHARE_INTERFACE: &str = "org.kde.kdeconnect.device.share";
+const TELEPHONY_INTERFACE: &str = "org.kde.kdeconnect.device.telephony";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const DBUS_INTERFACE: &str = "org.freedesktop.DBus";
+
+/// Something that happened in the daemon or on one of its devices.
+///
+/// State changes carry the new values when the signal includes them; fields
+/// left as `None` have to be read through the device's proxies.
+#[derive(Debug, Clone)]
+pub enum KdeConnectEvent {
+    /// The stream (re)subscribed to the daemon's signals. Anything emitted
+    /// while it was disconnected was missed, so cached state should be reloaded.
+    Connected,
+    /// The daemon claimed its bus name, after being started or restarted.
+    /// Like [`Connected`](Self::Connected), state from before is stale.
+    DaemonStarted,
+    /// The daemon released its bus name, because it quit or crashed.
+    DaemonStopped,
+    /// The daemon discovered a device.
+    DeviceAdded { device_id: String },
+    /// The daemon forgot a device.
+    DeviceRemoved { device_id: String },
+    /// A device property other than reachability or pairing, such as its name
+    /// or loaded plugins, changed.
+    DeviceChanged {
+        device_id: String,
+        /// The new name, if the name is what changed.
+        name: Option<String>,
+    },
+    /// A device connected or disconnected.
+    ReachableChanged { device_id: String, reachable: bool },
+    /// A device was paired or unpaired, or a pairing request was made or answered.
+    PairStateChanged {
+        device_id: String,
+        /// Whether the device is now paired.
+        paired: Option<bool>,
+        /// Whether we are now waiting for the device to accept pairing.
+        requested: Option<bool>,
+        /// Whether the device is now asking us to pair.
+        requested_by_peer: Option<bool>,
+    },
+    /// The battery charge or charging state changed.
+    BatteryChanged {
+        device_id: String,
+        /// New charge percentage (0-100).
+        charge: Option<i32>,
+        /// Whether the device is now charging.
+        is_charging: Option<bool>,
+    },
+    /// A notification was posted on the device, or an existing one was updated.
+    NotificationPosted {
+        device_id: String,
+        notification_id: String,
+    },
+    /// A notification was dismissed on the device.
+    NotificationRemoved {
+        device_id: String,
+        notification_id: String,
+    },
+    /// All notifications were dismissed on the device.
+    NotificationsCleared { device_id: String },
+    /// The daemon reported an SMS message.
+    ///
+    /// Besides newly sent and received messages this includes messages the
+    /// phone sends back when a conversation is requested; check the message
+    /// type and date before treating it as new.
+    SmsReceived {
+        device_id: String,
+        message: SmsMessage,
+    },
+    /// The phone finished sending a requested conversation.
+    ConversationLoaded {
+        device_id: String,
+        thread_id: i64,
+        message_count: u64,
+    },
+    /// A call is ringing or was missed (`event` is e.g. `callReceived` or `missedCall`).
+    CallReceived {
+        device_id: String,
+        event: String,
+        phone_number: String,
+        contact_name: String,
+    },
+    /// A file shared from the device finished downloading.
+    ShareReceived { device_id: String, url: String },
+    /// The remote media player's state changed.
+    MediaChanged {
+        device_id: String,
+        /// The new property values; empty when the signal didn't carry any.
+        changes: MediaChanges,
+    },
+}
+
+impl KdeConnectEvent {
+    /// Device the event is about, if it is about one.
+    pub fn device_id(&self) -> Option<&str> {
+        match self {
+            KdeConnectEvent::Connected
+            | KdeConnectEvent::DaemonStarted
+            | KdeConnectEvent::DaemonStopped => None,
+            KdeConnectEvent::DeviceAdded { device_id }
+            | KdeConnectEvent::DeviceRemoved { device_id }
+            | KdeConnectEvent::DeviceChanged { device_id, .. }
+            | KdeConnectEvent::ReachableChanged { device_id, .. }
+            | KdeConnectEvent::PairStateChanged { device_id, .. }
+            | KdeConnectEvent::BatteryChanged { device_id, .. }
+            | KdeConnectEvent::NotificationPosted { device_id, .. }
+            | KdeConnectEvent::NotificationRemoved { device_id, .. }
+            | KdeConnectEvent::NotificationsCleared { device_id }
+            | KdeConnectEvent::SmsReceived { device_id, .. }
+            | KdeConnectEvent::ConversationLoaded { device_id, .. }
+            | KdeConnectEvent::CallReceived { device_id, .. }
+            | KdeConnectEvent::ShareReceived { device_id, .. }
+            | KdeConnectEvent::MediaChanged { device_id, .. } => Some(device_id),
+        }
+    }
+
+    /// Parse a daemon signal, returning `None` for messages that aren't events.
+    pub fn from_message(msg: &zbus::Message) -> Option<Self> {
+        let header = msg.header();
+        if header.message_type() != zbus::message::Type::Signal {
+            return None;
+        }
+        let interface = header.interface()?.as_str();
+        let member = header.member()?.as_str();
+        let body = msg.body();
+
+        if interface == DBUS_INTERFACE {
+            if member != "NameOwnerChanged" {
+                return None;
+            }
+            let (name, _, new_owner) = body.deserialize::<(String, String, String)>().ok()?;
+            if name != SERVICE_NAME {
+                return None;
+            }
+            return Some(if new_owner.is_empty() {
+                KdeConnectEvent::DaemonStopped
+            } else {
+                KdeConnectEvent::DaemonStarted
+            });
+        }
+
+        if interface == DAEMON_INTERFACE {
+            return match member {
+                "deviceAdded" => {
+                    let (device_id,) = body.deserialize::<(String,)>().ok()?;
+                    Some(KdeConnectEvent::DeviceAdded { device_id })
+                }
+                "deviceRemoved" => {
+                    let (device_id,) = body.deserialize::<(String,)>().ok()?;
+                    Some(KdeConnectEvent::DeviceRemoved { device_id })
+                }
+                // Emitted with the device's reachability whenever it changes
+                "deviceVisibilityChanged" => {
+                    let (device_id, reachable) = body.deserialize::<(String, bool)>().ok()?;
+                    Some(KdeConnectEvent::ReachableChanged {
+                        device_id,
+                        reachable,
+                    })
+                }
+                _ => None,
+            };
+        }
+
+        let device_id = device_id_from_path(header.path()?.as_str())?.to_string();
+        match (interface, member) {
+            (PROPERTIES_INTERFACE, "PropertiesChanged") => {
+                let (interface, changed, _) = body
+                    .deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>()
+                    .ok()?;
+                from_properties_changed(device_id, &interface, &changed)
+            }
+            (DEVICE_INTERFACE, "reachableChanged") => {
+                let (reachable,) = body.deserialize::<(bool,)>().ok()?;
+                Some(KdeConnectEvent::ReachableChanged {
+                    device_id,
+                    reachable,
+                })
+            }
+            (DEVICE_INTERFACE, "pairStateChanged") => {
+                let (state,) = body.deserialize::<(i32,)>().ok()?;
+                Some(pair_state_changed(device_id, state))
+            }
+            // Older daemons signal pairing through the trust and request signals
+            (DEVICE_INTERFACE, "trustedChanged") => {
+                let (paired,) = body.deserialize::<(bool,)>().ok()?;
+                Some(KdeConnectEvent::PairStateChanged {
+                    device_id,
+                    paired: Some(paired),
+                    requested: None,
+                    requested_by_peer: None,
+                })
+            }
+            (DEVICE_INTERFACE, "pairingRequest" | "hasPairingRequestsChanged") => {
+                Some(KdeConnectEvent::PairStateChanged {
+                    device_id,
+                    paired: None,
+                    requested: None,
+                    requested_by_peer: None,
+                })
+            }
+            (DEVICE_INTERFACE, "nameChanged") => {
+                let name = body.deserialize::<(String,)>().ok().map(|(name,)| name);
+                Some(KdeConnectEvent::DeviceChanged { device_id, name })
+            }
+            (DEVICE_INTERFACE, "pluginsChanged") => Some(KdeConnectEvent::DeviceChanged {
+                device_id,
+                name: None,
+            }),
+            (BATTERY_INTERFACE, "refreshed") => {
+                let (is_charging, charge) = body.deserialize::<(bool, i32)>().ok()?;
+                Some(KdeConnectEvent::BatteryChanged {
+                    device_id,
+                    charge: Some(charge),
+                    is_charging: Some(is_charging),
+                })
+            }
+            (BATTERY_INTERFACE, _) => Some(KdeConnectEvent::BatteryChanged {
+                device_id,
+                charge: None,
+                is_charging: None,
+            }),
+            (MPRIS_REMOTE_INTERFACE, _) => Some(KdeConnectEvent::MediaChanged {
+                device_id,
+                changes: MediaChanges::default(),
+            }),
+            (NOTIFICATIONS_INTERFACE, "notificationPosted" | "notificationUpdated") => {
+                let (notification_id,) = body.deserialize::<(String,)>().ok()?;
+                Some(KdeConnectEvent::NotificationPosted {
+                    device_id,
+                    notification_id,
+                })
+            }
+            (NOTIFICATIONS_INTERFACE, "notificationRemoved") => {
+                let (notification_id,) = body.deserialize::<(String,)>().ok()?;
+                Some(KdeConnectEvent::NotificationRemoved {
+                    device_id,
+                    notification_id,
+                })
+            }
+            (NOTIFICATIONS_INTERFACE, "allNotificationsRemoved") => {
+                Some(KdeConnectEvent::NotificationsCleared { device_id })
+            }
+            // The first message of a new thread arrives as conversationCreated
+            (CONVERSATIONS_INTERFACE, "conversationCreated" | "conversationUpdated") => {
+                let value = body.deserialize::<OwnedValue>().ok()?;
+                let message = parse_sms_message(&value)?;
+                Some(KdeConnectEvent::SmsReceived { device_id, message })
+            }
+            (CONVERSATIONS_INTERFACE, "conversationLoaded") => {
+                let (thread_id, message_count) = body.deserialize::<(i64, u64)>().ok()?;
+                Some(KdeConnectEvent::ConversationLoaded {
+                    device_id,
+                    thread_id,
+                    message_count,
+                })
+            }
+            (TELEPHONY_INTERFACE, "callReceived") => {
+                let (event, phone_number, contact_name) =
+                    body.deserialize::<(String, String, String)>().ok()?;
+                Some(KdeConnectEvent::CallReceived {
+                    device_id,
+                    event,
+                    phone_number,
+                    contact_name,
+                })
+            }
+            (SHARE_INTERFACE, "shareReceived") => {
+                let (url,) = body.deserialize::<(String,)>().ok()?;
+                Some(KdeConnectEvent::ShareReceived { device_id, url })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Map a `PropertiesChanged` signal from a device or plugin object to an event.
+fn from_properties_changed(
+    device_id: String,
+    interface: &str,
+    changed: &HashMap<String, OwnedValue>,
+) -> Option<KdeConnectEvent> {
+    match interface {
+        DEVICE_INTERFACE => {
+            if let Some(reachable) = changed
+                .get("isReachable")
+                .and_then(|v| bool::try_from(v).ok())
+            {
+                Some(KdeConnectEvent::ReachableChanged {
+                    device_id,
+                    reachable,
+                })
+            } else if let Some(state) = changed.get("pairState").and_then(|v| i32::try_from(v).ok())
+            {
+                Some(pair_state_changed(device_id, state))
+            } else if changed.keys().any(|k| k.starts_with("isPair")) {
+                let flag = |key: &str| changed.get(key).and_then(|v| bool::try_from(v).ok());
+                Some(KdeConnectEvent::PairStateChanged {
+                    device_id,
+                    paired: flag("isPaired"),
+                    requested: flag("isPairRequested"),
+                    requested_by_peer: flag("isPairRequestedByPeer"),
+                })
+            } else {
+                let name = changed
+                    .get("name")
+                    .and_then(|v| String::try_from(v.clone()).ok());
+                Some(KdeConnectEvent::DeviceChanged { device_id, name })
+            }
+        }
+        BATTERY_INTERFACE => Some(KdeConnectEvent::BatteryChanged {
+            device_id,
+            charge: changed.get("charge").and_then(|v| i32::try_from(v).ok()),
+            is_charging: changed
+                .get("isCharging")
+                .and_then(|v| bool::try_from(v).ok()),
+        }),
+        MPRIS_REMOTE_INTERFACE => Some(KdeConnectEvent::MediaChanged {
+            device_id,
+            changes: MediaChanges::from_properties(changed),
+        }),
+        _ => None,
+    }
+}
+
+/// Event for KDE Connect's `PairState` value.
+///
+/// The daemon numbers the states `NotPaired`, `Requested`, `RequestedByPeer`
+/// and `Paired`; unknown values leave the flags for the caller to read.
+fn pair_state_changed(device_id: String, state: i32) -> KdeConnectEvent {
+    let known = (0..=3).contains(&state);
+    let flag = |value| known.then_some(state == value);
+    KdeConnectEvent::PairStateChanged {
+        device_id,
+        paired: flag(3),
+        requested: flag(1),
+        requested_by_peer: flag(2),
+    }
+}
+
+/// Extract the device ID from a device or plugin object path.
+///
+/// Path format: /modules/kdeconnect/devices/{device_id}[/{plugin}]
+pub fn device_id_from_path(path: &str) -> Option<&str> {
+    let rest = path.strip_prefix(BASE_PATH)?.strip_prefix("/devices/")?;
+    rest.split('/').next().filter(|id| !id.is_empty())
+}
+
+/// Stream of events from the daemon on the session bus.
+///
+/// See [`events_with`].
+pub fn events() -> impl Stream<Item = KdeConnectEvent> {
+    events_with(Connection::session)
+}
+
+/// Stream of events from the daemon, using `connect` to open the bus connection.
+///
+/// Besides the daemon's own signals the stream watches the daemon's bus name,
+/// yielding [`KdeConnectEvent::DaemonStopped`] and
+/// [`KdeConnectEvent::DaemonStarted`] as it goes away and comes back.
+///
+/// The stream starts with [`KdeConnectEvent::Connected`] once it is
+/// subscribed, so requests whose replies arrive as signals can be sent from
+/// then on without missing any. When the connection is lost it calls
+/// `connect` again, retrying until that succeeds, and yields another
+/// `Connected`. The stream never ends.
+pub fn events_with<F, Fut>(connect: F) -> impl Stream<Item = KdeConnectEvent>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = zbus::Result<Connection>>,
+{
+    futures_util::stream::unfold(
+        (connect, None::<Signals>),
+        |(mut connect, stream)| async move {
+            let Some(mut stream) = stream else {
+                let stream = subscribe(&mut connect).await;
+                return Some((KdeConnectEvent::Connected, (connect, Some(stream))));
+            };
+            loop {
+                match stream.next().await {
+                    Some(Ok(msg)) => {
+                        if let Some(event) = KdeConnectEvent::from_message(&msg) {
+                            return Some((event, (connect, Some(stream))));
+                        }
+                    }
+                    Some(Err(e)) => {
+                        tracing::warn!("D-Bus signal stream error: {}", e);
+                    }
+                    None => {
+                        tracing::warn!("D-Bus signal stream ended, reconnecting...");
+                        let stream = subscribe(&mut connect).await;
+                        return Some((KdeConnectEvent::Connected, (connect, Some(stream))));
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// The daemon's signals merged with changes of its bus name's owner.
+type Signals = futures_util::stream::Select<MessageStream, MessageStream>;
+
+/// Connect and subscribe to the daemon's signals, retrying until both succeed.
+async fn subscribe<F, Fut>(connect: &mut F) -> Signals
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = zbus::Result<Connection>>,
+{
+    loop {
+        match try_subscribe(connect().await).await {
+            Ok(stream) => {
+                tracing::debug!("Subscribed to KDE Connect signals");
+                return stream;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to subscribe to KDE Connect signals: {}", e);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+async fn try_subscribe(conn: zbus::Result<Connection>) -> zbus::Result<Signals> {
+    let conn = conn?;
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(SERVICE_NAME)?
+        .path_namespace(BASE_PATH)?
+        .build();
+    let signals = MessageStream::for_match_rule(rule, &conn, None).await?;
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface(DBUS_INTERFACE)?
+        .member("NameOwnerChanged")?
+        .arg(0, SERVICE_NAME)?
+        .build();
+    let owner_changes = MessageStream::for_match_rule(rule, &conn, None).await?;
+    Ok(futures_util::stream::select(signals, owner_changes))
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::mock::{MockDaemon, MockDevice, MockMedia, MockNotification, MockSms};
+    use crate::plugins::MessageType;
+
+    const DEVICE: &str = "abc123";
+
+    fn mock_events(mock: &MockDaemon) -> impl Stream<Item = KdeConnectEvent> + Unpin {
+        let conn = mock.connection().clone();
+        Box::pin(events_with(move || std::future::ready(Ok(conn.clone()))))
+    }
+
+    /// Wait for the first event on the stream that `pick` accepts.
+    async fn next_matching<T>(
+        events: &mut (impl Stream<Item = KdeConnectEvent> + Unpin),
+        pick: impl Fn(KdeConnectEvent) -> Option<T>,
+    ) -> T {
+        let wait = async {
+            loop {
+                let event = events.next().await.expect("event stream ended");
+                if let Some(picked) = pick(event) {
+                    return picked;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .expect("timed out waiting for event")
+    }
+
+    /// Start a mock with one device and an event stream already past `Connected`.
+    async fn subscribed_mock() -> (MockDaemon, impl Stream<Item = KdeConnectEvent> + Unpin) {
+        let mock = MockDaemon::start().await.unwrap();
+        let mut events = mock_events(&mock);
+        assert!(matches!(
+            events.next().await,
+            Some(KdeConnectEvent::Connected)
+        ));
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        (mock, events)
+    }
+
+    #[test]
+    fn device_id_from_device_and_plugin_paths() {
+        assert_eq!(
+            device_id_from_path("/modules/kdeconnect/devices/abc123"),
+            Some("abc123")
+        );
+        assert_eq!(
+            device_id_from_path("/modules/kdeconnect/devices/abc123/telephony"),
+            Some("abc123")
+        );
+        assert_eq!(device_id_from_path("/modules/kdeconnect"), None);
+        assert_eq!(device_id_from_path("/modules/kdeconnect/devices/"), None);
+    }
+
+    #[test]
+    fn pair_state_values_set_all_flags() {
+        let flags = |state| match pair_state_changed(DEVICE.to_string(), state) {
+            KdeConnectEvent::PairStateChanged {
+                paired,
+                requested,
+                requested_by_peer,
+                ..
+            } => (paired, requested, requested_by_peer),
+            other => panic!("unexpected event: {:?}", other),
+        };
+        assert_eq!(flags(0), (Some(false), Some(false), Some(false)));
+        assert_eq!(flags(1), (Some(false), Some(true), Some(false)));
+        assert_eq!(flags(3), (Some(true), Some(false), Some(false)));
+        assert_eq!(flags(7), (None, None, None));
+    }
+
+    #[tokio::test]
+    async fn device_lifecycle_events() {
+        let (mock, mut events) = subscribed_mock().await;
+
+        let added = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::DeviceAdded { device_id } => Some(device_id),
+            _ => None,
+        })
+        .await;
+        assert_eq!(added, DEVICE);
+
+        mock.set_reachable(DEVICE, false).await.unwrap();
+        let reachable = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::ReachableChanged { reachable, .. } => Some(reachable),
+            _ => None,
+        })
+        .await;
+        assert!(!reachable);
+
+        mock.request_pairing_from_peer(DEVICE).await.unwrap();
+        let pair_state = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::PairStateChanged {
+                paired,
+                requested,
+                requested_by_peer,
+                ..
+            } => Some((paired, requested, requested_by_peer)),
+            _ => None,
+        })
+        .await;
+        assert_eq!(pair_state, (Some(false), Some(false), Some(true)));
+
+        mock.remove_device(DEVICE).await.unwrap();
+        let removed = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::DeviceRemoved { device_id } => Some(device_id),
+            _ => None,
+        })
+        .await;
+        assert_eq!(removed, DEVICE);
+    }
+
+    #[tokio::test]
+    async fn plugin_state_events() {
+        let (mock, mut events) = subscribed_mock().await;
+
+        mock.set_battery(DEVICE, 15, false).await.unwrap();
+        let charge = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::BatteryChanged { charge, .. } => charge,
+            _ => None,
+        })
+        .await;
+        assert_eq!(charge, 15);
+        let is_charging = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::BatteryChanged { is_charging, .. } => is_charging,
+            _ => None,
+        })
+        .await;
+        assert!(!is_charging);
+
+        let media = MockMedia {
+            player_list: vec!["Spotify".to_string()],
+            title: "Song".to_string(),
+            is_playing: true,
+            ..Default::default()
+        };
+        mock.set_media(DEVICE, media).await.unwrap();
+        let player_list = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::MediaChanged { changes, .. } => changes.player_list,
+            _ => None,
+        })
+        .await;
+        assert_eq!(player_list, vec!["Spotify"]);
+        let is_playing = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::MediaChanged { changes, .. } => changes.is_playing,
+            _ => None,
+        })
+        .await;
+        assert!(is_playing);
+        let title = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::MediaChanged { changes, .. } => changes.title,
+            _ => None,
+        })
+        .await;
+        assert_eq!(title, "Song");
+    }
+
+    #[tokio::test]
+    async fn notification_events() {
+        let (mock, mut events) = subscribed_mock().await;
+
+        mock.post_notification(DEVICE, MockNotification::new("n1", "Chat", "Bob", "hi"))
+            .await
+            .unwrap();
+        let posted = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::NotificationPosted {
+                notification_id, ..
+            } => Some(notification_id),
+            _ => None,
+        })
+        .await;
+        assert_eq!(posted, "n1");
+
+        mock.remove_notification(DEVICE, "n1").await.unwrap();
+        let removed = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::NotificationRemoved {
+                notification_id, ..
+            } => Some(notification_id),
+            _ => None,
+        })
+        .await;
+        assert_eq!(removed, "n1");
+    }
+
+    #[tokio::test]
+    async fn sms_events() {
+        let (mock, mut events) = subscribed_mock().await;
+
+        mock.receive_sms(DEVICE, MockSms::inbox(5, 1, "+15550005", "hello", 1_000))
+            .await
+            .unwrap();
+        let (device_id, message) = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::SmsReceived { device_id, message } => Some((device_id, message)),
+            _ => None,
+        })
+        .await;
+        assert_eq!(device_id, DEVICE);
+        assert_eq!(message.thread_id, 5);
+        assert_eq!(message.body, "hello");
+        assert_eq!(message.message_type, MessageType::Inbox);
+
+        mock.emit_conversation_loaded(DEVICE, 5, 42).await.unwrap();
+        let loaded = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::ConversationLoaded {
+                thread_id,
+                message_count,
+                ..
+            } => Some((thread_id, message_count)),
+            _ => None,
+        })
+        .await;
+        assert_eq!(loaded, (5, 42));
+    }
+
+    #[tokio::test]
+    async fn call_and_share_events() {
+        let (mock, mut events) = subscribed_mock().await;
+
+        mock.emit_call_received(DEVICE, "missedCall", "+15551234567", "Alice")
+            .await
+            .unwrap();
+        let call = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::CallReceived {
+                device_id,
+                event,
+                phone_number,
+                contact_name,
+            } => Some((device_id, event, phone_number, contact_name)),
+            _ => None,
+        })
+        .await;
+        assert_eq!(
+            call,
+            (
+                DEVICE.to_string(),
+                "missedCall".to_string(),
+                "+15551234567".to_string(),
+                "Alice".to_string()
+            )
+        );
+
+        mock.emit_share_received(DEVICE, "file:///home/user/Downloads/photo.jpg")
+            .await
+            .unwrap();
+        let url = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::ShareReceived { url, .. } => Some(url),
+            _ => None,
+        })
+        .await;
+        assert_eq!(url, "file:///home/user/Downloads/photo.jpg");
+    }
+
+    #[tokio::test]
+    async fn daemon_restart_events() {
+        let (mock, mut events) = subscribed_mock().await;
+
+        mock.set_running(false).await.unwrap();
+        next_matching(&mut events, |e| {
+            matches!(e, KdeConnectEvent::DaemonStopped).then_some(())
+        })
+        .await;
+
+        mock.set_running(true).await.unwrap();
+        next_matching(&mut events, |e| {
+            matches!(e, KdeConnectEvent::DaemonStarted).then_some(())
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn reconnects_when_connection_is_lost() {
+        let first = MockDaemon::start().await.unwrap();
+        let second = MockDaemon::start().await.unwrap();
+        let connections = std::sync::Mutex::new(vec![
+            second.connection().clone(),
+            first.connection().clone(),
+        ]);
+        let mut events = Box::pin(events_with(move || {
+            std::future::ready(Ok(connections.lock().unwrap().pop().unwrap()))
+        }));
+
+        assert!(matches!(
+            events.next().await,
+            Some(KdeConnectEvent::Connected)
+        ));
+        drop(first);
+        next_matching(&mut events, |e| {
+            matches!(e, KdeConnectEvent::Connected).then_some(())
+        })
+        .await;
+
+        second
+            .add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        let added = next_matching(&mut events, |e| match e {
+            KdeConnectEvent::DeviceAdded { device_id } => Some(device_id),
+            _ => None,
+        })
+        .await;
+        assert_eq!(added, DEVICE);
+    }
+}
@@ -21,17 +21,23 @@
 //! }
 //! ```
 
+pub mod client;
 pub mod contacts;
 pub mod daemon;
 pub mod device;
+pub mod events;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod plugins;
 
 mod error;
 
+pub use client::{DeviceHandle, KdeConnectClient, Plugin};
 pub use contacts::{normalize_phone_number, phone_suffix, Contact, ContactLookup};
-pub use daemon::DaemonProxy;
+pub use daemon::{DaemonExit, DaemonProxy, DaemonStatus};
 pub use device::DeviceProxy;
 pub use error::{Error, Result};
+pub use events::{events, KdeConnectEvent};
 
 /// KDE Connect D-Bus service name
 pub const SERVICE_NAME: &str = "org.kde.kdeconnect.daemon";
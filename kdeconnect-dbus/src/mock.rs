@@ -0,0 +1,2271 @@
+//! In-process fake of the KDE Connect daemon for tests.
+//!
+//! Available with the `mock` feature. [`MockDaemon`] serves the daemon, device
+//! and plugin interfaces over a private peer-to-peer D-Bus connection, so the
+//! proxies in this crate (and code built on top of them) can be exercised
+//! without a running `kdeconnectd` or a phone.
+//!
+//! Tests script the fake by adding devices, seeding SMS threads, changing
+//! properties and emitting signals. Method calls made by the code under test
+//! are recorded and can be inspected with [`MockDaemon::calls`].
+//!
+//! ```
+//! use kdeconnect_dbus::mock::{MockDaemon, MockDevice};
+//! use kdeconnect_dbus::DaemonProxy;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> zbus::Result<()> {
+//! let mock = MockDaemon::start().await?;
+//! mock.add_device(MockDevice::new("abc123", "Pixel")).await?;
+//!
+//! let daemon = DaemonProxy::new(mock.connection()).await?;
+//! assert_eq!(daemon.devices().await?, vec!["abc123".to_string()]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{BASE_PATH, SERVICE_NAME};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use zbus::object_server::{Interface, ObjectServer, SignalEmitter};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{connection, interface, Connection, Guid};
+
+/// Plugin IDs served for a [`MockDevice`] created with [`MockDevice::new`].
+pub const DEFAULT_PLUGINS: &[&str] = &[
+    "kdeconnect_battery",
+    "kdeconnect_clipboard",
+    "kdeconnect_findmyphone",
+    "kdeconnect_mprisremote",
+    "kdeconnect_notifications",
+    "kdeconnect_ping",
+    "kdeconnect_share",
+    "kdeconnect_sms",
+    "kdeconnect_telephony",
+];
+
+/// Device ID returned by the fake daemon's `selfId`.
+pub const MOCK_SELF_ID: &str = "mock_self";
+
+/// A method call received by the fake daemon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockCall {
+    /// Device the call was made on (empty for daemon-level calls).
+    pub device_id: String,
+    /// Last component of the D-Bus interface name (e.g. `ping`, `conversations`).
+    pub plugin: String,
+    /// D-Bus member name (e.g. `sendPing`).
+    pub method: String,
+    /// Call arguments rendered as strings.
+    pub args: Vec<String>,
+}
+
+/// Initial state of a fake device.
+#[derive(Debug, Clone)]
+pub struct MockDevice {
+    pub id: String,
+    pub name: String,
+    pub device_type: String,
+    pub is_reachable: bool,
+    pub is_paired: bool,
+    pub is_pair_requested: bool,
+    pub is_pair_requested_by_peer: bool,
+    /// Plugin IDs to serve; objects are only registered for plugins listed here.
+    pub plugins: Vec<String>,
+}
+
+impl MockDevice {
+    /// A reachable, paired phone with all [`DEFAULT_PLUGINS`] enabled.
+    pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            device_type: "phone".to_string(),
+            is_reachable: true,
+            is_paired: true,
+            is_pair_requested: false,
+            is_pair_requested_by_peer: false,
+            plugins: DEFAULT_PLUGINS.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn has_plugin(&self, plugin: &str) -> bool {
+        self.plugins.iter().any(|p| p == plugin)
+    }
+}
+
+/// An SMS message as the daemon serializes it on the conversations interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockSms {
+    pub body: String,
+    pub addresses: Vec<String>,
+    pub date: i64,
+    /// Android message type (1 = inbox, 2 = sent, 5 = failed, ...).
+    pub message_type: i32,
+    pub read: bool,
+    pub thread_id: i64,
+    pub uid: i32,
+    pub sub_id: i64,
+    /// Attachments as `(part_id, mime_type, base64 thumbnail, unique_identifier)`.
+    pub attachments: Vec<(i64, String, String, String)>,
+}
+
+impl MockSms {
+    /// A read message received from `address`.
+    pub fn inbox(thread_id: i64, uid: i32, address: &str, body: &str, date: i64) -> Self {
+        Self {
+            body: body.to_string(),
+            addresses: vec![address.to_string()],
+            date,
+            message_type: 1,
+            read: true,
+            thread_id,
+            uid,
+            sub_id: -1,
+            attachments: Vec::new(),
+        }
+    }
+
+    /// A message sent by the user to `address`.
+    pub fn sent(thread_id: i64, uid: i32, address: &str, body: &str, date: i64) -> Self {
+        Self {
+            message_type: 2,
+            ..Self::inbox(thread_id, uid, address, body, date)
+        }
+    }
+
+    /// A message to `address` that the phone failed to send.
+    pub fn failed(thread_id: i64, uid: i32, address: &str, body: &str, date: i64) -> Self {
+        Self {
+            message_type: 5,
+            ..Self::inbox(thread_id, uid, address, body, date)
+        }
+    }
+
+    /// Add an MMS attachment with a base64 encoded thumbnail.
+    pub fn with_attachment(
+        mut self,
+        part_id: i64,
+        mime_type: &str,
+        thumbnail: &str,
+        unique_identifier: &str,
+    ) -> Self {
+        self.attachments.push((
+            part_id,
+            mime_type.to_string(),
+            thumbnail.to_string(),
+            unique_identifier.to_string(),
+        ));
+        self
+    }
+
+    /// Serialize to the struct layout used by KDE Connect's `ConversationMessage`.
+    pub fn to_value(&self) -> OwnedValue {
+        let addresses: Vec<(String,)> = self.addresses.iter().map(|a| (a.clone(),)).collect();
+        let attachments = self.attachments.clone();
+        let value = Value::from((
+            1i32, // eventField: EventTextMessage
+            self.body.clone(),
+            addresses,
+            self.date,
+            self.message_type,
+            i32::from(self.read),
+            self.thread_id,
+            self.uid,
+            self.sub_id,
+            attachments,
+        ));
+        OwnedValue::try_from(value).expect("SMS value contains no file descriptors")
+    }
+}
+
+/// State of a fake remote media player.
+#[derive(Debug, Clone, Default)]
+pub struct MockMedia {
+    pub player_list: Vec<String>,
+    pub player: String,
+    pub is_playing: bool,
+    pub volume: i32,
+    pub length: i32,
+    pub position: i32,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub can_seek: bool,
+    pub can_go_next: bool,
+    pub can_go_previous: bool,
+    pub can_play: bool,
+    pub can_pause: bool,
+    pub loop_status: String,
+    pub shuffle: bool,
+    pub album_art_url: String,
+}
+
+/// A notification posted on the fake phone.
+#[derive(Debug, Clone, Default)]
+pub struct MockNotification {
+    pub id: String,
+    pub app_name: String,
+    pub ticker: String,
+    pub title: String,
+    pub text: String,
+    pub icon_path: String,
+    pub dismissable: bool,
+    pub has_icon: bool,
+    pub silent: bool,
+    /// Non-empty when the notification can be replied to.
+    pub reply_id: String,
+}
+
+impl MockNotification {
+    /// A dismissable notification without a reply action.
+    pub fn new(id: &str, app_name: &str, title: &str, text: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            app_name: app_name.to_string(),
+            ticker: format!("{}: {}", title, text),
+            title: title.to_string(),
+            text: text.to_string(),
+            dismissable: true,
+            ..Default::default()
+        }
+    }
+}
+
+type CallLog = Arc<Mutex<Vec<MockCall>>>;
+
+fn record(log: &CallLog, device_id: &str, plugin: &str, method: &str, args: Vec<String>) {
+    log.lock().expect("call log poisoned").push(MockCall {
+        device_id: device_id.to_string(),
+        plugin: plugin.to_string(),
+        method: method.to_string(),
+        args,
+    });
+}
+
+/// Render an `a(s)` address list sent by a client as plain strings.
+fn addresses_to_strings(addresses: &[OwnedValue]) -> Vec<String> {
+    addresses
+        .iter()
+        .filter_map(|v| match &**v {
+            Value::Structure(s) => match s.fields().first() {
+                Some(Value::Str(s)) => Some(s.to_string()),
+                _ => None,
+            },
+            Value::Str(s) => Some(s.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn device_path(device_id: &str) -> String {
+    format!("{}/devices/{}", BASE_PATH, device_id)
+}
+
+fn plugin_path(device_id: &str, plugin: &str) -> String {
+    format!("{}/devices/{}/{}", BASE_PATH, device_id, plugin)
+}
+
+/// Unique bus name the fake daemon reports in `NameOwnerChanged`.
+const DAEMON_UNIQUE_NAME: &str = ":1.42";
+
+/// Scriptable fake `kdeconnectd`.
+///
+/// Dropping the mock closes the server side of the connection.
+pub struct MockDaemon {
+    client: Connection,
+    server: Connection,
+    calls: CallLog,
+}
+
+impl MockDaemon {
+    /// Start a fake daemon with no devices.
+    pub async fn start() -> zbus::Result<Self> {
+        let (server_stream, client_stream) = tokio::net::UnixStream::pair()?;
+        let calls = CallLog::default();
+
+        let guid = Guid::generate();
+        let server = connection::Builder::unix_stream(server_stream)
+            .server(guid)?
+            .p2p()
+            .serve_at(
+                "/org/freedesktop/DBus",
+                BusIface {
+                    running: true,
+                    installed: true,
+                    calls: calls.clone(),
+                },
+            )?
+            .serve_at(
+                SYSTEMD_PATH,
+                SystemdIface {
+                    calls: calls.clone(),
+                },
+            )?
+            .serve_at(SYSTEMD_UNIT_PATH, UnitIface { active_state: None })?
+            .serve_at(
+                BASE_PATH,
+                DaemonIface {
+                    devices: Vec::new(),
+                    calls: calls.clone(),
+                },
+            )?
+            .build();
+        let client = connection::Builder::unix_stream(client_stream)
+            .p2p()
+            .build();
+        let (server, client) = tokio::try_join!(server, client)?;
+
+        Ok(Self {
+            client,
+            server,
+            calls,
+        })
+    }
+
+    /// Client connection to hand to the code under test.
+    pub fn connection(&self) -> &Connection {
+        &self.client
+    }
+
+    /// All method calls received so far, oldest first.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.calls.lock().expect("call log poisoned").clone()
+    }
+
+    /// Method calls received for one plugin (e.g. `"ping"`).
+    pub fn calls_to(&self, plugin: &str) -> Vec<MockCall> {
+        self.calls()
+            .into_iter()
+            .filter(|c| c.plugin == plugin)
+            .collect()
+    }
+
+    /// Register a device and its plugin objects, then emit `deviceAdded`.
+    pub async fn add_device(&self, device: MockDevice) -> zbus::Result<()> {
+        let server = self.server.object_server();
+        let id = device.id.clone();
+        let path = device_path(&id);
+        let calls = &self.calls;
+
+        if device.has_plugin("kdeconnect_sms") {
+            server
+                .at(
+                    path.as_str(),
+                    ConversationsIface {
+                        device_id: id.clone(),
+                        messages: Vec::new(),
+                        calls: calls.clone(),
+                    },
+                )
+                .await?;
+            server
+                .at(
+                    plugin_path(&id, "sms"),
+                    SmsIface {
+                        device_id: id.clone(),
+                        calls: calls.clone(),
+                    },
+                )
+                .await?;
+        }
+        if device.has_plugin("kdeconnect_battery") {
+            server
+                .at(
+                    plugin_path(&id, "battery"),
+                    BatteryIface {
+                        charge: 100,
+                        is_charging: false,
+                    },
+                )
+                .await?;
+        }
+        if device.has_plugin("kdeconnect_ping") {
+            server
+                .at(
+                    plugin_path(&id, "ping"),
+                    PingIface {
+                        device_id: id.clone(),
+                        calls: calls.clone(),
+                    },
+                )
+                .await?;
+        }
+        if device.has_plugin("kdeconnect_findmyphone") {
+            server
+                .at(
+                    plugin_path(&id, "findmyphone"),
+                    FindMyPhoneIface {
+                        device_id: id.clone(),
+                        calls: calls.clone(),
+                    },
+                )
+                .await?;
+        }
+        if device.has_plugin("kdeconnect_clipboard") {
+            server
+                .at(
+                    plugin_path(&id, "clipboard"),
+                    ClipboardIface {
+                        device_id: id.clone(),
+                        calls: calls.clone(),
+                    },
+                )
+                .await?;
+        }
+        if device.has_plugin("kdeconnect_share") {
+            server
+                .at(
+                    plugin_path(&id, "share"),
+                    ShareIface {
+                        device_id: id.clone(),
+                        calls: calls.clone(),
+                    },
+                )
+                .await?;
+        }
+        if device.has_plugin("kdeconnect_telephony") {
+            server
+                .at(plugin_path(&id, "telephony"), TelephonyIface)
+                .await?;
+        }
+        if device.has_plugin("kdeconnect_mprisremote") {
+            server
+                .at(
+                    plugin_path(&id, "mprisremote"),
+                    MprisRemoteIface {
+                        device_id: id.clone(),
+                        media: MockMedia::default(),
+                        calls: calls.clone(),
+                    },
+                )
+                .await?;
+        }
+        if device.has_plugin("kdeconnect_notifications") {
+            server
+                .at(
+                    plugin_path(&id, "notifications"),
+                    NotificationsIface {
+                        device_id: id.clone(),
+                        active: Vec::new(),
+                        calls: calls.clone(),
+                    },
+                )
+                .await?;
+        }
+        server
+            .at(
+                path.as_str(),
+                DeviceIface {
+                    device,
+                    calls: calls.clone(),
+                },
+            )
+            .await?;
+
+        let daemon = server.interface::<_, DaemonIface>(BASE_PATH).await?;
+        daemon.get_mut().await.devices.push(id.clone());
+        DaemonIface::device_added(daemon.signal_emitter(), &id).await
+    }
+
+    /// Unregister a device and its plugin objects, then emit `deviceRemoved`.
+    pub async fn remove_device(&self, device_id: &str) -> zbus::Result<()> {
+        let server = self.server.object_server();
+        let path = device_path(device_id);
+
+        let notifications = plugin_path(device_id, "notifications");
+        if let Ok(iface) = server
+            .interface::<_, NotificationsIface>(notifications.as_str())
+            .await
+        {
+            let ids = iface.get().await.active.clone();
+            for id in ids {
+                server
+                    .remove::<NotificationIface, _>(format!("{}/{}", notifications, id))
+                    .await?;
+            }
+        }
+        let objects = [
+            (notifications, <NotificationsIface as Interface>::name()),
+            (
+                plugin_path(device_id, "sms"),
+                <SmsIface as Interface>::name(),
+            ),
+            (
+                plugin_path(device_id, "battery"),
+                <BatteryIface as Interface>::name(),
+            ),
+            (
+                plugin_path(device_id, "ping"),
+                <PingIface as Interface>::name(),
+            ),
+            (
+                plugin_path(device_id, "findmyphone"),
+                <FindMyPhoneIface as Interface>::name(),
+            ),
+            (
+                plugin_path(device_id, "clipboard"),
+                <ClipboardIface as Interface>::name(),
+            ),
+            (
+                plugin_path(device_id, "share"),
+                <ShareIface as Interface>::name(),
+            ),
+            (
+                plugin_path(device_id, "telephony"),
+                <TelephonyIface as Interface>::name(),
+            ),
+            (
+                plugin_path(device_id, "mprisremote"),
+                <MprisRemoteIface as Interface>::name(),
+            ),
+            (path.clone(), <ConversationsIface as Interface>::name()),
+            (path, <DeviceIface as Interface>::name()),
+        ];
+        for (path, interface) in objects {
+            // Plugins that weren't enabled for the device were never registered
+            match server.remove_named(path.as_str(), interface).await {
+                Ok(_) | Err(zbus::Error::InterfaceNotFound) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let daemon = server.interface::<_, DaemonIface>(BASE_PATH).await?;
+        daemon.get_mut().await.devices.retain(|d| d != device_id);
+        DaemonIface::device_removed(daemon.signal_emitter(), device_id).await
+    }
+
+    /// Simulate the daemon quitting or coming back, emitting `NameOwnerChanged`.
+    ///
+    /// Only the bus name changes hands; devices and plugin objects stay
+    /// registered, so calls made while "stopped" still succeed.
+    pub async fn set_running(&self, running: bool) -> zbus::Result<()> {
+        let iface = self
+            .server
+            .object_server()
+            .interface::<_, BusIface>("/org/freedesktop/DBus")
+            .await?;
+        let mut bus = iface.get_mut().await;
+        bus.set_running(iface.signal_emitter(), running).await
+    }
+
+    /// Control whether the bus can activate the daemon (i.e. whether it is installed).
+    pub async fn set_installed(&self, installed: bool) -> zbus::Result<()> {
+        let iface = self
+            .server
+            .object_server()
+            .interface::<_, BusIface>("/org/freedesktop/DBus")
+            .await?;
+        iface.get_mut().await.installed = installed;
+        Ok(())
+    }
+
+    /// Control the daemon's systemd user unit: `None` when there is no unit
+    /// file, otherwise its `ActiveState` (e.g. `"inactive"` or `"failed"`).
+    pub async fn set_unit(&self, active_state: Option<&str>) -> zbus::Result<()> {
+        let iface = self
+            .server
+            .object_server()
+            .interface::<_, UnitIface>(SYSTEMD_UNIT_PATH)
+            .await?;
+        iface.get_mut().await.active_state = active_state.map(str::to_string);
+        Ok(())
+    }
+
+    /// Change a device's reachability, emitting `reachableChanged` and `PropertiesChanged`.
+    pub async fn set_reachable(&self, device_id: &str, reachable: bool) -> zbus::Result<()> {
+        let iface = self
+            .server
+            .object_server()
+            .interface::<_, DeviceIface>(device_path(device_id))
+            .await?;
+        iface.get_mut().await.device.is_reachable = reachable;
+        let emitter = iface.signal_emitter();
+        DeviceIface::reachable_changed(emitter, reachable).await?;
+        let device = iface.get().await;
+        device.is_reachable_changed(emitter).await
+    }
+
+    /// Simulate the phone asking to pair with us.
+    pub async fn request_pairing_from_peer(&self, device_id: &str) -> zbus::Result<()> {
+        let iface = self
+            .server
+            .object_server()
+            .interface::<_, DeviceIface>(device_path(device_id))
+            .await?;
+        let mut device = iface.get_mut().await;
+        device.device.is_paired = false;
+        device.device.is_pair_requested_by_peer = true;
+        device.emit_pair_state(iface.signal_emitter()).await
+    }
+
+    /// Update the battery plugin's properties and emit `PropertiesChanged`.
+    pub async fn set_battery(
+        &self,
+        device_id: &str,
+        charge: i32,
+        is_charging: bool,
+    ) -> zbus::Result<()> {
+        let iface = self
+            .server
+            .object_server()
+            .interface::<_, BatteryIface>(plugin_path(device_id, "battery"))
+            .await?;
+        let mut battery = iface.get_mut().await;
+        battery.charge = charge;
+        battery.is_charging = is_charging;
+        battery.charge_changed(iface.signal_emitter()).await?;
+        battery.is_charging_changed(iface.signal_emitter()).await
+    }
+
+    /// Replace the remote media player state and emit `PropertiesChanged`.
+    pub async fn set_media(&self, device_id: &str, media: MockMedia) -> zbus::Result<()> {
+        let iface = self
+            .server
+            .object_server()
+            .interface::<_, MprisRemoteIface>(plugin_path(device_id, "mprisremote"))
+            .await?;
+        let mut mpris = iface.get_mut().await;
+        mpris.media = media;
+        mpris.emit_all_changed(iface.signal_emitter()).await
+    }
+
+    /// Current remote media player state, including changes made by clients.
+    pub async fn media(&self, device_id: &str) -> zbus::Result<MockMedia> {
+        let iface = self
+            .server
+            .object_server()
+            .interface::<_, MprisRemoteIface>(plugin_path(device_id, "mprisremote"))
+            .await?;
+        let media = iface.get().await.media.clone();
+        Ok(media)
+    }
+
+    /// Post a notification on the phone and emit `notificationPosted`.
+    pub async fn post_notification(
+        &self,
+        device_id: &str,
+        notification: MockNotification,
+    ) -> zbus::Result<()> {
+        let server = self.server.object_server();
+        let base = plugin_path(device_id, "notifications");
+        let id = notification.id.clone();
+        server
+            .at(
+                format!("{}/{}", base, id),
+                NotificationIface {
+                    device_id: device_id.to_string(),
+                    notification,
+                    calls: self.calls.clone(),
+                },
+            )
+            .await?;
+
+        let iface = server
+            .interface::<_, NotificationsIface>(base.as_str())
+            .await?;
+        iface.get_mut().await.active.push(id.clone());
+        NotificationsIface::notification_posted(iface.signal_emitter(), &id).await
+    }
+
+    /// Remove a notification from the phone and emit `notificationRemoved`.
+    pub async fn remove_notification(&self, device_id: &str, id: &str) -> zbus::Result<()> {
+        remove_notification(&self.server, device_id, id).await
+    }
+
+    /// Store SMS messages in the daemon's conversation cache without emitting signals.
+    ///
+    /// Seeded messages are returned by `activeConversations` and replayed as
+    /// signals when clients call `requestAllConversationThreads` or
+    /// `requestConversation`.
+    pub async fn add_sms(
+        &self,
+        device_id: &str,
+        messages: impl IntoIterator<Item = MockSms>,
+    ) -> zbus::Result<()> {
+        let iface = self
+            .server
+            .object_server()
+            .interface::<_, ConversationsIface>(device_path(device_id))
+            .await?;
+        iface.get_mut().await.messages.extend(messages);
+        Ok(())
+    }
+
+    /// Deliver a new SMS the way the daemon does when the phone forwards one.
+    ///
+    /// Emits `conversationCreated` for a new thread and `conversationUpdated`
+    /// for an existing one.
+    pub async fn receive_sms(&self, device_id: &str, sms: MockSms) -> zbus::Result<()> {
+        let iface = self
+            .server
+            .object_server()
+            .interface::<_, ConversationsIface>(device_path(device_id))
+            .await?;
+        let mut conversations = iface.get_mut().await;
+        let is_new_thread = !conversations
+            .messages
+            .iter()
+            .any(|m| m.thread_id == sms.thread_id);
+        let value = sms.to_value();
+        conversations.messages.push(sms);
+
+        if is_new_thread {
+            ConversationsIface::conversation_created(iface.signal_emitter(), value).await
+        } else {
+            ConversationsIface::conversation_updated(iface.signal_emitter(), value).await
+        }
+    }
+
+    /// Emit `conversationCreated` without touching the conversation cache.
+    pub async fn emit_conversation_created(
+        &self,
+        device_id: &str,
+        sms: &MockSms,
+    ) -> zbus::Result<()> {
+        let emitter = SignalEmitter::new(&self.server, device_path(device_id))?;
+        ConversationsIface::conversation_created(&emitter, sms.to_value()).await
+    }
+
+    /// Emit `conversationUpdated` without touching the conversation cache.
+    pub async fn emit_conversation_updated(
+        &self,
+        device_id: &str,
+        sms: &MockSms,
+    ) -> zbus::Result<()> {
+        let emitter = SignalEmitter::new(&self.server, device_path(device_id))?;
+        ConversationsIface::conversation_updated(&emitter, sms.to_value()).await
+    }
+
+    /// Emit `conversationLoaded` for a thread.
+    pub async fn emit_conversation_loaded(
+        &self,
+        device_id: &str,
+        thread_id: i64,
+        message_count: u64,
+    ) -> zbus::Result<()> {
+        let emitter = SignalEmitter::new(&self.server, device_path(device_id))?;
+        ConversationsIface::conversation_loaded(&emitter, thread_id, message_count).await
+    }
+
+    /// Emit the telephony plugin's `callReceived` signal.
+    pub async fn emit_call_received(
+        &self,
+        device_id: &str,
+        event: &str,
+        phone_number: &str,
+        contact_name: &str,
+    ) -> zbus::Result<()> {
+        let emitter = SignalEmitter::new(&self.server, plugin_path(device_id, "telephony"))?;
+        TelephonyIface::call_received(&emitter, event, phone_number, contact_name).await
+    }
+
+    /// Emit the share plugin's `shareReceived` signal.
+    pub async fn emit_share_received(&self, device_id: &str, url: &str) -> zbus::Result<()> {
+        let emitter = SignalEmitter::new(&self.server, plugin_path(device_id, "share"))?;
+        ShareIface::share_received(&emitter, url).await
+    }
+}
+
+async fn remove_notification(server: &Connection, device_id: &str, id: &str) -> zbus::Result<()> {
+    let object_server = server.object_server();
+    let base = plugin_path(device_id, "notifications");
+    object_server
+        .remove::<NotificationIface, _>(format!("{}/{}", base, id))
+        .await?;
+
+    let iface = object_server
+        .interface::<_, NotificationsIface>(base.as_str())
+        .await?;
+    iface.get_mut().await.active.retain(|n| n != id);
+    NotificationsIface::notification_removed(iface.signal_emitter(), id).await
+}
+
+/// Minimal `org.freedesktop.DBus` so bus-only calls like `AddMatch` succeed on
+/// the peer-to-peer connection. Every signal is delivered regardless of rules.
+struct BusIface {
+    running: bool,
+    installed: bool,
+    calls: CallLog,
+}
+
+impl BusIface {
+    /// Claim or release the daemon's name, emitting `NameOwnerChanged`.
+    async fn set_running(
+        &mut self,
+        emitter: &SignalEmitter<'_>,
+        running: bool,
+    ) -> zbus::Result<()> {
+        if self.running == running {
+            return Ok(());
+        }
+        self.running = running;
+        let (old_owner, new_owner) = if running {
+            ("", DAEMON_UNIQUE_NAME)
+        } else {
+            (DAEMON_UNIQUE_NAME, "")
+        };
+        Self::name_owner_changed(emitter, SERVICE_NAME, old_owner, new_owner).await
+    }
+}
+
+#[interface(name = "org.freedesktop.DBus")]
+impl BusIface {
+    #[zbus(name = "AddMatch")]
+    fn add_match(&self, _rule: &str) {}
+
+    #[zbus(name = "RemoveMatch")]
+    fn remove_match(&self, _rule: &str) {}
+
+    #[zbus(name = "NameHasOwner")]
+    fn name_has_owner(&self, name: &str) -> bool {
+        name == SERVICE_NAME && self.running
+    }
+
+    #[zbus(name = "ListActivatableNames")]
+    fn list_activatable_names(&self) -> Vec<String> {
+        if self.installed {
+            vec![SERVICE_NAME.to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    #[zbus(name = "StartServiceByName")]
+    async fn start_service_by_name(
+        &mut self,
+        name: &str,
+        _flags: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<u32> {
+        record(
+            &self.calls,
+            "",
+            "bus",
+            "StartServiceByName",
+            vec![name.to_string()],
+        );
+        if name != SERVICE_NAME || !self.installed {
+            return Err(zbus::fdo::Error::ServiceUnknown(name.to_string()));
+        }
+        // DBUS_START_REPLY_SUCCESS or DBUS_START_REPLY_ALREADY_RUNNING
+        let reply = if self.running { 2 } else { 1 };
+        self.set_running(&emitter, true).await?;
+        Ok(reply)
+    }
+
+    #[zbus(signal, name = "NameOwnerChanged")]
+    async fn name_owner_changed(
+        emitter: &SignalEmitter<'_>,
+        name: &str,
+        old_owner: &str,
+        new_owner: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Object path of the fake systemd user manager.
+const SYSTEMD_PATH: &str = "/org/freedesktop/systemd1";
+
+/// Object path the fake systemd hands out for every unit.
+const SYSTEMD_UNIT_PATH: &str = "/org/freedesktop/systemd1/unit/kdeconnect";
+
+struct SystemdIface {
+    calls: CallLog,
+}
+
+#[interface(name = "org.freedesktop.systemd1.Manager")]
+impl SystemdIface {
+    /// Units are loaded whether or not they exist, like systemd does.
+    #[zbus(name = "LoadUnit")]
+    fn load_unit(&self, _name: &str) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(SYSTEMD_UNIT_PATH).expect("valid unit path")
+    }
+
+    /// Starts the daemon if it has a unit.
+    #[zbus(name = "StartUnit")]
+    async fn start_unit(
+        &self,
+        name: &str,
+        _mode: &str,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> zbus::fdo::Result<OwnedObjectPath> {
+        record(
+            &self.calls,
+            "",
+            "systemd",
+            "StartUnit",
+            vec![name.to_string()],
+        );
+        let unit = server.interface::<_, UnitIface>(SYSTEMD_UNIT_PATH).await?;
+        let mut unit = unit.get_mut().await;
+        if unit.active_state.is_none() {
+            return Err(zbus::fdo::Error::Failed(format!("Unit {name} not found.")));
+        }
+        unit.active_state = Some("active".to_string());
+        let bus = server
+            .interface::<_, BusIface>("/org/freedesktop/DBus")
+            .await?;
+        bus.get_mut()
+            .await
+            .set_running(bus.signal_emitter(), true)
+            .await?;
+        Ok(OwnedObjectPath::try_from("/org/freedesktop/systemd1/job/1").expect("valid job path"))
+    }
+}
+
+struct UnitIface {
+    /// `None` when the unit has no unit file.
+    active_state: Option<String>,
+}
+
+#[interface(name = "org.freedesktop.systemd1.Unit")]
+impl UnitIface {
+    #[zbus(property, name = "LoadState")]
+    fn load_state(&self) -> String {
+        match self.active_state {
+            Some(_) => "loaded",
+            None => "not-found",
+        }
+        .to_string()
+    }
+
+    #[zbus(property, name = "ActiveState")]
+    fn active_state(&self) -> String {
+        self.active_state
+            .clone()
+            .unwrap_or_else(|| "inactive".to_string())
+    }
+}
+
+struct DaemonIface {
+    devices: Vec<String>,
+    calls: CallLog,
+}
+
+#[interface(name = "org.kde.kdeconnect.daemon")]
+impl DaemonIface {
+    #[zbus(name = "devices")]
+    fn devices(&self) -> Vec<String> {
+        self.devices.clone()
+    }
+
+    #[zbus(name = "forceOnNetworkChange")]
+    fn force_on_network_change(&self) {
+        record(
+            &self.calls,
+            "",
+            "daemon",
+            "forceOnNetworkChange",
+            Vec::new(),
+        );
+    }
+
+    #[zbus(name = "selfId")]
+    fn self_id(&self) -> String {
+        MOCK_SELF_ID.to_string()
+    }
+
+    #[zbus(signal, name = "deviceAdded")]
+    async fn device_added(emitter: &SignalEmitter<'_>, id: &str) -> zbus::Result<()>;
+
+    #[zbus(signal, name = "deviceRemoved")]
+    async fn device_removed(emitter: &SignalEmitter<'_>, id: &str) -> zbus::Result<()>;
+
+    #[zbus(signal, name = "deviceVisibilityChanged")]
+    async fn device_visibility_changed(
+        emitter: &SignalEmitter<'_>,
+        id: &str,
+        visible: bool,
+    ) -> zbus::Result<()>;
+}
+
+struct DeviceIface {
+    device: MockDevice,
+    calls: CallLog,
+}
+
+impl DeviceIface {
+    /// KDE Connect's `PairState` value for the current flags.
+    fn pair_state(&self) -> i32 {
+        if self.device.is_paired {
+            3
+        } else if self.device.is_pair_requested_by_peer {
+            2
+        } else if self.device.is_pair_requested {
+            1
+        } else {
+            0
+        }
+    }
+
+    async fn emit_pair_state(&self, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        Self::pair_state_changed(emitter, self.pair_state()).await?;
+        self.is_paired_changed(emitter).await?;
+        self.is_pair_requested_changed(emitter).await?;
+        self.is_pair_requested_by_peer_changed(emitter).await
+    }
+
+    fn record(&self, method: &str) {
+        record(&self.calls, &self.device.id, "device", method, Vec::new());
+    }
+}
+
+#[interface(name = "org.kde.kdeconnect.device")]
+impl DeviceIface {
+    #[zbus(property, name = "name")]
+    fn name(&self) -> String {
+        self.device.name.clone()
+    }
+
+    #[zbus(property, name = "type")]
+    fn device_type(&self) -> String {
+        self.device.device_type.clone()
+    }
+
+    #[zbus(property, name = "isReachable")]
+    fn is_reachable(&self) -> bool {
+        self.device.is_reachable
+    }
+
+    #[zbus(property, name = "isPaired")]
+    fn is_trusted(&self) -> bool {
+        self.device.is_paired
+    }
+
+    #[zbus(property, name = "isPairRequested")]
+    fn is_pair_requested(&self) -> bool {
+        self.device.is_pair_requested
+    }
+
+    #[zbus(property, name = "isPairRequestedByPeer")]
+    fn is_pair_requested_by_peer(&self) -> bool {
+        self.device.is_pair_requested_by_peer
+    }
+
+    #[zbus(property, name = "supportedPlugins")]
+    fn supported_plugins(&self) -> Vec<String> {
+        self.device.plugins.clone()
+    }
+
+    #[zbus(name = "requestPairing")]
+    async fn request_pair(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.record("requestPairing");
+        self.device.is_pair_requested = true;
+        Ok(self.emit_pair_state(&emitter).await?)
+    }
+
+    #[zbus(name = "unpair")]
+    async fn unpair(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.record("unpair");
+        self.device.is_paired = false;
+        Ok(self.emit_pair_state(&emitter).await?)
+    }
+
+    #[zbus(name = "acceptPairing")]
+    async fn accept_pairing(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.record("acceptPairing");
+        self.device.is_paired = true;
+        self.device.is_pair_requested = false;
+        self.device.is_pair_requested_by_peer = false;
+        Ok(self.emit_pair_state(&emitter).await?)
+    }
+
+    #[zbus(name = "cancelPairing")]
+    async fn reject_pairing(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        self.record("cancelPairing");
+        self.device.is_pair_requested = false;
+        self.device.is_pair_requested_by_peer = false;
+        Ok(self.emit_pair_state(&emitter).await?)
+    }
+
+    /// Like the daemon, report no plugins loaded while unreachable or unpaired.
+    #[zbus(name = "hasPlugin")]
+    fn has_plugin(&self, plugin: &str) -> bool {
+        self.device.is_reachable && self.device.is_paired && self.device.has_plugin(plugin)
+    }
+
+    #[zbus(signal, name = "reachableChanged")]
+    async fn reachable_changed(emitter: &SignalEmitter<'_>, reachable: bool) -> zbus::Result<()>;
+
+    #[zbus(signal, name = "pairStateChanged")]
+    async fn pair_state_changed(emitter: &SignalEmitter<'_>, pair_state: i32) -> zbus::Result<()>;
+}
+
+struct BatteryIface {
+    charge: i32,
+    is_charging: bool,
+}
+
+#[interface(name = "org.kde.kdeconnect.device.battery")]
+impl BatteryIface {
+    #[zbus(property, name = "charge")]
+    fn charge(&self) -> i32 {
+        self.charge
+    }
+
+    #[zbus(property, name = "isCharging")]
+    fn is_charging(&self) -> bool {
+        self.is_charging
+    }
+}
+
+struct PingIface {
+    device_id: String,
+    calls: CallLog,
+}
+
+#[interface(name = "org.kde.kdeconnect.device.ping")]
+impl PingIface {
+    /// Only the argument-less overload is served; zbus interfaces cannot
+    /// overload a member by signature.
+    #[zbus(name = "sendPing")]
+    fn send_ping(&self) {
+        record(&self.calls, &self.device_id, "ping", "sendPing", Vec::new());
+    }
+}
+
+struct FindMyPhoneIface {
+    device_id: String,
+    calls: CallLog,
+}
+
+#[interface(name = "org.kde.kdeconnect.device.findmyphone")]
+impl FindMyPhoneIface {
+    #[zbus(name = "ring")]
+    fn ring(&self) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "findmyphone",
+            "ring",
+            Vec::new(),
+        );
+    }
+}
+
+struct ClipboardIface {
+    device_id: String,
+    calls: CallLog,
+}
+
+#[interface(name = "org.kde.kdeconnect.device.clipboard")]
+impl ClipboardIface {
+    #[zbus(name = "sendClipboard")]
+    fn send_clipboard(&self) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "clipboard",
+            "sendClipboard",
+            Vec::new(),
+        );
+    }
+
+    #[zbus(property, name = "isAutoShareDisabled")]
+    fn is_auto_share_disabled(&self) -> bool {
+        false
+    }
+}
+
+struct ShareIface {
+    device_id: String,
+    calls: CallLog,
+}
+
+#[interface(name = "org.kde.kdeconnect.device.share")]
+impl ShareIface {
+    #[zbus(name = "shareUrl")]
+    fn share_url(&self, url: &str) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "share",
+            "shareUrl",
+            vec![url.to_string()],
+        );
+    }
+
+    #[zbus(name = "shareText")]
+    fn share_text(&self, text: &str) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "share",
+            "shareText",
+            vec![text.to_string()],
+        );
+    }
+
+    #[zbus(signal, name = "shareReceived")]
+    async fn share_received(emitter: &SignalEmitter<'_>, url: &str) -> zbus::Result<()>;
+}
+
+struct TelephonyIface;
+
+#[interface(name = "org.kde.kdeconnect.device.telephony")]
+impl TelephonyIface {
+    #[zbus(signal, name = "callReceived")]
+    async fn call_received(
+        emitter: &SignalEmitter<'_>,
+        event: &str,
+        phone_number: &str,
+        contact_name: &str,
+    ) -> zbus::Result<()>;
+}
+
+struct MprisRemoteIface {
+    device_id: String,
+    media: MockMedia,
+    calls: CallLog,
+}
+
+impl MprisRemoteIface {
+    async fn emit_all_changed(&self, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        self.player_list_changed(emitter).await?;
+        self.player_changed(emitter).await?;
+        self.is_playing_changed(emitter).await?;
+        self.volume_changed(emitter).await?;
+        self.length_changed(emitter).await?;
+        self.position_changed(emitter).await?;
+        self.title_changed(emitter).await?;
+        self.artist_changed(emitter).await?;
+        self.album_changed(emitter).await?;
+        self.can_seek_changed(emitter).await?;
+        self.can_go_next_changed(emitter).await?;
+        self.can_go_previous_changed(emitter).await?;
+        self.can_play_changed(emitter).await?;
+        self.can_pause_changed(emitter).await?;
+        self.loop_status_changed(emitter).await?;
+        self.shuffle_changed(emitter).await?;
+        self.album_art_url_changed(emitter).await
+    }
+}
+
+#[interface(name = "org.kde.kdeconnect.device.mprisremote")]
+impl MprisRemoteIface {
+    #[zbus(property, name = "playerList")]
+    fn player_list(&self) -> Vec<String> {
+        self.media.player_list.clone()
+    }
+
+    #[zbus(property, name = "player")]
+    fn player(&self) -> String {
+        self.media.player.clone()
+    }
+
+    #[zbus(property, name = "player")]
+    fn set_player(&mut self, player: String) {
+        self.media.player = player;
+    }
+
+    #[zbus(property, name = "isPlaying")]
+    fn is_playing(&self) -> bool {
+        self.media.is_playing
+    }
+
+    #[zbus(property, name = "volume")]
+    fn volume(&self) -> i32 {
+        self.media.volume
+    }
+
+    #[zbus(property, name = "volume")]
+    fn set_volume(&mut self, volume: i32) {
+        self.media.volume = volume;
+    }
+
+    #[zbus(property, name = "length")]
+    fn length(&self) -> i32 {
+        self.media.length
+    }
+
+    #[zbus(property, name = "position")]
+    fn position(&self) -> i32 {
+        self.media.position
+    }
+
+    #[zbus(property, name = "position")]
+    fn set_position(&mut self, position: i32) {
+        self.media.position = position;
+    }
+
+    #[zbus(property, name = "title")]
+    fn title(&self) -> String {
+        self.media.title.clone()
+    }
+
+    #[zbus(property, name = "artist")]
+    fn artist(&self) -> String {
+        self.media.artist.clone()
+    }
+
+    #[zbus(property, name = "album")]
+    fn album(&self) -> String {
+        self.media.album.clone()
+    }
+
+    #[zbus(property, name = "canSeek")]
+    fn can_seek(&self) -> bool {
+        self.media.can_seek
+    }
+
+    #[zbus(property, name = "canGoNext")]
+    fn can_go_next(&self) -> bool {
+        self.media.can_go_next
+    }
+
+    #[zbus(property, name = "canGoPrevious")]
+    fn can_go_previous(&self) -> bool {
+        self.media.can_go_previous
+    }
+
+    #[zbus(property, name = "canPlay")]
+    fn can_play(&self) -> bool {
+        self.media.can_play
+    }
+
+    #[zbus(property, name = "canPause")]
+    fn can_pause(&self) -> bool {
+        self.media.can_pause
+    }
+
+    #[zbus(property, name = "loopStatus")]
+    fn loop_status(&self) -> String {
+        self.media.loop_status.clone()
+    }
+
+    #[zbus(property, name = "loopStatus")]
+    fn set_loop_status(&mut self, loop_status: String) {
+        self.media.loop_status = loop_status;
+    }
+
+    #[zbus(property, name = "shuffle")]
+    fn shuffle(&self) -> bool {
+        self.media.shuffle
+    }
+
+    #[zbus(property, name = "shuffle")]
+    fn set_shuffle(&mut self, shuffle: bool) {
+        self.media.shuffle = shuffle;
+    }
+
+    #[zbus(property, name = "albumArtUrl")]
+    fn album_art_url(&self) -> String {
+        self.media.album_art_url.clone()
+    }
+
+    #[zbus(name = "seek")]
+    async fn seek(
+        &mut self,
+        offset: i32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        record(
+            &self.calls,
+            &self.device_id,
+            "mprisremote",
+            "seek",
+            vec![offset.to_string()],
+        );
+        self.media.position = (self.media.position + offset).clamp(0, self.media.length);
+        Ok(self.position_changed(&emitter).await?)
+    }
+
+    #[zbus(name = "requestPlayerList")]
+    fn request_player_list(&self) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "mprisremote",
+            "requestPlayerList",
+            Vec::new(),
+        );
+    }
+
+    #[zbus(name = "sendAction")]
+    async fn send_action(
+        &mut self,
+        action: &str,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        record(
+            &self.calls,
+            &self.device_id,
+            "mprisremote",
+            "sendAction",
+            vec![action.to_string()],
+        );
+        let was_playing = self.media.is_playing;
+        match action {
+            "Play" => self.media.is_playing = true,
+            "Pause" | "Stop" => self.media.is_playing = false,
+            "PlayPause" => self.media.is_playing = !self.media.is_playing,
+            _ => {}
+        }
+        if was_playing != self.media.is_playing {
+            self.is_playing_changed(&emitter).await?;
+        }
+        Ok(())
+    }
+}
+
+struct NotificationsIface {
+    device_id: String,
+    active: Vec<String>,
+    calls: CallLog,
+}
+
+#[interface(name = "org.kde.kdeconnect.device.notifications")]
+impl NotificationsIface {
+    #[zbus(name = "activeNotifications")]
+    fn active_notifications(&self) -> Vec<String> {
+        self.active.clone()
+    }
+
+    #[zbus(name = "sendReply")]
+    fn send_reply(&self, reply_id: &str, message: &str) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "notifications",
+            "sendReply",
+            vec![reply_id.to_string(), message.to_string()],
+        );
+    }
+
+    #[zbus(signal, name = "notificationPosted")]
+    async fn notification_posted(emitter: &SignalEmitter<'_>, public_id: &str) -> zbus::Result<()>;
+
+    #[zbus(signal, name = "notificationRemoved")]
+    async fn notification_removed(emitter: &SignalEmitter<'_>, public_id: &str)
+        -> zbus::Result<()>;
+
+    #[zbus(signal, name = "notificationUpdated")]
+    async fn notification_updated(emitter: &SignalEmitter<'_>, public_id: &str)
+        -> zbus::Result<()>;
+
+    #[zbus(signal, name = "allNotificationsRemoved")]
+    async fn all_notifications_removed(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+struct NotificationIface {
+    device_id: String,
+    notification: MockNotification,
+    calls: CallLog,
+}
+
+#[interface(name = "org.kde.kdeconnect.device.notifications.notification")]
+impl NotificationIface {
+    #[zbus(property, name = "internalId")]
+    fn internal_id(&self) -> String {
+        self.notification.id.clone()
+    }
+
+    #[zbus(property, name = "appName")]
+    fn app_name(&self) -> String {
+        self.notification.app_name.clone()
+    }
+
+    #[zbus(property, name = "ticker")]
+    fn ticker(&self) -> String {
+        self.notification.ticker.clone()
+    }
+
+    #[zbus(property, name = "title")]
+    fn title(&self) -> String {
+        self.notification.title.clone()
+    }
+
+    #[zbus(property, name = "text")]
+    fn text(&self) -> String {
+        self.notification.text.clone()
+    }
+
+    #[zbus(property, name = "iconPath")]
+    fn icon_path(&self) -> String {
+        self.notification.icon_path.clone()
+    }
+
+    #[zbus(property, name = "dismissable")]
+    fn dismissable(&self) -> bool {
+        self.notification.dismissable
+    }
+
+    #[zbus(property, name = "hasIcon")]
+    fn has_icon(&self) -> bool {
+        self.notification.has_icon
+    }
+
+    #[zbus(property, name = "silent")]
+    fn silent(&self) -> bool {
+        self.notification.silent
+    }
+
+    #[zbus(property, name = "replyId")]
+    fn reply_id(&self) -> String {
+        self.notification.reply_id.clone()
+    }
+
+    /// Removal happens on a separate task: the object server is locked while
+    /// this call is being dispatched.
+    #[zbus(name = "dismiss")]
+    fn dismiss(&self, #[zbus(connection)] conn: &Connection) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "notification",
+            "dismiss",
+            vec![self.notification.id.clone()],
+        );
+        let conn = conn.clone();
+        let device_id = self.device_id.clone();
+        let id = self.notification.id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = remove_notification(&conn, &device_id, &id).await {
+                tracing::warn!(
+                    "Mock: failed to remove dismissed notification {}: {}",
+                    id,
+                    e
+                );
+            }
+        });
+    }
+
+    #[zbus(name = "sendReply")]
+    fn send_reply(&self, message: &str) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "notification",
+            "sendReply",
+            vec![self.notification.id.clone(), message.to_string()],
+        );
+    }
+}
+
+struct SmsIface {
+    device_id: String,
+    calls: CallLog,
+}
+
+#[interface(name = "org.kde.kdeconnect.device.sms")]
+impl SmsIface {
+    #[zbus(name = "requestAllConversations")]
+    fn request_all_conversations(&self) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "sms",
+            "requestAllConversations",
+            Vec::new(),
+        );
+    }
+
+    #[zbus(name = "requestConversation")]
+    fn request_conversation(&self, thread_id: i64, start_timestamp: i64, count: i64) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "sms",
+            "requestConversation",
+            vec![
+                thread_id.to_string(),
+                start_timestamp.to_string(),
+                count.to_string(),
+            ],
+        );
+    }
+
+    /// Recorded args: the comma-joined addresses, the text, the attachment
+    /// count and the subscription ID.
+    #[zbus(name = "sendSms")]
+    fn send_sms(
+        &self,
+        addresses: Vec<OwnedValue>,
+        text_message: &str,
+        attachment_urls: Vec<OwnedValue>,
+        sub_id: i64,
+    ) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "sms",
+            "sendSms",
+            vec![
+                addresses_to_strings(&addresses).join(","),
+                text_message.to_string(),
+                attachment_urls.len().to_string(),
+                sub_id.to_string(),
+            ],
+        );
+    }
+}
+
+struct ConversationsIface {
+    device_id: String,
+    messages: Vec<MockSms>,
+    calls: CallLog,
+}
+
+impl ConversationsIface {
+    /// Messages grouped by thread, newest first within each thread.
+    fn threads(&self) -> BTreeMap<i64, Vec<&MockSms>> {
+        let mut threads: BTreeMap<i64, Vec<&MockSms>> = BTreeMap::new();
+        for msg in &self.messages {
+            threads.entry(msg.thread_id).or_default().push(msg);
+        }
+        for msgs in threads.values_mut() {
+            msgs.sort_by_key(|m| std::cmp::Reverse(m.date));
+        }
+        threads
+    }
+}
+
+#[interface(name = "org.kde.kdeconnect.device.conversations")]
+impl ConversationsIface {
+    /// Like the daemon, returns the newest message of each thread.
+    #[zbus(name = "activeConversations")]
+    fn active_conversations(&self) -> Vec<OwnedValue> {
+        self.threads()
+            .values()
+            .filter_map(|msgs| msgs.first().map(|m| m.to_value()))
+            .collect()
+    }
+
+    /// Replays the newest message of each thread as `conversationCreated`,
+    /// followed by `conversationLoaded` per thread.
+    #[zbus(name = "requestAllConversationThreads")]
+    async fn request_all_conversation_threads(
+        &self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        record(
+            &self.calls,
+            &self.device_id,
+            "conversations",
+            "requestAllConversationThreads",
+            Vec::new(),
+        );
+        let threads = self.threads();
+        for msgs in threads.values() {
+            if let Some(latest) = msgs.first() {
+                Self::conversation_created(&emitter, latest.to_value()).await?;
+            }
+        }
+        for (thread_id, msgs) in &threads {
+            Self::conversation_loaded(&emitter, *thread_id, msgs.len() as u64).await?;
+        }
+        Ok(())
+    }
+
+    /// Replays messages `start..end` (counted from the newest) as
+    /// `conversationUpdated`, then emits `conversationLoaded` with the
+    /// thread's total message count.
+    #[zbus(name = "requestConversation")]
+    async fn request_conversation(
+        &self,
+        conversation_id: i64,
+        start: i32,
+        end: i32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        record(
+            &self.calls,
+            &self.device_id,
+            "conversations",
+            "requestConversation",
+            vec![
+                conversation_id.to_string(),
+                start.to_string(),
+                end.to_string(),
+            ],
+        );
+        let threads = self.threads();
+        let msgs = threads
+            .get(&conversation_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let start = (start.max(0) as usize).min(msgs.len());
+        let end = (end.max(0) as usize).clamp(start, msgs.len());
+        for msg in &msgs[start..end] {
+            Self::conversation_updated(&emitter, msg.to_value()).await?;
+        }
+        Ok(Self::conversation_loaded(&emitter, conversation_id, msgs.len() as u64).await?)
+    }
+
+    #[zbus(name = "replyToConversation")]
+    fn reply_to_conversation(
+        &self,
+        conversation_id: i64,
+        message: &str,
+        attachment_urls: Vec<OwnedValue>,
+    ) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "conversations",
+            "replyToConversation",
+            vec![
+                conversation_id.to_string(),
+                message.to_string(),
+                attachment_urls.len().to_string(),
+            ],
+        );
+    }
+
+    #[zbus(name = "sendWithoutConversation")]
+    fn send_without_conversation(
+        &self,
+        addresses: Vec<OwnedValue>,
+        message: &str,
+        attachment_urls: Vec<OwnedValue>,
+    ) {
+        record(
+            &self.calls,
+            &self.device_id,
+            "conversations",
+            "sendWithoutConversation",
+            vec![
+                addresses_to_strings(&addresses).join(","),
+                message.to_string(),
+                attachment_urls.len().to_string(),
+            ],
+        );
+    }
+
+    /// Pretends the file was downloaded to the temp directory and emits
+    /// `attachmentReceived`.
+    #[zbus(name = "requestAttachmentFile")]
+    async fn request_attachment_file(
+        &self,
+        part_id: i64,
+        unique_identifier: &str,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        record(
+            &self.calls,
+            &self.device_id,
+            "conversations",
+            "requestAttachmentFile",
+            vec![part_id.to_string(), unique_identifier.to_string()],
+        );
+        let path = std::env::temp_dir().join(unique_identifier);
+        Ok(Self::attachment_received(&emitter, &path.to_string_lossy(), unique_identifier).await?)
+    }
+
+    #[zbus(signal, name = "attachmentReceived")]
+    async fn attachment_received(
+        emitter: &SignalEmitter<'_>,
+        file_path: &str,
+        file_name: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal, name = "conversationCreated")]
+    async fn conversation_created(emitter: &SignalEmitter<'_>, msg: OwnedValue)
+        -> zbus::Result<()>;
+
+    #[zbus(signal, name = "conversationUpdated")]
+    async fn conversation_updated(emitter: &SignalEmitter<'_>, msg: OwnedValue)
+        -> zbus::Result<()>;
+
+    #[zbus(signal, name = "conversationLoaded")]
+    async fn conversation_loaded(
+        emitter: &SignalEmitter<'_>,
+        conversation_id: i64,
+        message_count: u64,
+    ) -> zbus::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::{
+        parse_conversations, parse_sms_message, BatteryProxy, ConversationsProxy, MessageType,
+        MprisRemoteProxy, NotificationProxy, NotificationsProxy, PingProxy, ShareProxy,
+        TelephonyProxy,
+    };
+    use crate::{DaemonProxy, DeviceProxy};
+    use futures_util::{Stream, StreamExt};
+    use std::time::Duration;
+
+    const DEVICE: &str = "abc123";
+
+    async fn mock_with_device() -> MockDaemon {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        mock
+    }
+
+    /// Wait for the next stream item, failing the test instead of hanging.
+    async fn next<S: Stream + Unpin>(stream: &mut S) -> S::Item {
+        tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("timed out waiting for signal")
+            .expect("signal stream ended")
+    }
+
+    #[tokio::test]
+    async fn daemon_lists_devices() {
+        let mock = mock_with_device().await;
+        let daemon = DaemonProxy::new(mock.connection()).await.unwrap();
+
+        assert_eq!(daemon.devices().await.unwrap(), vec![DEVICE.to_string()]);
+        assert_eq!(daemon.self_id().await.unwrap(), MOCK_SELF_ID);
+        assert!(DaemonProxy::is_running(mock.connection()).await);
+    }
+
+    #[tokio::test]
+    async fn add_and_remove_device_emit_signals() {
+        let mock = MockDaemon::start().await.unwrap();
+        let daemon = DaemonProxy::new(mock.connection()).await.unwrap();
+        let mut added = daemon.receive_device_added().await.unwrap();
+        let mut removed = daemon.receive_device_removed().await.unwrap();
+
+        mock.add_device(MockDevice::new(DEVICE, "Pixel"))
+            .await
+            .unwrap();
+        assert_eq!(next(&mut added).await.args().unwrap().id, DEVICE);
+
+        mock.remove_device(DEVICE).await.unwrap();
+        assert_eq!(next(&mut removed).await.args().unwrap().id, DEVICE);
+        assert!(daemon.devices().await.unwrap().is_empty());
+        assert!(DeviceProxy::for_device(mock.connection(), DEVICE)
+            .await
+            .unwrap()
+            .name()
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn device_properties() {
+        let mock = mock_with_device().await;
+        let device = DeviceProxy::for_device(mock.connection(), DEVICE)
+            .await
+            .unwrap();
+
+        assert_eq!(device.name().await.unwrap(), "Pixel");
+        assert_eq!(device.device_type().await.unwrap(), "phone");
+        assert!(device.is_reachable().await.unwrap());
+        assert!(device.is_trusted().await.unwrap());
+        assert!(device.has_plugin("kdeconnect_sms").await.unwrap());
+        assert!(!device.has_plugin("kdeconnect_sftp").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn reachability_change_is_signalled() {
+        let mock = mock_with_device().await;
+        let device = DeviceProxy::for_device(mock.connection(), DEVICE)
+            .await
+            .unwrap();
+        let mut changes = device.receive_reachable_changed().await.unwrap();
+
+        mock.set_reachable(DEVICE, false).await.unwrap();
+
+        assert!(!next(&mut changes).await.args().unwrap().reachable);
+        assert!(!device.is_reachable().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn pairing_request_from_peer_can_be_accepted() {
+        let mock = mock_with_device().await;
+        let device = DeviceProxy::for_device(mock.connection(), DEVICE)
+            .await
+            .unwrap();
+        let mut pair_state = device.receive_pair_state_changed().await.unwrap();
+
+        mock.request_pairing_from_peer(DEVICE).await.unwrap();
+        assert_eq!(next(&mut pair_state).await.args().unwrap().pair_state, 2);
+
+        device.accept_pairing().await.unwrap();
+        assert_eq!(next(&mut pair_state).await.args().unwrap().pair_state, 3);
+        assert!(device.is_trusted().await.unwrap());
+        assert!(!device.is_pair_requested_by_peer().await.unwrap());
+        assert_eq!(mock.calls_to("device")[0].method, "acceptPairing");
+    }
+
+    #[tokio::test]
+    async fn plugins_are_only_served_when_enabled() {
+        let mock = MockDaemon::start().await.unwrap();
+        mock.add_device(MockDevice {
+            plugins: vec!["kdeconnect_ping".to_string()],
+            ..MockDevice::new(DEVICE, "Pixel")
+        })
+        .await
+        .unwrap();
+
+        let battery = BatteryProxy::builder(mock.connection())
+            .path(plugin_path(DEVICE, "battery"))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(battery.charge().await.is_err());
+
+        mock.remove_device(DEVICE).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn battery_updates() {
+        let mock = mock_with_device().await;
+        mock.set_battery(DEVICE, 42, true).await.unwrap();
+
+        let battery = BatteryProxy::builder(mock.connection())
+            .path(plugin_path(DEVICE, "battery"))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(battery.charge().await.unwrap(), 42);
+        assert!(battery.is_charging().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn plugin_calls_are_recorded() {
+        let mock = mock_with_device().await;
+        let ping = PingProxy::builder(mock.connection())
+            .path(plugin_path(DEVICE, "ping"))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let share = ShareProxy::builder(mock.connection())
+            .path(plugin_path(DEVICE, "share"))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        ping.send_ping().await.unwrap();
+        share.share_text("hello").await.unwrap();
+
+        assert_eq!(
+            mock.calls(),
+            vec![
+                MockCall {
+                    device_id: DEVICE.to_string(),
+                    plugin: "ping".to_string(),
+                    method: "sendPing".to_string(),
+                    args: Vec::new(),
+                },
+                MockCall {
+                    device_id: DEVICE.to_string(),
+                    plugin: "share".to_string(),
+                    method: "shareText".to_string(),
+                    args: vec!["hello".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn share_and_call_signals() {
+        let mock = mock_with_device().await;
+        let share = ShareProxy::builder(mock.connection())
+            .path(plugin_path(DEVICE, "share"))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let telephony = TelephonyProxy::builder(mock.connection())
+            .path(plugin_path(DEVICE, "telephony"))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let mut shares = share.receive_share_received().await.unwrap();
+        let mut calls = telephony.receive_call_received().await.unwrap();
+
+        mock.emit_share_received(DEVICE, "file:///tmp/photo.jpg")
+            .await
+            .unwrap();
+        mock.emit_call_received(DEVICE, "ringing", "+15551234567", "Alice")
+            .await
+            .unwrap();
+
+        let share_signal = next(&mut shares).await;
+        assert_eq!(share_signal.args().unwrap().url, "file:///tmp/photo.jpg");
+        let call_signal = next(&mut calls).await;
+        let call = call_signal.args().unwrap();
+        assert_eq!(call.event, "ringing");
+        assert_eq!(call.phone_number, "+15551234567");
+        assert_eq!(call.contact_name, "Alice");
+    }
+
+    #[tokio::test]
+    async fn media_actions_update_state() {
+        let mock = mock_with_device().await;
+        mock.set_media(
+            DEVICE,
+            MockMedia {
+                player_list: vec!["Spotify".to_string()],
+                player: "Spotify".to_string(),
+                title: "Song".to_string(),
+                volume: 50,
+                length: 200_000,
+                can_seek: true,
+                album_art_url: "file:///tmp/albumart/1a2b".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let mpris = MprisRemoteProxy::builder(mock.connection())
+            .path(plugin_path(DEVICE, "mprisremote"))
+            .unwrap()
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(mpris.title().await.unwrap(), "Song");
+        assert_eq!(
+            mpris.album_art_url().await.unwrap(),
+            "file:///tmp/albumart/1a2b"
+        );
+        assert!(!mpris.is_playing().await.unwrap());
+
+        mpris.send_action("PlayPause").await.unwrap();
+        mpris.set_volume(80).await.unwrap();
+        mpris.seek(5_000).await.unwrap();
+        mpris.set_loop_status("Playlist").await.unwrap();
+        mpris.set_shuffle(true).await.unwrap();
+
+        assert!(mpris.is_playing().await.unwrap());
+        let media = mock.media(DEVICE).await.unwrap();
+        assert_eq!(media.volume, 80);
+        assert_eq!(media.position, 5_000);
+        assert_eq!(media.loop_status, "Playlist");
+        assert!(media.shuffle);
+    }
+
+    #[tokio::test]
+    async fn notifications_can_be_listed_and_dismissed() {
+        let mock = mock_with_device().await;
+        let base = plugin_path(DEVICE, "notifications");
+        let notifications = NotificationsProxy::builder(mock.connection())
+            .path(base.as_str())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let mut posted = notifications.receive_notification_posted().await.unwrap();
+        let mut removed = notifications.receive_notification_removed().await.unwrap();
+
+        mock.post_notification(
+            DEVICE,
+            MockNotification::new("n1", "Signal", "Bob", "Lunch?"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(next(&mut posted).await.args().unwrap().public_id, "n1");
+        assert_eq!(
+            notifications.active_notifications().await.unwrap(),
+            vec!["n1".to_string()]
+        );
+
+        let notification = NotificationProxy::builder(mock.connection())
+            .path(format!("{}/n1", base))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(notification.app_name().await.unwrap(), "Signal");
+        assert_eq!(notification.text().await.unwrap(), "Lunch?");
+
+        notification.dismiss().await.unwrap();
+        assert_eq!(next(&mut removed).await.args().unwrap().public_id, "n1");
+        assert!(notifications
+            .active_notifications()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn active_conversations_returns_newest_message_per_thread() {
+        let mock = mock_with_device().await;
+        mock.add_sms(
+            DEVICE,
+            [
+                MockSms::inbox(1, 10, "+15550001", "first", 1_000),
+                MockSms::sent(1, 11, "+15550001", "reply", 2_000),
+                MockSms::inbox(2, 20, "+15550002", "other thread", 1_500),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let conversations = ConversationsProxy::builder(mock.connection())
+            .path(device_path(DEVICE))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let summaries = parse_conversations(conversations.active_conversations().await.unwrap());
+
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].thread_id, 1);
+        assert_eq!(summaries[0].last_message, "reply");
+        assert_eq!(summaries[1].addresses, vec!["+15550002".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn request_conversation_replays_messages_then_loaded() {
+        let mock = mock_with_device().await;
+        mock.add_sms(
+            DEVICE,
+            [
+                MockSms::inbox(1, 10, "+15550001", "oldest", 1_000),
+                MockSms::sent(1, 11, "+15550001", "middle", 2_000),
+                MockSms::inbox(1, 12, "+15550001", "newest", 3_000),
+                MockSms::failed(1, 13, "+15550001", "unsent", 4_000),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let conversations = ConversationsProxy::builder(mock.connection())
+            .path(device_path(DEVICE))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let mut updated = conversations.receive_conversation_updated().await.unwrap();
+        let mut loaded = conversations.receive_conversation_loaded().await.unwrap();
+
+        conversations.request_conversation(1, 0, 3).await.unwrap();
+
+        let failed = parse_sms_message(&next(&mut updated).await.args().unwrap().msg).unwrap();
+        let first = parse_sms_message(&next(&mut updated).await.args().unwrap().msg).unwrap();
+        let second = parse_sms_message(&next(&mut updated).await.args().unwrap().msg).unwrap();
+        assert_eq!(failed.message_type, MessageType::Failed);
+        assert_eq!(first.body, "newest");
+        assert_eq!(first.message_type, MessageType::Inbox);
+        assert_eq!(second.body, "middle");
+        assert_eq!(second.message_type, MessageType::Sent);
+
+        let loaded_signal = next(&mut loaded).await;
+        let args = loaded_signal.args().unwrap();
+        assert_eq!(args.conversation_id, 1);
+        assert_eq!(args.message_count, 4);
+    }
+
+    #[tokio::test]
+    async fn receive_sms_creates_then_updates_thread() {
+        let mock = mock_with_device().await;
+        let conversations = ConversationsProxy::builder(mock.connection())
+            .path(device_path(DEVICE))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let mut created = conversations.receive_conversation_created().await.unwrap();
+        let mut updated = conversations.receive_conversation_updated().await.unwrap();
+
+        mock.receive_sms(DEVICE, MockSms::inbox(7, 1, "+15550007", "hi", 1_000))
+            .await
+            .unwrap();
+        mock.receive_sms(DEVICE, MockSms::inbox(7, 2, "+15550007", "again", 2_000))
+            .await
+            .unwrap();
+
+        let created_msg = parse_sms_message(&next(&mut created).await.args().unwrap().msg).unwrap();
+        let updated_msg = parse_sms_message(&next(&mut updated).await.args().unwrap().msg).unwrap();
+        assert_eq!(created_msg.body, "hi");
+        assert_eq!(updated_msg.body, "again");
+        assert_eq!(updated_msg.thread_id, 7);
+    }
+
+    #[tokio::test]
+    async fn mms_attachments_are_parsed_and_downloadable() {
+        let mock = mock_with_device().await;
+        mock.add_sms(
+            DEVICE,
+            [
+                MockSms::inbox(1, 10, "+15550001", "", 1_000).with_attachment(
+                    4,
+                    "image/jpeg",
+                    "/9j/4A==",
+                    "PART_4_cat.jpg",
+                ),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let conversations = ConversationsProxy::builder(mock.connection())
+            .path(device_path(DEVICE))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let values = conversations.active_conversations().await.unwrap();
+        let message = parse_sms_message(&values[0]).unwrap();
+        let attachment = &message.attachments[0];
+        assert_eq!(attachment.part_id, 4);
+        assert_eq!(attachment.mime_type, "image/jpeg");
+        assert_eq!(attachment.thumbnail, vec![0xff, 0xd8, 0xff, 0xe0]);
+
+        let mut received = conversations.receive_attachment_received().await.unwrap();
+        conversations
+            .request_attachment_file(attachment.part_id, &attachment.unique_identifier)
+            .await
+            .unwrap();
+        let signal = next(&mut received).await;
+        let args = signal.args().unwrap();
+        assert_eq!(args.file_name, "PART_4_cat.jpg");
+        assert!(args.file_path.ends_with("PART_4_cat.jpg"));
+        assert_eq!(
+            mock.calls_to("conversations")[0].args,
+            vec!["4", "PART_4_cat.jpg"]
+        );
+    }
+
+    #[tokio::test]
+    async fn send_without_conversation_is_recorded() {
+        let mock = mock_with_device().await;
+        let conversations = ConversationsProxy::builder(mock.connection())
+            .path(device_path(DEVICE))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let addresses = vec![Value::from(("+15550009".to_string(),))];
+        conversations
+            .send_without_conversation(addresses, "hello", Vec::new())
+            .await
+            .unwrap();
+
+        let calls = mock.calls_to("conversations");
+        assert_eq!(calls[0].method, "sendWithoutConversation");
+        assert_eq!(calls[0].args, vec!["+15550009", "hello", "0"]);
+    }
+
+    #[tokio::test]
+    async fn bus_match_rules_are_accepted() {
+        let mock = MockDaemon::start().await.unwrap();
+        let dbus = zbus::fdo::DBusProxy::new(mock.connection()).await.unwrap();
+        let rule = zbus::MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .sender(crate::SERVICE_NAME)
+            .unwrap()
+            .build();
+
+        dbus.add_match_rule(rule).await.unwrap();
+    }
+}
@@ -14,16 +14,16 @@ pub mod share;
 pub mod sms;
 pub mod telephony;
 
-pub use battery::BatteryProxy;
+pub use battery::{BatteryProxy, BatteryStatus};
 pub use clipboard::ClipboardProxy;
 pub use findmyphone::FindMyPhoneProxy;
-pub use mprisremote::MprisRemoteProxy;
+pub use mprisremote::{MediaChanges, MprisRemoteProxy};
 pub use notifications::{NotificationInfo, NotificationProxy, NotificationsProxy};
 pub use ping::PingProxy;
 pub use share::ShareProxy;
 pub use sms::{
     canonicalize_phone_number, is_address_valid, parse_conversations, parse_messages,
-    parse_sms_message, ConversationSummary, ConversationsProxy, MessageType, SmsMessage, SmsProxy,
-    MAX_CONVERSATIONS,
+    parse_sms_message, Attachment, ConversationSummary, ConversationsProxy, MessageKey,
+    MessageType, SmsMessage, SmsProxy, MAX_CONVERSATIONS,
 };
 pub use telephony::TelephonyProxy;
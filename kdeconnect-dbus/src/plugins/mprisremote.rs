@@ -2,7 +2,9 @@
 //!
 //! Provides control of media players running on the remote device (phone).
 
+use std::collections::HashMap;
 use zbus::proxy;
+use zbus::zvariant::OwnedValue;
 
 /// Proxy for the MPRIS Remote plugin D-Bus interface.
 ///
@@ -57,7 +59,45 @@ pub trait MprisRemote {
     #[zbus(property, name = "canSeek")]
     fn can_seek(&self) -> zbus::Result<bool>;
 
+    /// Check if the player can skip to the next track.
+    #[zbus(property, name = "canGoNext")]
+    fn can_go_next(&self) -> zbus::Result<bool>;
+
+    /// Check if the player can go back to the previous track.
+    #[zbus(property, name = "canGoPrevious")]
+    fn can_go_previous(&self) -> zbus::Result<bool>;
+
+    /// Check if the player can start playback.
+    #[zbus(property, name = "canPlay")]
+    fn can_play(&self) -> zbus::Result<bool>;
+
+    /// Check if the player can pause playback.
+    #[zbus(property, name = "canPause")]
+    fn can_pause(&self) -> zbus::Result<bool>;
+
+    /// Get the loop mode: "None", "Track" or "Playlist".
+    #[zbus(property, name = "loopStatus")]
+    fn loop_status(&self) -> zbus::Result<String>;
+
+    /// Set the loop mode (writable property).
+    #[zbus(property, name = "loopStatus")]
+    fn set_loop_status(&self, loop_status: &str) -> zbus::Result<()>;
+
+    /// Check if the player plays tracks in random order.
+    #[zbus(property, name = "shuffle")]
+    fn shuffle(&self) -> zbus::Result<bool>;
+
+    /// Turn random order on or off (writable property).
+    #[zbus(property, name = "shuffle")]
+    fn set_shuffle(&self, shuffle: bool) -> zbus::Result<()>;
+
+    /// Get the album art of the current track, as a URL of the daemon's
+    /// cached copy; empty when the track has none or it is still downloading.
+    #[zbus(property, name = "albumArtUrl")]
+    fn album_art_url(&self) -> zbus::Result<String>;
+
     /// Seek by a relative offset in milliseconds.
+    #[zbus(name = "seek")]
     fn seek(&self, offset: i32) -> zbus::Result<()>;
 
     /// Set the playback position in milliseconds (writable property).
@@ -82,3 +122,67 @@ pub trait MprisRemote {
     #[zbus(name = "sendAction")]
     fn send_action(&self, action: &str) -> zbus::Result<()>;
 }
+
+/// New values of the player properties carried by a `PropertiesChanged`
+/// signal; properties the signal didn't include are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MediaChanges {
+    pub player_list: Option<Vec<String>>,
+    pub player: Option<String>,
+    pub is_playing: Option<bool>,
+    pub volume: Option<i32>,
+    /// Track length in milliseconds.
+    pub length: Option<i32>,
+    /// Playback position in milliseconds.
+    pub position: Option<i32>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub can_seek: Option<bool>,
+    pub can_go_next: Option<bool>,
+    pub can_go_previous: Option<bool>,
+    pub can_play: Option<bool>,
+    pub can_pause: Option<bool>,
+    pub loop_status: Option<String>,
+    pub shuffle: Option<bool>,
+    pub album_art_url: Option<String>,
+}
+
+impl MediaChanges {
+    /// Read the changed properties of the mprisremote interface.
+    pub fn from_properties(changed: &HashMap<String, OwnedValue>) -> Self {
+        fn get<T: TryFrom<OwnedValue>>(
+            changed: &HashMap<String, OwnedValue>,
+            key: &str,
+        ) -> Option<T> {
+            changed
+                .get(key)
+                .and_then(|v| v.try_clone().ok())
+                .and_then(|v| T::try_from(v).ok())
+        }
+        Self {
+            player_list: get(changed, "playerList"),
+            player: get(changed, "player"),
+            is_playing: get(changed, "isPlaying"),
+            volume: get(changed, "volume"),
+            length: get(changed, "length"),
+            position: get(changed, "position"),
+            title: get(changed, "title"),
+            artist: get(changed, "artist"),
+            album: get(changed, "album"),
+            can_seek: get(changed, "canSeek"),
+            can_go_next: get(changed, "canGoNext"),
+            can_go_previous: get(changed, "canGoPrevious"),
+            can_play: get(changed, "canPlay"),
+            can_pause: get(changed, "canPause"),
+            loop_status: get(changed, "loopStatus"),
+            shuffle: get(changed, "shuffle"),
+            album_art_url: get(changed, "albumArtUrl"),
+        }
+    }
+
+    /// Whether the signal carried none of the player properties.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Provides access to notifications from the remote device.
 
+use std::path::PathBuf;
 use zbus::proxy;
 
 /// Proxy for the notifications plugin D-Bus interface.
@@ -103,8 +104,12 @@ pub struct NotificationInfo {
     pub title: String,
     /// The notification body text.
     pub text: String,
+    /// Local copy of the notification icon, when the phone sent one.
+    pub icon_path: Option<PathBuf>,
     /// Whether the notification can be dismissed.
     pub dismissable: bool,
     /// Whether the notification can be replied to.
     pub repliable: bool,
+    /// Whether the phone showed the notification without sound or vibration.
+    pub silent: bool,
 }
@@ -2,6 +2,7 @@
 //!
 //! Provides access to SMS conversations and messages on the remote device.
 
+use serde::{Deserialize, Serialize};
 use zbus::proxy;
 use zbus::zvariant::{OwnedValue, Value};
 
@@ -85,6 +86,20 @@ pub trait Conversations {
     #[zbus(signal, name = "conversationCreated")]
     fn conversation_created(&self, msg: OwnedValue) -> zbus::Result<()>;
 
+    /// Ask the phone to send the full file of an MMS attachment.
+    /// The daemon emits `attachmentReceived` once the file is downloaded.
+    ///
+    /// # Arguments
+    /// * `part_id` - Part ID of the attachment within the MMS message
+    /// * `unique_identifier` - Unique identifier of the attachment file
+    #[zbus(name = "requestAttachmentFile")]
+    fn request_attachment_file(&self, part_id: i64, unique_identifier: &str) -> zbus::Result<()>;
+
+    /// Signal emitted when a requested attachment has been downloaded.
+    /// `file_path` is the local path of the file, `file_name` its unique identifier.
+    #[zbus(signal, name = "attachmentReceived")]
+    fn attachment_received(&self, file_path: String, file_name: String) -> zbus::Result<()>;
+
     /// Reply to an existing conversation thread with a text message.
     ///
     /// # Arguments
@@ -117,34 +132,54 @@ pub trait Conversations {
     ) -> zbus::Result<()>;
 }
 
-/// Message type indicating direction (sent vs received).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Android message type: the direction of a message and, for outgoing
+/// messages, whether it has actually been sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MessageType {
     /// Message received from the contact (inbox).
     Inbox = 1,
     /// Message sent by the user.
     Sent = 2,
+    /// Unsent draft saved on the phone.
+    Draft = 3,
+    /// Outgoing message the phone is currently sending.
+    Outbox = 4,
+    /// Outgoing message that could not be sent.
+    Failed = 5,
+    /// Outgoing message waiting to be sent, e.g. until the phone has signal.
+    Queued = 6,
+}
+
+impl MessageType {
+    /// Whether the message was written by the user rather than received.
+    pub fn is_outgoing(self) -> bool {
+        self != MessageType::Inbox
+    }
+
+    /// Whether the message is still on its way out.
+    pub fn is_pending(self) -> bool {
+        matches!(self, MessageType::Outbox | MessageType::Queued)
+    }
 }
 
 impl From<i32> for MessageType {
     fn from(value: i32) -> Self {
-        // Android SMS type constants:
-        // 1 = MESSAGE_TYPE_INBOX (received)
-        // 2 = MESSAGE_TYPE_SENT
-        // 3 = MESSAGE_TYPE_DRAFT
-        // 4 = MESSAGE_TYPE_OUTBOX
-        // 5 = MESSAGE_TYPE_FAILED
-        // 6 = MESSAGE_TYPE_QUEUED
-        // Only type 1 is truly a received message; all others are outgoing
+        // Android SMS type constants (Telephony.TextBasedSmsColumns)
         match value {
             1 => MessageType::Inbox,
+            3 => MessageType::Draft,
+            4 => MessageType::Outbox,
+            5 => MessageType::Failed,
+            6 => MessageType::Queued,
+            // 2 = MESSAGE_TYPE_SENT; treat unknown types as sent so they
+            // are never shown as received
             _ => MessageType::Sent,
         }
     }
 }
 
 /// A single SMS message.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SmsMessage {
     /// The message text content.
     pub body: String,
@@ -153,7 +188,7 @@ pub struct SmsMessage {
     pub addresses: Vec<String>,
     /// Unix timestamp in milliseconds.
     pub date: i64,
-    /// Whether this is a sent or received message.
+    /// Direction and delivery state of the message.
     pub message_type: MessageType,
     /// Whether the message has been read.
     pub read: bool,
@@ -165,9 +200,46 @@ pub struct SmsMessage {
     /// SIM subscription ID (-1 for default).
     /// Required for MMS group messages to use the correct SIM.
     pub sub_id: i64,
+    /// MMS attachments (empty for plain SMS).
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+}
+
+/// Identity of a message, for telling fetched copies of it apart from
+/// other messages.
+///
+/// Android numbers messages in its SMS and MMS tables separately, so a `uid`
+/// is only unique together with the table. The thread is part of the key too:
+/// [`SmsMessage::is_mms`] can take a text-only MMS for an SMS, and keeping the
+/// colliding ids to one thread makes that much less likely to merge two
+/// messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MessageKey {
+    pub thread_id: i64,
+    pub uid: i32,
+    pub is_mms: bool,
 }
 
 impl SmsMessage {
+    /// Whether this came from Android's MMS table rather than its SMS table.
+    ///
+    /// The daemon doesn't say which table a message came from, so this is
+    /// inferred: attachments and group threads need MMS. A text-only MMS to a
+    /// single recipient, such as a long text the phone sent as MMS, is taken
+    /// for an SMS.
+    pub fn is_mms(&self) -> bool {
+        !self.attachments.is_empty() || self.addresses.len() > 1
+    }
+
+    /// Key identifying this message; see [`MessageKey`].
+    pub fn key(&self) -> MessageKey {
+        MessageKey {
+            thread_id: self.thread_id,
+            uid: self.uid,
+            is_mms: self.is_mms(),
+        }
+    }
+
     /// Get the primary address (first participant) for display purposes.
     pub fn primary_address(&self) -> &str {
         self.addresses
@@ -177,8 +249,53 @@ impl SmsMessage {
     }
 }
 
+#[cfg(any(test, feature = "mock"))]
+impl SmsMessage {
+    /// An unread SMS from `+15550001`, for tests; change other fields with
+    /// struct update syntax.
+    pub fn test(thread_id: i64, uid: i32, body: &str, date: i64) -> Self {
+        Self {
+            body: body.to_string(),
+            addresses: vec!["+15550001".to_string()],
+            date,
+            message_type: MessageType::Inbox,
+            read: false,
+            thread_id,
+            uid,
+            sub_id: -1,
+            attachments: Vec::new(),
+        }
+    }
+}
+
+/// An attachment of an MMS message.
+///
+/// Only a thumbnail is sent along with the message; the full file has to be
+/// requested with [`ConversationsProxy::request_attachment_file`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// Part ID of the attachment within the MMS message.
+    pub part_id: i64,
+    /// MIME type of the attachment, e.g. `image/jpeg`.
+    pub mime_type: String,
+    /// File name of the attachment, taken from its unique identifier.
+    pub file_name: String,
+    /// Identifier of the attachment file on the phone.
+    pub unique_identifier: String,
+    /// Encoded thumbnail image (empty if the phone sent none).
+    #[serde(with = "base64_bytes")]
+    pub thumbnail: Vec<u8>,
+}
+
+impl Attachment {
+    /// Whether the attachment is an image.
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
 /// Summary of a conversation for the conversation list.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ConversationSummary {
     /// The conversation thread ID.
     pub thread_id: i64,
@@ -234,10 +351,42 @@ fn get_i32_from_value(value: &Value<'_>) -> Option<i32> {
     }
 }
 
+/// Parse an attachment struct `(part_id: x, mime_type: s, thumbnail: s, unique_identifier: s)`.
+///
+/// The thumbnail is sent base64 encoded.
+fn parse_attachment(value: &Value<'_>) -> Option<Attachment> {
+    let fields = match value {
+        Value::Structure(s) => s.fields(),
+        Value::Value(inner) => return parse_attachment(inner),
+        _ => return None,
+    };
+    let part_id = fields.first().and_then(get_i64_from_value)?;
+    let mime_type = fields.get(1).and_then(get_string_from_value)?;
+    let thumbnail = fields
+        .get(2)
+        .and_then(get_string_from_value)
+        .and_then(|encoded| base64_bytes::decode(&encoded))
+        .unwrap_or_default();
+    let unique_identifier = fields.get(3).and_then(get_string_from_value)?;
+    let file_name = unique_identifier
+        .rsplit('/')
+        .next()
+        .unwrap_or(&unique_identifier)
+        .to_string();
+
+    Some(Attachment {
+        part_id,
+        mime_type,
+        file_name,
+        unique_identifier,
+        thumbnail,
+    })
+}
+
 /// Parse a D-Bus variant value into an SmsMessage.
 ///
 /// KDE Connect returns messages as structs with fields in order:
-/// (type: i32, body: s, addresses: a(s), date: i64, read: i32, ?, thread_id: i64, ?, ?, attachments: av)
+/// (type: i32, body: s, addresses: a(s), date: i64, read: i32, ?, thread_id: i64, ?, ?, attachments: a(xsss))
 pub fn parse_sms_message(value: &OwnedValue) -> Option<SmsMessage> {
     // Dereference OwnedValue to get Value
     let value_ref: &Value<'_> = value;
@@ -292,7 +441,7 @@ pub fn parse_sms_message(value: &OwnedValue) -> Option<SmsMessage> {
     // Field 3: date (i64)
     let date = fields.get(3).and_then(get_i64_from_value).unwrap_or(0);
 
-    // Field 4: type (i32) - 1=Inbox (incoming), 2=Sent (outgoing)
+    // Field 4: type (i32) - Android message type, see MessageType
     let msg_type_value = fields.get(4).and_then(get_i32_from_value).unwrap_or(1);
     let msg_type_parsed = MessageType::from(msg_type_value);
 
@@ -311,7 +460,12 @@ pub fn parse_sms_message(value: &OwnedValue) -> Option<SmsMessage> {
 
     // Field 8: subID (i64) - SIM subscription ID (which SIM card to use)
     let sub_id = fields.get(8).and_then(get_i64_from_value).unwrap_or(-1);
-    // Field 9: attachments (array)
+
+    // Field 9: attachments (array of structs)
+    let attachments = match fields.get(9) {
+        Some(Value::Array(arr)) => arr.iter().filter_map(parse_attachment).collect(),
+        _ => Vec::new(),
+    };
 
     Some(SmsMessage {
         body,
@@ -322,6 +476,7 @@ pub fn parse_sms_message(value: &OwnedValue) -> Option<SmsMessage> {
         thread_id,
         uid,
         sub_id,
+        attachments,
     })
 }
 
@@ -416,3 +571,84 @@ pub fn is_address_valid(address: &str) -> bool {
 
     false
 }
+
+/// Base64 coding of thumbnails, both on D-Bus and when serialized.
+mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Decode standard base64, ignoring line breaks. Returns `None` on invalid input.
+    pub fn decode(encoded: &str) -> Option<Vec<u8>> {
+        let encoded: String = encoded.split_ascii_whitespace().collect();
+        STANDARD.decode(encoded).ok()
+    }
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        decode(&encoded).ok_or_else(|| serde::de::Error::custom("invalid base64"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thumbnails_are_base64_encoded() {
+        let attachment = Attachment {
+            part_id: 1,
+            mime_type: "image/png".to_string(),
+            file_name: "photo.png".to_string(),
+            unique_identifier: "PART_1_photo.png".to_string(),
+            thumbnail: b"\xff\x00\x10\x80".to_vec(),
+        };
+        let json = serde_json::to_value(&attachment).unwrap();
+        assert_eq!(json["thumbnail"], "/wAQgA==");
+        assert_eq!(
+            serde_json::from_value::<Attachment>(json).unwrap(),
+            attachment
+        );
+
+        assert_eq!(base64_bytes::decode("Zm9v\nYmFy"), Some(b"foobar".to_vec()));
+        assert_eq!(base64_bytes::decode("Zm9v!"), None);
+    }
+
+    #[test]
+    fn android_message_types_are_kept() {
+        assert_eq!(MessageType::from(1), MessageType::Inbox);
+        assert_eq!(MessageType::from(2), MessageType::Sent);
+        assert_eq!(MessageType::from(5), MessageType::Failed);
+        assert_eq!(MessageType::from(99), MessageType::Sent);
+        assert!(!MessageType::Inbox.is_outgoing());
+        assert!(MessageType::Draft.is_outgoing());
+        assert!(MessageType::Queued.is_pending());
+        assert!(!MessageType::Failed.is_pending());
+    }
+
+    #[test]
+    fn attachments_are_parsed_from_structs() {
+        let value = Value::from((3i64, "image/png", "iVBORw==", "PART_12_photo.png"));
+        let attachment = parse_attachment(&value).unwrap();
+        assert_eq!(attachment.part_id, 3);
+        assert!(attachment.is_image());
+        assert_eq!(attachment.file_name, "PART_12_photo.png");
+        assert_eq!(attachment.thumbnail, vec![0x89, b'P', b'N', b'G']);
+    }
+
+    #[test]
+    fn sms_and_mms_with_the_same_uid_have_different_keys() {
+        let sms = SmsMessage::test(1, 7, "hi", 1_000);
+        let group_mms = SmsMessage {
+            addresses: vec!["+15550001".to_string(), "+15550002".to_string()],
+            ..sms.clone()
+        };
+        assert!(!sms.is_mms());
+        assert!(group_mms.is_mms());
+        assert_ne!(sms.key(), group_mms.key());
+    }
+}